@@ -0,0 +1,221 @@
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+
+use crate::{ApiError, ApiResult};
+use crate::auth::AuthContext;
+use crate::handlers::{self, AppState};
+use crate::object_lock;
+use crate::xml;
+
+/// What the ACLs must grant for an action.
+enum Requirement {
+    /// A permission on the bucket's ACL.
+    Bucket(storage::Permission),
+    /// A permission on the object's ACL.
+    Object(storage::Permission),
+    /// Nothing an ACL can grant; only the bucket owner may.
+    Owner,
+    /// Open to everyone; creating a bucket is checked against existing
+    /// names instead.
+    Anyone,
+}
+
+fn requirement(action: &str) -> Requirement {
+    use storage::Permission::*;
+
+    match action {
+        "s3:ListBucket" | "s3:ListBucketVersions" | "s3:ListBucketMultipartUploads" => Requirement::Bucket(Read),
+        "s3:PutObject" | "s3:DeleteObject" | "s3:DeleteObjectVersion"
+        | "s3:AbortMultipartUpload" | "s3:ListMultipartUploadParts" => Requirement::Bucket(Write),
+        "s3:CreateBucket" => Requirement::Anyone,
+        "s3:GetBucketAcl" => Requirement::Bucket(ReadAcp),
+        "s3:PutBucketAcl" => Requirement::Bucket(WriteAcp),
+        "s3:GetObject" | "s3:GetObjectVersion" => Requirement::Object(Read),
+        "s3:GetObjectAcl" | "s3:GetObjectVersionAcl" => Requirement::Object(ReadAcp),
+        "s3:PutObjectAcl" | "s3:PutObjectVersionAcl" => Requirement::Object(WriteAcp),
+        _ => Requirement::Owner,
+    }
+}
+
+/// ACL of buckets and objects stored before ACLs were recorded, which
+/// everyone could use.
+fn open_acl() -> storage::AccessControlList {
+    storage::AccessControlList {
+        grants: vec![storage::Grant {
+            grantee: storage::Grantee::AllUsers,
+            permission: storage::Permission::FullControl,
+        }],
+    }
+}
+
+/// Checks that the ACLs let `auth` perform `action` on `bucket`, or on `key`
+/// within it. The bucket owner may do anything in the bucket, and an
+/// object's owner anything to the object. Objects without an ACL of their
+/// own, and keys that do not exist, are judged by the bucket's ACL.
+pub(crate) async fn check(
+    state: &AppState,
+    bucket: &storage::Bucket,
+    auth: &AuthContext,
+    action: &str,
+    key: Option<&str>,
+    params: &HashMap<String, String>,
+) -> ApiResult<()> {
+    // Buckets created before owners were recorded belong to everyone
+    if bucket.owner_id.as_deref().is_none_or(|owner| owner == auth.owner_id) {
+        return Ok(());
+    }
+
+    let caller = auth.authenticated.then_some(auth.owner_id.as_str());
+    let bucket_acl = bucket.acl.clone().unwrap_or_else(open_acl);
+
+    let allowed = match (requirement(action), key) {
+        (Requirement::Owner, _) => false,
+        (Requirement::Anyone, _) => true,
+        (Requirement::Bucket(permission), _) | (Requirement::Object(permission), None) => {
+            bucket_acl.allows(caller, permission)
+        }
+        (Requirement::Object(permission), Some(key)) => {
+            let version_id = handlers::version_id_param(params)?;
+            let record = state.storage_engine.get_object_record(&bucket.name, key, version_id).await?;
+            let owns_object = record.as_ref()
+                .and_then(|r| r.metadata.owner_id.as_deref())
+                .is_some_and(|owner| owner == auth.owner_id);
+
+            owns_object || record.and_then(|r| r.metadata.acl)
+                .unwrap_or(bucket_acl)
+                .allows(caller, permission)
+        }
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(ApiError::AccessDenied("Access Denied".to_string()))
+    }
+}
+
+/// Owner and ACL for something new created by `auth`, from `x-amz-acl`.
+/// Without the header the ACL is private.
+pub(crate) fn requested_acl(headers: &HeaderMap, auth: &AuthContext) -> ApiResult<(String, storage::AccessControlList)> {
+    let canned = match headers.get("x-amz-acl").and_then(|h| h.to_str().ok()) {
+        None => storage::CannedAcl::Private,
+        Some(name) => parse_canned(name)?,
+    };
+
+    Ok((auth.owner_id.clone(), storage::AccessControlList::canned(canned, &auth.owner_id)))
+}
+
+fn parse_canned(name: &str) -> ApiResult<storage::CannedAcl> {
+    storage::CannedAcl::parse(name.trim())
+        .ok_or_else(|| ApiError::InvalidArgument(format!("Unsupported canned ACL: {}", name.trim())))
+}
+
+/// The ACL a PutBucketAcl or PutObjectAcl asks for, from either `x-amz-acl`
+/// or an AccessControlPolicy body. The body may not name a different owner.
+fn replacement_acl(owner_id: &str, headers: &HeaderMap, body: &Bytes) -> ApiResult<storage::AccessControlList> {
+    let canned = headers.get("x-amz-acl").and_then(|h| h.to_str().ok());
+
+    match (canned, body.is_empty()) {
+        (Some(name), true) => Ok(storage::AccessControlList::canned(parse_canned(name)?, owner_id)),
+        (Some(_), false) => Err(ApiError::InvalidRequest(
+            "Specify either an x-amz-acl header or an access control policy body, not both".to_string()
+        )),
+        (None, true) => Err(ApiError::XmlError("Missing AccessControlPolicy element".to_string())),
+        (None, false) => {
+            let (named_owner, acl) = xml::parse_access_control_policy(handlers::body_text(body)?)?;
+            if named_owner.is_some_and(|named| named != owner_id) {
+                return Err(ApiError::AccessDenied("The owner of an ACL cannot be changed".to_string()));
+            }
+            Ok(acl)
+        }
+    }
+}
+
+pub async fn get_bucket_acl(state: &AppState, bucket: &str, auth: &AuthContext) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let owner_id = bucket.owner_id.unwrap_or_else(|| auth.owner_id.clone());
+    let acl = bucket.acl.unwrap_or_else(open_acl);
+
+    Ok(object_lock::xml_response(xml::serialize_access_control_policy(&owner_id, &acl)))
+}
+
+pub async fn put_bucket_acl(
+    state: &AppState,
+    bucket: &str,
+    auth: &AuthContext,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let config = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+    let owner_id = config.owner_id.unwrap_or_else(|| auth.owner_id.clone());
+
+    let acl = replacement_acl(&owner_id, headers, &body)?;
+    state.storage_engine.set_bucket_acl(bucket, acl).await?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+pub async fn get_object_acl(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    auth: &AuthContext,
+) -> ApiResult<Response> {
+    let config = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let version_id = handlers::version_id_param(params)?;
+    let record = state.storage_engine.get_object_record(bucket, key, version_id).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+
+    let owner_id = record.metadata.owner_id
+        .or(config.owner_id)
+        .unwrap_or_else(|| auth.owner_id.clone());
+    let acl = record.metadata.acl.or(config.acl).unwrap_or_else(open_acl);
+
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", "application/xml".to_string()),
+            ("x-amz-version-id", storage::format_version_id(&record.metadata.version_id)),
+        ],
+        xml::serialize_access_control_policy(&owner_id, &acl),
+    ).into_response())
+}
+
+pub async fn put_object_acl(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    auth: &AuthContext,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let version_id = handlers::version_id_param(params)?;
+    let record = state.storage_engine.get_object_record(bucket, key, version_id).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+    let owner_id = record.metadata.owner_id.clone().unwrap_or_else(|| auth.owner_id.clone());
+
+    let acl = replacement_acl(&owner_id, headers, &body)?;
+    let record = state.storage_engine
+        .set_object_acl(bucket, key, Some(record.metadata.version_id), acl).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+
+    Ok((
+        StatusCode::OK,
+        [("x-amz-version-id", storage::format_version_id(&record.metadata.version_id))],
+    ).into_response())
+}
@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{ApiError, ApiResult};
+use crate::auth::{self, AuthContext};
+use crate::handlers::AppState;
+use crate::virtual_host;
+
+#[derive(serde::Deserialize)]
+pub struct CreateKeyRequest {
+    owner_id: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PresignQuery {
+    bucket: String,
+    key: String,
+    method: Option<String>,
+    expires: Option<i64>,
+}
+
+fn require_admin(auth: &AuthContext) -> ApiResult<()> {
+    if auth.is_admin {
+        Ok(())
+    } else {
+        Err(ApiError::AccessDenied("Access key management requires a node admin key".to_string()))
+    }
+}
+
+fn key_json(credential: &storage::Credential, include_secret: bool) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "access_key": credential.access_key,
+        "owner_id": credential.owner_id,
+        "enabled": credential.enabled,
+        "created_at": credential.created_at.to_rfc3339(),
+    });
+
+    if include_secret {
+        value["secret_key"] = serde_json::Value::String(credential.secret_key.clone());
+    }
+
+    value
+}
+
+fn map_key_error(access_key: &str, error: storage::StorageError) -> ApiError {
+    match error {
+        storage::StorageError::ObjectNotFound(_) => {
+            ApiError::InvalidRequest(format!("No such access key: {}", access_key))
+        }
+        e => ApiError::Storage(e.to_string()),
+    }
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response {
+    (
+        status,
+        [("content-type", "application/json")],
+        body.to_string(),
+    ).into_response()
+}
+
+pub async fn list_keys(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+) -> ApiResult<Response> {
+    require_admin(&auth)?;
+
+    let credentials = state.storage_engine.list_credentials().await
+        .map_err(|e| ApiError::Storage(e.to_string()))?;
+
+    let keys: Vec<_> = credentials.iter().map(|c| key_json(c, false)).collect();
+
+    Ok(json_response(StatusCode::OK, serde_json::json!({ "keys": keys })))
+}
+
+pub async fn create_key(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthContext>,
+    Json(request): Json<CreateKeyRequest>,
+) -> ApiResult<Response> {
+    require_admin(&auth)?;
+
+    if request.owner_id.trim().is_empty() {
+        return Err(ApiError::InvalidRequest("owner_id must not be empty".to_string()));
+    }
+
+    let credential = state.storage_engine.create_credential(&request.owner_id).await
+        .map_err(|e| ApiError::Storage(e.to_string()))?;
+
+    Ok(json_response(StatusCode::CREATED, key_json(&credential, true)))
+}
+
+pub async fn rotate_key(
+    State(state): State<Arc<AppState>>,
+    Path(access_key): Path<String>,
+    Extension(auth): Extension<AuthContext>,
+) -> ApiResult<Response> {
+    require_admin(&auth)?;
+
+    let credential = state.storage_engine.rotate_credential(&access_key).await
+        .map_err(|e| map_key_error(&access_key, e))?;
+
+    Ok(json_response(StatusCode::OK, key_json(&credential, true)))
+}
+
+pub async fn revoke_key(
+    State(state): State<Arc<AppState>>,
+    Path(access_key): Path<String>,
+    Extension(auth): Extension<AuthContext>,
+) -> ApiResult<Response> {
+    require_admin(&auth)?;
+
+    let credential = state.storage_engine.revoke_credential(&access_key).await
+        .map_err(|e| map_key_error(&access_key, e))?;
+
+    Ok(json_response(StatusCode::OK, key_json(&credential, false)))
+}
+
+/// Returns a presigned URL for `bucket/key`, signed with the caller's own
+/// access key so it grants no more than the caller already has.
+pub async fn presign(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PresignQuery>,
+    Extension(auth): Extension<AuthContext>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    if !auth.authenticated {
+        return Err(ApiError::AccessDenied("Presigning requires a signed request".to_string()));
+    }
+
+    let method = query.method.as_deref().unwrap_or("GET").to_uppercase();
+    if method != "GET" && method != "PUT" {
+        return Err(ApiError::InvalidRequest(format!("Cannot presign method {}", method)));
+    }
+
+    let host = headers.get("host")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| ApiError::InvalidRequest("Missing Host header".to_string()))?;
+
+    let key = auth::lookup_access_key(&state, &auth.access_key).await?;
+    let expires = query.expires.unwrap_or(3600);
+    let encoded_key = auth::uri_encode(&query.key, false);
+
+    // Clients reaching the node through a base domain get a virtual-hosted URL
+    let (host, path) = if virtual_host::is_base_domain(&state.base_domains, &uri, &headers) {
+        (format!("{}.{}", query.bucket, host), format!("/{}", encoded_key))
+    } else {
+        (host.to_string(), format!("/{}/{}", query.bucket, encoded_key))
+    };
+
+    let url = auth::presign_url(&method, &host, &path, &auth.access_key, &key.secret_key, expires)?;
+
+    Ok(json_response(StatusCode::OK, serde_json::json!({
+        "url": url,
+        "method": method,
+        "expires_in": expires,
+    })))
+}
@@ -0,0 +1,155 @@
+use axum::http::HeaderMap;
+use openssl::base64;
+use storage::{ChecksumAlgorithm, ClientChecksum, StagedData, UploadDigests};
+
+use crate::{ApiError, ApiResult};
+use crate::chunked::Trailers;
+
+/// The `x-amz-checksum-*` header carrying a checksum made with `algorithm`.
+pub(crate) fn header_name(algorithm: ChecksumAlgorithm) -> String {
+    format!("x-amz-checksum-{}", algorithm.name().to_ascii_lowercase())
+}
+
+/// What a client says an upload's data hashes to: a `Content-MD5` and at
+/// most one `x-amz-checksum-*` value, both base64 encoded. The checksum may
+/// instead follow an aws-chunked body as a trailing header.
+#[derive(Debug, Default)]
+pub(crate) struct ExpectedChecksums {
+    content_md5: Option<String>,
+    algorithm: Option<ChecksumAlgorithm>,
+    checksum: Option<String>,
+    trailing: bool,
+}
+
+impl ExpectedChecksums {
+    /// Reads `Content-MD5`, `x-amz-checksum-*`, `x-amz-trailer` and
+    /// `x-amz-sdk-checksum-algorithm`. The SDK header alone asks for a
+    /// checksum to be computed and stored without one to check against.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> ApiResult<Self> {
+        let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok()).map(str::trim);
+
+        let content_md5 = header("content-md5")
+            .map(|value| {
+                decode_digest(value, 16)
+                    .ok_or_else(|| ApiError::InvalidDigest("The Content-MD5 you specified was not valid".to_string()))
+            })
+            .transpose()?;
+
+        let mut given = ChecksumAlgorithm::ALL.into_iter()
+            .filter_map(|algorithm| header(&header_name(algorithm)).map(|value| (algorithm, value)));
+        let checksum = given.next();
+        if given.next().is_some() {
+            return Err(ApiError::InvalidRequest(
+                "Expecting a single x-amz-checksum- header. Multiple checksum Types are not allowed.".to_string()
+            ));
+        }
+        let checksum = checksum
+            .map(|(algorithm, value)| {
+                decode_digest(value, algorithm.digest_length())
+                    .map(|value| (algorithm, value))
+                    .ok_or_else(|| ApiError::InvalidRequest(
+                        format!("Value for {} header is invalid.", header_name(algorithm))
+                    ))
+            })
+            .transpose()?;
+
+        let trailing = header("x-amz-trailer")
+            .map(|name| {
+                ChecksumAlgorithm::ALL.into_iter()
+                    .find(|algorithm| header_name(*algorithm).eq_ignore_ascii_case(name))
+                    .ok_or_else(|| ApiError::InvalidRequest(format!("Unsupported trailing header {}", name)))
+            })
+            .transpose()?;
+        if trailing.is_some() && checksum.is_some() {
+            return Err(ApiError::InvalidRequest(
+                "Expecting a single x-amz-checksum- header. Multiple checksum Types are not allowed.".to_string()
+            ));
+        }
+        let given_algorithm = checksum.as_ref().map(|(algorithm, _)| *algorithm).or(trailing);
+
+        let sdk_algorithm = header("x-amz-sdk-checksum-algorithm")
+            .map(|name| {
+                ChecksumAlgorithm::from_name(name).ok_or_else(|| ApiError::InvalidRequest(
+                    "Value for x-amz-sdk-checksum-algorithm header is invalid.".to_string()
+                ))
+            })
+            .transpose()?;
+        if let (Some(sdk_algorithm), Some(algorithm)) = (sdk_algorithm, given_algorithm) {
+            if sdk_algorithm != algorithm {
+                return Err(ApiError::InvalidRequest(
+                    format!("Expecting checksum in {} as specified by x-amz-sdk-checksum-algorithm", header_name(sdk_algorithm))
+                ));
+            }
+        }
+
+        Ok(Self {
+            content_md5,
+            algorithm: given_algorithm.or(sdk_algorithm),
+            checksum: checksum.map(|(_, value)| value),
+            trailing: trailing.is_some(),
+        })
+    }
+
+    /// The digests staging must compute for `verify`.
+    pub(crate) fn digests(&self) -> UploadDigests {
+        UploadDigests { content_md5: self.content_md5.is_some(), checksum: self.algorithm }
+    }
+
+    /// Fails with `BadDigest` when the staged data does not match. A
+    /// trailing checksum is taken from `trailers`, so the body must have
+    /// been read to the end.
+    pub(crate) fn verify(&self, staged: &StagedData, trailers: &Trailers) -> ApiResult<()> {
+        if let Some(expected) = &self.content_md5 {
+            if staged.content_md5() != Some(expected.as_str()) {
+                return Err(ApiError::BadDigest(
+                    "The Content-MD5 you specified did not match what we received.".to_string()
+                ));
+            }
+        }
+
+        let expected = match (self.algorithm, self.trailing) {
+            (Some(algorithm), true) => {
+                let name = header_name(algorithm);
+                let value = trailers.get(&name)
+                    .ok_or_else(|| ApiError::InvalidRequest(format!("The {} trailing header is missing", name)))?;
+                let value = decode_digest(value.trim(), algorithm.digest_length())
+                    .ok_or_else(|| ApiError::InvalidRequest(format!("Value for {} trailing header is invalid.", name)))?;
+                Some(value)
+            }
+            _ => self.checksum.clone(),
+        };
+
+        if let (Some(algorithm), Some(expected)) = (self.algorithm, &expected) {
+            let actual = staged.checksum().client.as_ref().map(|checksum| checksum.value.as_str());
+            if actual != Some(expected.as_str()) {
+                return Err(ApiError::BadDigest(
+                    format!("The {} you specified did not match the calculated checksum.", algorithm.name())
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a base64 digest of `length` bytes, returning it re-encoded so
+/// that it compares equal to the digests staging computes.
+fn decode_digest(value: &str, length: usize) -> Option<String> {
+    let digest = base64::decode_block(value).ok()?;
+    (digest.len() == length).then(|| base64::encode_block(&digest))
+}
+
+/// Whether a GET or HEAD asks for the object's checksum with
+/// `x-amz-checksum-mode: ENABLED`.
+pub(crate) fn checksum_mode_enabled(headers: &HeaderMap) -> bool {
+    headers.get("x-amz-checksum-mode")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|mode| mode.eq_ignore_ascii_case("ENABLED"))
+}
+
+/// The header returning a stored client checksum, if there is one.
+pub(crate) fn checksum_headers(checksum: Option<&ClientChecksum>) -> Vec<(String, String)> {
+    checksum
+        .map(|checksum| (header_name(checksum.algorithm), checksum.value.clone()))
+        .into_iter()
+        .collect()
+}
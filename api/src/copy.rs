@@ -0,0 +1,487 @@
+use axum::{
+    http::{HeaderMap, Method, StatusCode},
+    response::{AppendHeaders, IntoResponse, Response},
+};
+use std::collections::HashMap;
+
+use crate::{ApiError, ApiResult};
+use crate::acl;
+use crate::auth::{self, AuthContext};
+use crate::encryption;
+use crate::handlers::{self, AppState};
+use crate::object_lock;
+use crate::policy;
+use crate::tagging;
+use crate::xml;
+
+/// The object version named by `x-amz-copy-source`.
+struct CopySource {
+    bucket: String,
+    key: String,
+    version_id: Option<storage::Version>,
+}
+
+/// Parses `x-amz-copy-source`: `[/]bucket/key[?versionId=id]`, with the
+/// bucket and key URL-encoded.
+fn parse_copy_source(headers: &HeaderMap) -> ApiResult<CopySource> {
+    let value = headers.get("x-amz-copy-source")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| ApiError::InvalidArgument("Invalid x-amz-copy-source header".to_string()))?;
+
+    let (path, query) = match value.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (value, None),
+    };
+
+    let path = auth::percent_decode(path.trim_start_matches('/'));
+    let (bucket, key) = path.split_once('/')
+        .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+        .ok_or_else(|| ApiError::InvalidArgument(
+            "Copy Source must mention the source bucket and key: sourcebucket/sourcekey".to_string()
+        ))?;
+
+    let version_id = match query.and_then(|q| q.strip_prefix("versionId=")) {
+        Some(version_id) => Some(storage::parse_version_id(version_id)
+            .ok_or_else(|| ApiError::InvalidArgument("Invalid version id specified".to_string()))?),
+        None => None,
+    };
+
+    Ok(CopySource {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        version_id,
+    })
+}
+
+/// Looks up the copy source and applies the `x-amz-copy-source-if-*`
+/// conditions. Unlike GET, a "not modified" outcome fails the copy. The
+/// caller must also be allowed to read the source.
+async fn resolve_copy_source(
+    state: &AppState,
+    headers: &HeaderMap,
+    auth: &AuthContext,
+) -> ApiResult<(CopySource, storage::ObjectRecord)> {
+    let source = parse_copy_source(headers)?;
+
+    let params: HashMap<String, String> = source.version_id.iter()
+        .map(|v| ("versionId".to_string(), storage::format_version_id(v)))
+        .collect();
+    let action = policy::object_action(&Method::GET, &params);
+    policy::authorize(state, auth, action, &source.bucket, Some(&source.key), &params).await?;
+
+    let record = state.storage_engine
+        .get_object_record(&source.bucket, &source.key, source.version_id).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", source.bucket, source.key)))?;
+
+    if handlers::evaluate_preconditions(headers, "x-amz-copy-source-", &record.metadata.etag, record.metadata.created_at)? {
+        return Err(ApiError::PreconditionFailed("Copy source was not modified".to_string()));
+    }
+
+    Ok((source, record))
+}
+
+pub async fn copy_object(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    headers: &HeaderMap,
+    auth: &AuthContext,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let (source, record) = resolve_copy_source(state, headers, auth).await?;
+
+    let replace = match headers.get("x-amz-metadata-directive").and_then(|h| h.to_str().ok()) {
+        None | Some("COPY") => false,
+        Some("REPLACE") => true,
+        Some(other) => {
+            return Err(ApiError::InvalidArgument(format!("Unknown metadata directive: {}", other)));
+        }
+    };
+
+    if !replace && source.bucket == bucket && source.key == key {
+        return Err(ApiError::InvalidRequest(
+            "This copy request is illegal because it is trying to copy an object to itself \
+             without changing the object's metadata".to_string()
+        ));
+    }
+
+    let (content_type, custom_metadata) = if replace {
+        let content_type = headers.get("content-type")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+        (content_type, handlers::custom_metadata(headers))
+    } else {
+        (Some(record.metadata.content_type.clone()), record.metadata.custom_metadata.clone())
+    };
+
+    let tags = match headers.get("x-amz-tagging-directive").and_then(|h| h.to_str().ok()) {
+        None | Some("COPY") => record.metadata.tags.clone(),
+        Some("REPLACE") => tagging::requested_tags(headers)?,
+        Some(other) => {
+            return Err(ApiError::InvalidArgument(format!("Unknown tagging directive: {}", other)));
+        }
+    };
+
+    // Like its lock, the copy's owner and ACL never come from the source
+    let (owner_id, acl) = acl::requested_acl(headers, auth)?;
+    let attributes = storage::ObjectAttributes {
+        content_type,
+        custom_metadata,
+        lock: object_lock::requested_lock(headers)?,
+        tags,
+        owner_id: Some(owner_id),
+        acl: Some(acl),
+    };
+
+    // Encryption is chosen afresh too; an SSE-C source needs its key
+    let source_key = encryption::customer_key(headers, encryption::COPY_SOURCE_KEY_PREFIX)?;
+    let sse = encryption::requested_encryption(state, bucket, headers).await?;
+
+    let staged = state.storage_engine.stage_existing(&record, source_key.as_ref(), sse.as_ref()).await?;
+    let encryption_headers = encryption::encryption_headers(staged.encryption());
+    let object_ref = state.storage_engine.put_staged_object(bucket, key, staged, attributes).await?;
+
+    handlers::replicate_store(state, &object_ref).await;
+
+    let xml = xml::serialize_copy_result("CopyObjectResult", &object_ref.etag, object_ref.last_modified);
+
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", "application/xml".to_string()),
+            ("x-amz-version-id", storage::format_version_id(&object_ref.version_id)),
+            ("x-amz-copy-source-version-id", storage::format_version_id(&record.metadata.version_id)),
+        ],
+        AppendHeaders(encryption_headers),
+        xml,
+    ).into_response())
+}
+
+pub async fn upload_part_copy(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+    auth: &AuthContext,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let upload_id = params.get("uploadId")
+        .ok_or_else(|| ApiError::InvalidRequest("Missing uploadId".to_string()))?;
+    let part_number = params.get("partNumber")
+        .and_then(|n| n.parse::<u32>().ok())
+        .ok_or_else(|| ApiError::InvalidRequest("Missing or invalid partNumber".to_string()))?;
+
+    let upload = state.storage_engine.get_multipart_upload(upload_id).await?
+        .filter(|u| u.bucket == bucket && u.key == key)
+        .ok_or_else(|| ApiError::NoSuchUpload(upload_id.to_string()))?;
+
+    let (_, record) = resolve_copy_source(state, headers, auth).await?;
+    let source_key = encryption::customer_key(headers, encryption::COPY_SOURCE_KEY_PREFIX)?;
+    let customer_key = encryption::customer_key(headers, encryption::OBJECT_KEY_PREFIX)?;
+
+    // Whole plaintext sources can share their bytes with a plaintext part;
+    // anything else is read through and staged as the upload requires
+    let range = headers.get("x-amz-copy-source-range").and_then(|h| h.to_str().ok());
+    let staged = match range {
+        None if record.metadata.encryption.is_none() && upload.encryption.is_none() => {
+            state.storage_engine.stage_existing(&record, source_key.as_ref(), None).await?
+        }
+        _ => {
+            let (offset, length) = match range {
+                Some(range) => parse_copy_range(range, record.metadata.size)?,
+                None => (0, record.metadata.size),
+            };
+            let reader = state.storage_engine.read_range(&record, offset, length, source_key.as_ref()).await?;
+            state.storage_engine
+                .stage_part_data(upload_id, reader, customer_key.as_ref(), storage::UploadDigests::default()).await?
+        }
+    };
+
+    let part = state.storage_engine.upload_part(upload_id, part_number, staged).await?;
+    let xml = xml::serialize_copy_result("CopyPartResult", &part.etag, part.last_modified);
+
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", "application/xml".to_string()),
+            ("x-amz-copy-source-version-id", storage::format_version_id(&record.metadata.version_id)),
+        ],
+        AppendHeaders(encryption::encryption_headers(upload.encryption.as_ref())),
+        xml,
+    ).into_response())
+}
+
+/// Parses `x-amz-copy-source-range: bytes=first-last`. Unlike `Range`, both
+/// ends are required and must fall inside the source.
+fn parse_copy_range(range: &str, size: u64) -> ApiResult<(u64, u64)> {
+    let invalid = || ApiError::InvalidArgument(
+        "The x-amz-copy-source-range value must be of the form bytes=first-last \
+         where first and last are the zero-based offsets of the first and last bytes to copy".to_string()
+    );
+
+    let (first, last) = range.trim()
+        .strip_prefix("bytes=")
+        .and_then(|spec| spec.split_once('-'))
+        .ok_or_else(invalid)?;
+    let first = first.trim().parse::<u64>().map_err(|_| invalid())?;
+    let last = last.trim().parse::<u64>().map_err(|_| invalid())?;
+
+    if first > last {
+        return Err(invalid());
+    }
+    if last >= size {
+        return Err(ApiError::InvalidRange(
+            format!("Range specified is not valid for source object of size: {}", size)
+        ));
+    }
+
+    Ok((first, last - first + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::tests::{admin, body_string, conditions, test_state};
+    use axum::{body::Body, extract::{Extension, Path, Query, State}};
+    use std::sync::Arc;
+
+    /// A state with bucket `docs`, storing under a fresh directory.
+    async fn docs_state() -> (std::path::PathBuf, Arc<AppState>) {
+        let dir = std::env::temp_dir().join(format!("copy-{}", uuid::Uuid::new_v4()));
+        let state = test_state(&dir).await;
+        state.storage_engine.create_bucket("docs", None, Some("admin")).await.unwrap();
+        (dir, Arc::new(state))
+    }
+
+    async fn put(state: &Arc<AppState>, key: &str, headers: HeaderMap, body: impl Into<Body>) -> ApiResult<Response> {
+        handlers::put_object(
+            State(state.clone()),
+            Path(("docs".to_string(), key.to_string())),
+            Query(HashMap::new()),
+            Extension(admin()),
+            headers,
+            body.into(),
+        ).await
+    }
+
+    async fn get(state: &Arc<AppState>, key: &str) -> Response {
+        handlers::get_object(
+            State(state.clone()),
+            Path(("docs".to_string(), key.to_string())),
+            Query(HashMap::new()),
+            Extension(admin()),
+            HeaderMap::new(),
+        ).await.unwrap()
+    }
+
+    #[test]
+    fn copy_source_ranges_need_both_ends_inside_the_source() {
+        assert_eq!(parse_copy_range("bytes=0-9", 100).unwrap(), (0, 10));
+        assert_eq!(parse_copy_range(" bytes=99-99 ", 100).unwrap(), (99, 1));
+        for range in ["bytes=10-9", "bytes=-10", "bytes=10-", "0-9", "bytes=a-9", "items=0-9"] {
+            assert!(matches!(parse_copy_range(range, 100), Err(ApiError::InvalidArgument(_))), "{}", range);
+        }
+        assert!(matches!(parse_copy_range("bytes=0-100", 100), Err(ApiError::InvalidRange(_))));
+    }
+
+    #[test]
+    fn copy_sources_name_a_bucket_key_and_optional_version() {
+        let parse = |value: &str| parse_copy_source(&conditions(&[("x-amz-copy-source", value)]));
+
+        let source = parse("/docs/a%2Fb/c%20d.txt").unwrap();
+        assert_eq!((source.bucket.as_str(), source.key.as_str(), source.version_id), ("docs", "a/b/c d.txt", None));
+
+        let version_id = uuid::Uuid::new_v4();
+        let source = parse(&format!("docs/a?versionId={}", storage::format_version_id(&version_id))).unwrap();
+        assert_eq!(source.version_id, Some(version_id));
+
+        for value in ["docs", "docs/", "/a", "docs/a?versionId=bogus"] {
+            assert!(matches!(parse(value), Err(ApiError::InvalidArgument(_))), "{}", value);
+        }
+    }
+
+    #[tokio::test]
+    async fn metadata_is_copied_or_replaced_by_directive() {
+        let (dir, state) = docs_state().await;
+        let source = conditions(&[("content-type", "text/plain"), ("x-amz-meta-color", "red")]);
+        put(&state, "a", source, "data").await.unwrap();
+
+        put(&state, "copied", conditions(&[("x-amz-copy-source", "docs/a"), ("content-type", "text/html")]), "").await.unwrap();
+        let copied = get(&state, "copied").await;
+        assert_eq!(copied.headers()["content-type"], "text/plain");
+        assert_eq!(copied.headers()["x-amz-meta-color"], "red");
+        assert_eq!(body_string(copied).await, "data");
+
+        let replace = conditions(&[
+            ("x-amz-copy-source", "docs/a"),
+            ("x-amz-metadata-directive", "REPLACE"),
+            ("content-type", "text/html"),
+            ("x-amz-meta-size", "large"),
+        ]);
+        put(&state, "replaced", replace, "").await.unwrap();
+        let replaced = get(&state, "replaced").await;
+        assert_eq!(replaced.headers()["content-type"], "text/html");
+        assert_eq!(replaced.headers()["x-amz-meta-size"], "large");
+        assert!(!replaced.headers().contains_key("x-amz-meta-color"));
+
+        let unknown = conditions(&[("x-amz-copy-source", "docs/a"), ("x-amz-metadata-directive", "MERGE")]);
+        assert!(matches!(put(&state, "b", unknown, "").await, Err(ApiError::InvalidArgument(_))));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn copying_to_itself_needs_replace() {
+        let (dir, state) = docs_state().await;
+        put(&state, "a", conditions(&[("x-amz-meta-color", "red")]), "data").await.unwrap();
+
+        let result = put(&state, "a", conditions(&[("x-amz-copy-source", "/docs/a")]), "").await;
+        assert!(matches!(result, Err(ApiError::InvalidRequest(_))));
+        let copy = conditions(&[("x-amz-copy-source", "/docs/a"), ("x-amz-metadata-directive", "COPY")]);
+        assert!(matches!(put(&state, "a", copy, "").await, Err(ApiError::InvalidRequest(_))));
+
+        let replace = conditions(&[
+            ("x-amz-copy-source", "/docs/a"),
+            ("x-amz-metadata-directive", "REPLACE"),
+            ("x-amz-meta-color", "blue"),
+        ]);
+        put(&state, "a", replace, "").await.unwrap();
+        let response = get(&state, "a").await;
+        assert_eq!(response.headers()["x-amz-meta-color"], "blue");
+        assert_eq!(body_string(response).await, "data");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn copy_source_conditions_are_checked() {
+        let (dir, state) = docs_state().await;
+        put(&state, "a", HeaderMap::new(), "data").await.unwrap();
+        let etag = state.storage_engine.get_object_record("docs", "a", None).await.unwrap().unwrap().metadata.etag;
+        let copy = |condition: &'static str, value: &str| {
+            conditions(&[("x-amz-copy-source", "docs/a"), (condition, value)])
+        };
+
+        let failing = [
+            copy("x-amz-copy-source-if-match", "\"other\""),
+            copy("x-amz-copy-source-if-none-match", &etag),
+            copy("x-amz-copy-source-if-unmodified-since", "Sun, 06 Nov 1994 08:49:37 GMT"),
+            copy("x-amz-copy-source-if-modified-since", "Fri, 01 Jan 2100 00:00:00 GMT"),
+        ];
+        for headers in failing {
+            let result = put(&state, "b", headers.clone(), "").await;
+            assert!(matches!(result, Err(ApiError::PreconditionFailed(_))), "{:?}", headers);
+        }
+        assert!(state.storage_engine.get_object_record("docs", "b", None).await.unwrap().is_none());
+
+        // Plain conditions apply to the destination PUT, not the source
+        let headers = conditions(&[("x-amz-copy-source", "docs/a"), ("if-match", "\"other\""), ("x-amz-copy-source-if-match", &etag)]);
+        put(&state, "b", headers, "").await.unwrap();
+        assert_eq!(body_string(get(&state, "b").await).await, "data");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn earlier_versions_can_be_copied() {
+        let (dir, state) = docs_state().await;
+        state.storage_engine.set_bucket_versioning("docs", storage::VersioningStatus::Enabled).await.unwrap();
+        let first = put(&state, "a", HeaderMap::new(), "one").await.unwrap();
+        let first = first.headers()["x-amz-version-id"].to_str().unwrap().to_string();
+        put(&state, "a", HeaderMap::new(), "two").await.unwrap();
+
+        let source = format!("docs/a?versionId={}", first);
+        let response = put(&state, "b", conditions(&[("x-amz-copy-source", &source)]), "").await.unwrap();
+        assert_eq!(response.headers()["x-amz-copy-source-version-id"], first.as_str());
+        assert_eq!(body_string(get(&state, "b").await).await, "one");
+
+        let missing = format!("docs/a?versionId={}", storage::format_version_id(&uuid::Uuid::new_v4()));
+        let result = put(&state, "b", conditions(&[("x-amz-copy-source", &missing)]), "").await;
+        assert!(matches!(result, Err(ApiError::NoSuchKey(_))));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn plaintext_copies_share_the_source_bytes() {
+        let (dir, mut state) = docs_state().await;
+        // Room for the source but not for a second full copy of it
+        let engine = storage::StorageEngine::new(dir.join("tight").to_str().unwrap(), 6).await.unwrap();
+        engine.create_bucket("docs", None, Some("admin")).await.unwrap();
+        Arc::get_mut(&mut state).unwrap().storage_engine = Arc::new(engine);
+
+        put(&state, "a", HeaderMap::new(), "hello").await.unwrap();
+        let used = state.storage_engine.get_stats().await.used_space_bytes;
+        assert_eq!(used, 5);
+
+        put(&state, "b", conditions(&[("x-amz-copy-source", "docs/a")]), "").await.unwrap();
+        assert_eq!(state.storage_engine.get_stats().await.used_space_bytes, used);
+        let records = [
+            state.storage_engine.get_object_record("docs", "a", None).await.unwrap().unwrap(),
+            state.storage_engine.get_object_record("docs", "b", None).await.unwrap().unwrap(),
+        ];
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let paths = records.iter()
+                .map(|r| dir.join("tight").join("objects").join(&r.id[..2]).join(&r.id))
+                .map(|path| std::fs::metadata(path).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(paths[0].ino(), paths[1].ino());
+        }
+
+        // Deleting one name frees nothing; deleting the last frees the bytes
+        state.storage_engine.delete_object("docs", "a", None, false).await.unwrap();
+        assert_eq!(state.storage_engine.get_stats().await.used_space_bytes, used);
+        assert_eq!(body_string(get(&state, "b").await).await, "hello");
+        state.storage_engine.delete_object("docs", "b", None, false).await.unwrap();
+        assert_eq!(state.storage_engine.get_stats().await.used_space_bytes, 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn parts_are_copied_whole_or_by_range() {
+        let (dir, state) = docs_state().await;
+        let source = "0123456789".repeat(storage::MIN_PART_SIZE as usize / 10 + 1);
+        put(&state, "a", HeaderMap::new(), source.clone()).await.unwrap();
+        let upload = state.storage_engine
+            .create_multipart_upload("docs", "b", storage::ObjectAttributes::default(), None).await.unwrap();
+        let used = state.storage_engine.get_stats().await.used_space_bytes;
+
+        let copy_part = |part_number: u32, range: Option<&str>| {
+            let params = HashMap::from([
+                ("uploadId".to_string(), upload.upload_id.clone()),
+                ("partNumber".to_string(), part_number.to_string()),
+            ]);
+            let mut headers = conditions(&[("x-amz-copy-source", "docs/a")]);
+            if let Some(range) = range {
+                headers.insert("x-amz-copy-source-range", range.parse().unwrap());
+            }
+            let state = state.clone();
+            async move { upload_part_copy(&state, "docs", "b", &params, &headers, &admin()).await }
+        };
+
+        let whole = copy_part(1, None).await.unwrap();
+        assert!(body_string(whole).await.contains("<CopyPartResult"));
+        // The whole-object part is a hard link and takes no new space
+        assert_eq!(state.storage_engine.get_stats().await.used_space_bytes, used);
+        copy_part(2, Some("bytes=2-5")).await.unwrap();
+        assert_eq!(state.storage_engine.get_stats().await.used_space_bytes, used + 4);
+
+        assert!(matches!(copy_part(3, Some("bytes=5-2")).await, Err(ApiError::InvalidArgument(_))));
+        let past_end = format!("bytes=0-{}", source.len());
+        assert!(matches!(copy_part(3, Some(&past_end)).await, Err(ApiError::InvalidRange(_))));
+
+        let parts = state.storage_engine.list_parts(&upload.upload_id, 0, 1000).await.unwrap().parts;
+        assert_eq!(parts.iter().map(|p| p.size).collect::<Vec<_>>(), [source.len() as u64, 4]);
+        let requested: Vec<_> = parts.iter().map(|p| (p.part_number, p.etag.clone())).collect();
+        state.storage_engine.complete_multipart_upload(&upload.upload_id, &requested, None).await.unwrap();
+        assert_eq!(body_string(get(&state, "b").await).await, format!("{}2345", source));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
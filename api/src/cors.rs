@@ -0,0 +1,168 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::{ApiError, ApiResult};
+use crate::handlers::{self, AppState};
+use crate::xml;
+
+pub async fn get_bucket_cors(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let cors = bucket.cors.ok_or_else(|| ApiError::NoSuchCorsConfiguration(
+        "The CORS configuration does not exist".to_string()
+    ))?;
+
+    let xml = xml::serialize_cors_configuration(&cors);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
+
+pub async fn put_bucket_cors(state: &AppState, bucket: &str, body: Bytes) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let cors = xml::parse_cors_configuration(handlers::body_text(&body)?)?;
+
+    state.storage_engine.set_bucket_cors(bucket, Some(cors)).await?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+pub async fn delete_bucket_cors(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    state.storage_engine.set_bucket_cors(bucket, None).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// OPTIONS on a bucket. Preflights are not signed, so they bypass
+/// authentication and are answered from the bucket's CORS rules alone.
+pub async fn preflight_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(bucket): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    preflight(&state, &bucket, &headers).await
+}
+
+/// OPTIONS on an object; answered like `preflight_bucket`.
+pub async fn preflight_object(
+    State(state): State<Arc<AppState>>,
+    Path((bucket, _key)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    preflight(&state, &bucket, &headers).await
+}
+
+async fn preflight(state: &AppState, bucket: &str, headers: &HeaderMap) -> ApiResult<Response> {
+    let header_text = |name| headers.get(name).and_then(|h: &HeaderValue| h.to_str().ok());
+
+    let origin = header_text(header::ORIGIN).ok_or_else(|| ApiError::InvalidRequest(
+        "Insufficient information. Origin request header needed.".to_string()
+    ))?;
+    let method = header_text(header::ACCESS_CONTROL_REQUEST_METHOD).ok_or_else(|| ApiError::InvalidRequest(
+        "Invalid Access-Control-Request-Method".to_string()
+    ))?;
+    let request_headers: Vec<&str> = header_text(header::ACCESS_CONTROL_REQUEST_HEADERS)
+        .map(|list| list.split(',').map(str::trim).filter(|h| !h.is_empty()).collect())
+        .unwrap_or_default();
+
+    let config = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+    let forbidden = || ApiError::CorsForbidden(
+        "CORSResponse: This CORS request is not allowed. This is usually because the evalution of Origin, \
+         request method / Access-Control-Request-Method or Access-Control-Request-Headers are not whitelisted \
+         by the resource's CORS spec.".to_string()
+    );
+    let cors = config.cors.ok_or_else(forbidden)?;
+    let rule = cors.find_rule(origin, method, &request_headers).ok_or_else(forbidden)?;
+
+    let mut response_headers = allow_headers(rule, origin);
+    response_headers.push((header::ACCESS_CONTROL_ALLOW_METHODS, rule.allowed_methods.join(", ")));
+    if !request_headers.is_empty() {
+        response_headers.push((header::ACCESS_CONTROL_ALLOW_HEADERS, request_headers.join(", ")));
+    }
+    if let Some(max_age) = rule.max_age_seconds {
+        response_headers.push((header::ACCESS_CONTROL_MAX_AGE, max_age.to_string()));
+    }
+
+    let mut response = StatusCode::OK.into_response();
+    extend_headers(&mut response, response_headers);
+    Ok(response)
+}
+
+/// Response middleware: adds CORS headers to responses for requests whose
+/// `Origin` a rule of the addressed bucket allows. Without a matching rule
+/// the response is left alone and the browser withholds it from the page.
+pub async fn apply(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let origin = request.headers().get(header::ORIGIN)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+    let bucket = request.uri().path().trim_start_matches('/').split('/').next()
+        .filter(|name| !name.is_empty() && !name.starts_with('_'))
+        .map(|s| s.to_string());
+
+    let cors = match (origin, bucket) {
+        (Some(origin), Some(bucket)) if request.method() != Method::OPTIONS => {
+            match state.storage_engine.get_bucket(&bucket).await {
+                Ok(Some(config)) => config.cors.map(|cors| (cors, origin)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let method = request.method().as_str().to_string();
+
+    let mut response = next.run(request).await;
+
+    if let Some((cors, origin)) = cors {
+        if let Some(rule) = cors.find_rule(&origin, &method, &[]) {
+            let mut response_headers = allow_headers(rule, &origin);
+            if !rule.expose_headers.is_empty() {
+                response_headers.push((header::ACCESS_CONTROL_EXPOSE_HEADERS, rule.expose_headers.join(", ")));
+            }
+            extend_headers(&mut response, response_headers);
+        }
+    }
+
+    response
+}
+
+/// Headers every response allowed by `rule` carries. Rules open to all
+/// origins answer with `*`; others name the origin and allow credentials.
+fn allow_headers(rule: &storage::CorsRule, origin: &str) -> Vec<(header::HeaderName, String)> {
+    let mut headers = vec![(
+        header::VARY,
+        "Origin, Access-Control-Request-Headers, Access-Control-Request-Method".to_string(),
+    )];
+    if rule.allows_any_origin() {
+        headers.push((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*".to_string()));
+    } else {
+        headers.push((header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.to_string()));
+        headers.push((header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true".to_string()));
+    }
+    headers
+}
+
+fn extend_headers(response: &mut Response, headers: Vec<(header::HeaderName, String)>) {
+    for (name, value) in headers {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use openssl::base64;
+use storage::{CustomerKey, EncryptionMode, ObjectEncryption, ServerSideEncryption, SSE_ALGORITHM};
+
+use crate::{ApiError, ApiResult};
+use crate::handlers::{self, AppState};
+use crate::xml;
+
+/// Prefix of the SSE-C headers carrying the key of the addressed object.
+pub(crate) const OBJECT_KEY_PREFIX: &str = "x-amz-";
+
+/// Prefix of the SSE-C headers carrying the key of a copy source.
+pub(crate) const COPY_SOURCE_KEY_PREFIX: &str = "x-amz-copy-source-";
+
+/// Reads a customer-supplied key from the
+/// `<prefix>server-side-encryption-customer-{algorithm,key,key-MD5}`
+/// headers. All three must be given together, and the MD5 must match the
+/// key.
+pub(crate) fn customer_key(headers: &HeaderMap, prefix: &str) -> ApiResult<Option<CustomerKey>> {
+    let header = |name: &str| {
+        headers.get(format!("{}server-side-encryption-customer-{}", prefix, name).as_str())
+            .and_then(|h| h.to_str().ok())
+    };
+
+    let (algorithm, key, key_md5) = match (header("algorithm"), header("key"), header("key-md5")) {
+        (None, None, None) => return Ok(None),
+        (Some(algorithm), Some(key), Some(key_md5)) => (algorithm, key, key_md5),
+        _ => {
+            return Err(ApiError::InvalidArgument(
+                "Requests specifying Server Side Encryption with Customer provided keys must provide \
+                 the encryption algorithm, key and key MD5".to_string()
+            ));
+        }
+    };
+
+    if algorithm != SSE_ALGORITHM {
+        return Err(ApiError::InvalidArgument(
+            format!("The encryption algorithm {} is not supported", algorithm)
+        ));
+    }
+
+    let key = base64::decode_block(key.trim())
+        .map_err(|_| ApiError::InvalidArgument("The secret key was invalid for the specified algorithm".to_string()))?;
+    let key = CustomerKey::new(&key)?;
+    if key.key_md5() != key_md5.trim() {
+        return Err(ApiError::InvalidArgument(
+            "The calculated MD5 hash of the key did not match the hash that was provided".to_string()
+        ));
+    }
+
+    Ok(Some(key))
+}
+
+/// How a write to `bucket` should be encrypted: with a customer key if one
+/// is given, with SSE-S3 if `x-amz-server-side-encryption` asks for it, and
+/// otherwise as the bucket's default encryption says.
+pub(crate) async fn requested_encryption(
+    state: &AppState,
+    bucket: &str,
+    headers: &HeaderMap,
+) -> ApiResult<Option<ServerSideEncryption>> {
+    let customer_key = customer_key(headers, OBJECT_KEY_PREFIX)?;
+    let algorithm = headers.get("x-amz-server-side-encryption").and_then(|h| h.to_str().ok());
+
+    match (customer_key, algorithm) {
+        (Some(_), Some(_)) => Err(ApiError::InvalidArgument(
+            "Server Side Encryption with Customer provided key is incompatible with the encryption method specified"
+                .to_string()
+        )),
+        (Some(key), None) => Ok(Some(ServerSideEncryption::Customer(key))),
+        (None, Some(SSE_ALGORITHM)) => Ok(Some(ServerSideEncryption::S3)),
+        (None, Some(other)) => Err(ApiError::InvalidArgument(
+            format!("The encryption method {} is not supported", other)
+        )),
+        (None, None) => {
+            let default = state.storage_engine.get_bucket(bucket).await?
+                .and_then(|config| config.encryption);
+            Ok(default.map(|_| ServerSideEncryption::S3))
+        }
+    }
+}
+
+/// Headers telling the client how an object is encrypted at rest.
+pub(crate) fn encryption_headers(encryption: Option<&ObjectEncryption>) -> Vec<(String, String)> {
+    match encryption.map(|encryption| &encryption.mode) {
+        None => Vec::new(),
+        Some(EncryptionMode::S3) => {
+            vec![("x-amz-server-side-encryption".to_string(), SSE_ALGORITHM.to_string())]
+        }
+        Some(EncryptionMode::Customer { key_md5 }) => vec![
+            ("x-amz-server-side-encryption-customer-algorithm".to_string(), SSE_ALGORITHM.to_string()),
+            ("x-amz-server-side-encryption-customer-key-md5".to_string(), key_md5.clone()),
+        ],
+    }
+}
+
+pub async fn get_bucket_encryption(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let encryption = bucket.encryption.ok_or_else(|| ApiError::ServerSideEncryptionConfigurationNotFound(
+        "The server side encryption configuration was not found".to_string()
+    ))?;
+
+    let xml = xml::serialize_bucket_encryption(&encryption);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
+
+pub async fn put_bucket_encryption(state: &AppState, bucket: &str, body: Bytes) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let encryption = xml::parse_bucket_encryption(handlers::body_text(&body)?)?;
+
+    state.storage_engine.set_bucket_encryption(bucket, Some(encryption)).await?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+pub async fn delete_bucket_encryption(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    state.storage_engine.set_bucket_encryption(bucket, None).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -4,7 +4,7 @@ use thiserror::Error;
 
 pub type ApiResult<T> = Result<T, ApiError>;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum ApiError {
     #[error("Storage error: {0}")]
     Storage(String),
@@ -38,11 +38,133 @@ pub enum ApiError {
     
     #[error("Authentication error: {0}")]
     AuthError(String),
+    
+    #[error("Signature mismatch: {0}")]
+    SignatureDoesNotMatch(String),
+    
+    #[error("Request time too skewed: {0}")]
+    RequestTimeTooSkewed(String),
+    
+    #[error("Payload hash mismatch: {0}")]
+    ContentSha256Mismatch(String),
+    
+    #[error("Bad digest: {0}")]
+    BadDigest(String),
+    
+    #[error("Invalid digest: {0}")]
+    InvalidDigest(String),
+    
+    #[error("Incomplete body: {0}")]
+    IncompleteBody(String),
+    
+    #[error("Multipart upload not found: {0}")]
+    NoSuchUpload(String),
+    
+    #[error("Invalid part: {0}")]
+    InvalidPart(String),
+    
+    #[error("Invalid part order: {0}")]
+    InvalidPartOrder(String),
+    
+    #[error("Entity too small: {0}")]
+    EntityTooSmall(String),
+    
+    #[error("Invalid range: {0}")]
+    InvalidRange(String),
+    
+    #[error("Invalid part number: {0}")]
+    InvalidPartNumber(String),
+    
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+    
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    
+    #[error("Bucket already exists: {0}")]
+    BucketAlreadyExists(String),
+    
+    #[error("Bucket already owned by you: {0}")]
+    BucketAlreadyOwnedByYou(String),
+    
+    #[error("Bucket not empty: {0}")]
+    BucketNotEmpty(String),
+    
+    #[error("Invalid bucket name: {0}")]
+    InvalidBucketName(String),
+    
+    #[error("Key too long: {0}")]
+    KeyTooLong(String),
+    
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+    
+    #[error("Invalid bucket state: {0}")]
+    InvalidBucketState(String),
+    
+    #[error("Object Lock configuration not found: {0}")]
+    ObjectLockConfigurationNotFound(String),
+    
+    #[error("No such object lock configuration: {0}")]
+    NoSuchObjectLockConfiguration(String),
+    
+    #[error("No such lifecycle configuration: {0}")]
+    NoSuchLifecycleConfiguration(String),
+    
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+    
+    #[error("No such bucket policy: {0}")]
+    NoSuchBucketPolicy(String),
+    
+    #[error("Malformed policy: {0}")]
+    MalformedPolicy(String),
+    
+    #[error("No such CORS configuration: {0}")]
+    NoSuchCorsConfiguration(String),
+    
+    #[error("CORS request forbidden: {0}")]
+    CorsForbidden(String),
+    
+    #[error("No such encryption configuration: {0}")]
+    ServerSideEncryptionConfigurationNotFound(String),
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, error_code, message) = match self {
+impl From<storage::StorageError> for ApiError {
+    fn from(err: storage::StorageError) -> Self {
+        match err {
+            storage::StorageError::NoSuchUpload(msg) => ApiError::NoSuchUpload(msg),
+            storage::StorageError::InvalidPart(msg) => ApiError::InvalidPart(msg),
+            storage::StorageError::InvalidPartOrder(msg) => ApiError::InvalidPartOrder(msg),
+            storage::StorageError::EntityTooSmall(msg) => ApiError::EntityTooSmall(msg),
+            storage::StorageError::InvalidArgument(msg) => ApiError::InvalidArgument(msg),
+            storage::StorageError::NoSuchBucket(msg) => ApiError::NoSuchBucket(msg),
+            storage::StorageError::BucketAlreadyExists(msg) => ApiError::BucketAlreadyExists(msg),
+            storage::StorageError::BucketNotEmpty(msg) => ApiError::BucketNotEmpty(msg),
+            storage::StorageError::InvalidBucketName(msg) => ApiError::InvalidBucketName(msg),
+            storage::StorageError::InvalidBucketState(msg) => ApiError::InvalidBucketState(msg),
+            storage::StorageError::KeyTooLong(msg) => ApiError::KeyTooLong(msg),
+            storage::StorageError::InvalidRequest(msg) => ApiError::InvalidRequest(msg),
+            storage::StorageError::ObjectLocked(msg) => ApiError::AccessDenied(msg),
+            storage::StorageError::PreconditionFailed(msg) => ApiError::PreconditionFailed(msg),
+            storage::StorageError::InvalidTag(msg) => ApiError::InvalidTag(msg),
+            storage::StorageError::MalformedPolicy(msg) => ApiError::MalformedPolicy(msg),
+            storage::StorageError::InvalidEncryptionKey(msg) => ApiError::AccessDenied(msg),
+            // A request body that fails to decode reports why through the
+            // I/O error of the read that hit it
+            storage::StorageError::Io(e) => match e.get_ref().and_then(|inner| inner.downcast_ref::<ApiError>()) {
+                Some(body_error) => body_error.clone(),
+                None => ApiError::Storage(storage::StorageError::Io(e).to_string()),
+            },
+            e => ApiError::Storage(e.to_string()),
+        }
+    }
+}
+
+impl ApiError {
+    /// HTTP status, S3 error code and message for this error.
+    pub(crate) fn into_parts(self) -> (StatusCode, &'static str, String) {
+        match self {
             ApiError::Storage(msg) => (StatusCode::INTERNAL_SERVER_ERROR, "InternalError", msg),
             ApiError::Consensus(msg) => (StatusCode::SERVICE_UNAVAILABLE, "ServiceUnavailable", msg),
             ApiError::NoSuchBucket(msg) => (StatusCode::NOT_FOUND, "NoSuchBucket", msg),
@@ -54,7 +176,45 @@ impl IntoResponse for ApiError {
             ApiError::InsufficientReplicas => (StatusCode::SERVICE_UNAVAILABLE, "ServiceUnavailable", "Insufficient replicas for write operation".to_string()),
             ApiError::XmlError(msg) => (StatusCode::BAD_REQUEST, "MalformedXML", msg),
             ApiError::AuthError(msg) => (StatusCode::UNAUTHORIZED, "InvalidAccessKeyId", msg),
-        };
+            ApiError::SignatureDoesNotMatch(msg) => (StatusCode::FORBIDDEN, "SignatureDoesNotMatch", msg),
+            ApiError::RequestTimeTooSkewed(msg) => (StatusCode::FORBIDDEN, "RequestTimeTooSkewed", msg),
+            ApiError::ContentSha256Mismatch(msg) => (StatusCode::BAD_REQUEST, "XAmzContentSHA256Mismatch", msg),
+            ApiError::BadDigest(msg) => (StatusCode::BAD_REQUEST, "BadDigest", msg),
+            ApiError::InvalidDigest(msg) => (StatusCode::BAD_REQUEST, "InvalidDigest", msg),
+            ApiError::IncompleteBody(msg) => (StatusCode::BAD_REQUEST, "IncompleteBody", msg),
+            ApiError::NoSuchUpload(msg) => (StatusCode::NOT_FOUND, "NoSuchUpload", msg),
+            ApiError::InvalidPart(msg) => (StatusCode::BAD_REQUEST, "InvalidPart", msg),
+            ApiError::InvalidPartOrder(msg) => (StatusCode::BAD_REQUEST, "InvalidPartOrder", msg),
+            ApiError::EntityTooSmall(msg) => (StatusCode::BAD_REQUEST, "EntityTooSmall", msg),
+            ApiError::InvalidRange(msg) => (StatusCode::RANGE_NOT_SATISFIABLE, "InvalidRange", msg),
+            ApiError::InvalidPartNumber(msg) => (StatusCode::RANGE_NOT_SATISFIABLE, "InvalidPartNumber", msg),
+            ApiError::PreconditionFailed(msg) => (StatusCode::PRECONDITION_FAILED, "PreconditionFailed", msg),
+            ApiError::InvalidArgument(msg) => (StatusCode::BAD_REQUEST, "InvalidArgument", msg),
+            ApiError::BucketAlreadyExists(msg) => (StatusCode::CONFLICT, "BucketAlreadyExists", msg),
+            ApiError::BucketAlreadyOwnedByYou(msg) => (StatusCode::CONFLICT, "BucketAlreadyOwnedByYou", msg),
+            ApiError::BucketNotEmpty(msg) => (StatusCode::CONFLICT, "BucketNotEmpty", msg),
+            ApiError::InvalidBucketName(msg) => (StatusCode::BAD_REQUEST, "InvalidBucketName", msg),
+            ApiError::KeyTooLong(msg) => (StatusCode::BAD_REQUEST, "KeyTooLongError", msg),
+            ApiError::MethodNotAllowed(msg) => (StatusCode::METHOD_NOT_ALLOWED, "MethodNotAllowed", msg),
+            ApiError::InvalidBucketState(msg) => (StatusCode::CONFLICT, "InvalidBucketState", msg),
+            ApiError::ObjectLockConfigurationNotFound(msg) => (StatusCode::NOT_FOUND, "ObjectLockConfigurationNotFoundError", msg),
+            ApiError::NoSuchObjectLockConfiguration(msg) => (StatusCode::NOT_FOUND, "NoSuchObjectLockConfiguration", msg),
+            ApiError::NoSuchLifecycleConfiguration(msg) => (StatusCode::NOT_FOUND, "NoSuchLifecycleConfiguration", msg),
+            ApiError::InvalidTag(msg) => (StatusCode::BAD_REQUEST, "InvalidTag", msg),
+            ApiError::NoSuchBucketPolicy(msg) => (StatusCode::NOT_FOUND, "NoSuchBucketPolicy", msg),
+            ApiError::MalformedPolicy(msg) => (StatusCode::BAD_REQUEST, "MalformedPolicy", msg),
+            ApiError::NoSuchCorsConfiguration(msg) => (StatusCode::NOT_FOUND, "NoSuchCORSConfiguration", msg),
+            ApiError::CorsForbidden(msg) => (StatusCode::FORBIDDEN, "AccessForbidden", msg),
+            ApiError::ServerSideEncryptionConfigurationNotFound(msg) => {
+                (StatusCode::NOT_FOUND, "ServerSideEncryptionConfigurationNotFoundError", msg)
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error_code, message) = self.into_parts();
 
         let error_xml = format!(
             r#"<?xml version="1.0" encoding="UTF-8"?>
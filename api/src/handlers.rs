@@ -1,25 +1,44 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::{IntoResponse, Response},
-    body::Bytes,
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::{AppendHeaders, IntoResponse, Response},
+    body::{Body, Bytes},
 };
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
 use crate::{ApiError, ApiResult};
-use crate::auth::{extract_auth_info, AuthContext};
+use crate::acl;
+use crate::auth::{self, AuthContext};
+use crate::checksum;
+use crate::chunked;
+use crate::copy;
+use crate::cors;
+use crate::encryption;
+use crate::lifecycle;
+use crate::multipart;
+use crate::tagging;
+use crate::object_lock;
+use crate::policy;
+use crate::versioning;
 use crate::xml;
 use crate::{
+    DeleteError, DeleteObjectsResponse, DeleteRequest, DeletedObject, ObjectIdentifier,
     ListBucketsResponse, ListObjectsV2Response, BucketInfo, ObjectInfo, Owner, CommonPrefix,
-    PutObjectResponse, GetObjectResponse, DeleteObjectResponse, HeadObjectResponse,
 };
 
 pub struct AppState {
     pub storage_engine: Arc<storage::StorageEngine>,
     pub consensus_manager: Arc<consensus::ConsensusManager>,
     pub cluster_state: Arc<tokio::sync::RwLock<crate::ClusterState>>,
+    /// Node-level access key -> secret key pairs; these act as admin keys.
+    pub credentials: HashMap<String, String>,
+    pub allow_anonymous: bool,
+    /// Domains whose subdomains name buckets, lowercased.
+    pub base_domains: Vec<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -36,26 +55,162 @@ pub struct ListObjectsV2Query {
     start_after: Option<String>,
     #[serde(rename = "encoding-type")]
     encoding_type: Option<String>,
+    #[serde(rename = "fetch-owner")]
+    fetch_owner: Option<bool>,
+    /// Lists only keys carrying these tags, given in `x-amz-tagging` form.
+    /// Not part of S3.
+    #[serde(rename = "tag-filter")]
+    tag_filter: Option<String>,
+}
+
+pub(crate) async fn check_write_enabled(state: &AppState) -> ApiResult<()> {
+    if !state.cluster_state.read().await.is_write_enabled {
+        return Err(ApiError::InsufficientReplicas);
+    }
+    Ok(())
+}
+
+/// Rejects a body whose SHA-256 differs from the hash the client signed.
+pub(crate) fn verify_payload_hash(auth: &AuthContext, actual: &str) -> ApiResult<()> {
+    if let Some(expected) = auth.signed_payload_hash() {
+        if !expected.eq_ignore_ascii_case(actual) {
+            return Err(ApiError::ContentSha256Mismatch(
+                "The provided x-amz-content-sha256 does not match the request body".to_string()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects a buffered body whose SHA-256 differs from the hash the client
+/// signed. Nothing is hashed for unsigned payloads.
+pub(crate) fn verify_body_hash(auth: &AuthContext, body: &[u8]) -> ApiResult<()> {
+    if auth.signed_payload_hash().is_none() {
+        return Ok(());
+    }
+    verify_payload_hash(auth, &auth::hex_encode(&Sha256::digest(body)))
+}
+
+/// Streams a request body to a staging file, encrypted as requested, then
+/// checks it against the signed payload hash and the client's checksums
+/// before anything is committed.
+pub(crate) async fn stage_body(
+    state: &AppState,
+    auth: &AuthContext,
+    headers: &HeaderMap,
+    body: Body,
+    encryption: Option<&storage::ServerSideEncryption>,
+) -> ApiResult<storage::StagedData> {
+    let checksums = checksum::ExpectedChecksums::from_headers(headers)?;
+    let (reader, trailers) = chunked::body_reader(auth, headers, body)?;
+    let staged = state.storage_engine.stage_data_with(reader, encryption, checksums.digests()).await?;
+    verify_payload_hash(auth, &staged.checksum().sha256)?;
+    checksums.verify(&staged, &trailers)?;
+
+    Ok(staged)
+}
+
+/// Largest XML document accepted on an object sub-resource such as
+/// `?retention` or `?tagging`.
+const MAX_SMALL_BODY_SIZE: usize = 64 * 1024;
+
+/// Buffers the small XML body of an object sub-resource request, which
+/// arrives through the streaming PUT object route, and checks it against
+/// the signed payload hash.
+pub(crate) async fn read_small_body(auth: &AuthContext, body: Body) -> ApiResult<Bytes> {
+    let body = axum::body::to_bytes(body, MAX_SMALL_BODY_SIZE).await
+        .map_err(|e| ApiError::InvalidRequest(format!("Failed to read request body: {}", e)))?;
+    verify_body_hash(auth, &body)?;
+    Ok(body)
+}
+
+pub(crate) fn body_text(body: &Bytes) -> ApiResult<&str> {
+    std::str::from_utf8(body)
+        .map_err(|_| ApiError::XmlError("Request body is not valid UTF-8".to_string()))
+}
+
+/// Collects `x-amz-meta-*` headers into user metadata.
+pub(crate) fn custom_metadata(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut custom_metadata = HashMap::new();
+    for (name, value) in headers.iter() {
+        if let Some(meta_key) = name.as_str().strip_prefix("x-amz-meta-") {
+            if let Ok(meta_value) = value.to_str() {
+                custom_metadata.insert(meta_key.to_string(), meta_value.to_string());
+            }
+        }
+    }
+    custom_metadata
+}
+
+/// The `versionId` query parameter, which may be `null`.
+pub(crate) fn version_id_param(params: &HashMap<String, String>) -> ApiResult<Option<storage::Version>> {
+    params.get("versionId")
+        .map(|v| storage::parse_version_id(v)
+            .ok_or_else(|| ApiError::InvalidArgument("Invalid version id specified".to_string())))
+        .transpose()
+}
+
+/// Asks the consensus layer to replicate a newly stored version. Failures are
+/// logged only: the object is already durable locally.
+pub(crate) async fn replicate_store(state: &AppState, object_ref: &storage::ObjectReference) {
+    let metadata = consensus::ObjectReplicationMetadata {
+        bucket: object_ref.bucket.clone(),
+        key: object_ref.key.clone(),
+        version_id: object_ref.version_id,
+        size: object_ref.size,
+        checksum: object_ref.etag.clone(),
+        target_nodes: vec![], // Will be filled by consensus manager
+    };
+    
+    if let Err(e) = state.consensus_manager.request_replication(
+        object_ref.id.clone(),
+        consensus::ReplicationOperation::Store,
+        metadata,
+        None, // Data will be read from storage by other nodes
+    ).await {
+        tracing::warn!("Failed to initiate replication for {}: {}", object_ref.id, e);
+    }
+}
+
+pub(crate) async fn replicate_delete(state: &AppState, bucket: &str, key: &str, version_id: Option<Uuid>) {
+    let metadata = consensus::ObjectReplicationMetadata {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        version_id: version_id.unwrap_or_else(Uuid::new_v4),
+        size: 0,
+        checksum: String::new(),
+        target_nodes: vec![],
+    };
+    
+    if let Err(e) = state.consensus_manager.request_replication(
+        format!("{}:{}", bucket, key),
+        consensus::ReplicationOperation::Delete,
+        metadata,
+        None,
+    ).await {
+        tracing::warn!("Failed to initiate delete replication for {}:{}: {}", bucket, key, e);
+    }
 }
 
 pub async fn list_buckets(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    Extension(auth): Extension<AuthContext>,
 ) -> ApiResult<Response> {
-    let _auth = extract_auth_info(&headers)?;
-    
-    // For now, return a simple bucket list
-    // In a real implementation, this would query the storage engine
+    // Buckets created before owners were recorded are visible to everyone
+    let buckets = state.storage_engine.list_buckets().await?
+        .into_iter()
+        .filter(|b| b.owner_id.as_deref().is_none_or(|owner| owner == auth.owner_id))
+        .map(|b| BucketInfo {
+            name: b.name,
+            creation_date: b.created_at,
+        })
+        .collect();
+
     let response = ListBucketsResponse {
-        buckets: vec![
-            BucketInfo {
-                name: "default".to_string(),
-                creation_date: chrono::Utc::now(),
-            }
-        ],
+        buckets,
         owner: Owner {
-            id: "o3storage".to_string(),
-            display_name: "O3Storage System".to_string(),
+            id: auth.owner_id.clone(),
+            display_name: auth.owner_id.clone(),
         },
     };
 
@@ -68,25 +223,291 @@ pub async fn list_buckets(
     ).into_response())
 }
 
-pub async fn create_bucket(
+/// PUT on a bucket: creates it, or sets a configuration sub-resource.
+pub async fn put_bucket(
     State(state): State<Arc<AppState>>,
     Path(bucket): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
     headers: HeaderMap,
+    body: Bytes,
 ) -> ApiResult<Response> {
-    let _auth = extract_auth_info(&headers)?;
-    
-    // Check if writes are enabled
-    let cluster_state = state.cluster_state.read().await;
-    if !cluster_state.is_write_enabled {
-        return Err(ApiError::InsufficientReplicas);
+    let action = policy::bucket_action(&Method::PUT, &params);
+    policy::authorize(&state, &auth, action, &bucket, None, &params).await?;
+    verify_body_hash(&auth, &body)?;
+
+    if params.contains_key("versioning") {
+        return versioning::put_bucket_versioning(&state, &bucket, body).await;
     }
-    drop(cluster_state);
-    
-    state.storage_engine.create_bucket(&bucket, None).await
-        .map_err(|e| ApiError::Storage(e.to_string()))?;
+    if params.contains_key("object-lock") {
+        return object_lock::put_object_lock_configuration(&state, &bucket, body).await;
+    }
+    if params.contains_key("lifecycle") {
+        return lifecycle::put_bucket_lifecycle(&state, &bucket, body).await;
+    }
+    if params.contains_key("policy") {
+        return policy::put_bucket_policy(&state, &bucket, body).await;
+    }
+    if params.contains_key("acl") {
+        return acl::put_bucket_acl(&state, &bucket, &auth, &headers, body).await;
+    }
+    if params.contains_key("cors") {
+        return cors::put_bucket_cors(&state, &bucket, body).await;
+    }
+    if params.contains_key("encryption") {
+        return encryption::put_bucket_encryption(&state, &bucket, body).await;
+    }
+
+    create_bucket(&state, bucket, &auth, &headers, body).await
+}
+
+async fn create_bucket(
+    state: &AppState,
+    bucket: String,
+    auth: &AuthContext,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    check_write_enabled(state).await?;
+
+    if let Some(existing) = state.storage_engine.get_bucket(&bucket).await? {
+        return Err(if existing.owner_id.as_deref() == Some(auth.owner_id.as_str()) {
+            ApiError::BucketAlreadyOwnedByYou(bucket)
+        } else {
+            ApiError::BucketAlreadyExists(bucket)
+        });
+    }
+
+    let body = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::XmlError("Request body is not valid UTF-8".to_string()))?;
+    let region = xml::parse_create_bucket_configuration(body);
+
+    let object_lock = headers.get("x-amz-bucket-object-lock-enabled")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"));
+
+    let (owner_id, acl) = acl::requested_acl(headers, auth)?;
+    let mut config = storage::Bucket::new(bucket.clone(), region, Some(owner_id));
+    config.acl = Some(acl);
+    if object_lock {
+        config = config.with_object_lock();
+    }
+    state.storage_engine.create_bucket_with(config).await?;
     
     let xml = xml::serialize_create_bucket();
     
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", "application/xml".to_string()),
+            ("location", format!("/{}", bucket)),
+        ],
+        xml,
+    ).into_response())
+}
+
+pub async fn head_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(bucket): Path<String>,
+    Extension(auth): Extension<AuthContext>,
+) -> ApiResult<Response> {
+    let config = state.storage_engine.get_bucket(&bucket).await?;
+    policy::check(&state, config.as_ref(), &auth, "s3:ListBucket", &bucket, None, &HashMap::new()).await?;
+    let bucket = config.ok_or(ApiError::NoSuchBucket(bucket))?;
+
+    Ok((
+        StatusCode::OK,
+        [("x-amz-bucket-region", bucket.region)],
+    ).into_response())
+}
+
+/// DELETE on a bucket: removes it, or a configuration sub-resource.
+pub async fn delete_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(bucket): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
+) -> ApiResult<Response> {
+    let action = policy::bucket_action(&Method::DELETE, &params);
+    policy::authorize(&state, &auth, action, &bucket, None, &params).await?;
+
+    if params.contains_key("lifecycle") {
+        return lifecycle::delete_bucket_lifecycle(&state, &bucket).await;
+    }
+    if params.contains_key("policy") {
+        return policy::delete_bucket_policy(&state, &bucket).await;
+    }
+    if params.contains_key("cors") {
+        return cors::delete_bucket_cors(&state, &bucket).await;
+    }
+    if params.contains_key("encryption") {
+        return encryption::delete_bucket_encryption(&state, &bucket).await;
+    }
+
+    check_write_enabled(&state).await?;
+
+    state.storage_engine.delete_bucket(&bucket).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn get_bucket_location(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let xml = xml::serialize_location_constraint(&bucket.region);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
+
+async fn list_object_versions(
+    state: &AppState,
+    bucket: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    if !state.storage_engine.bucket_exists(bucket).await? {
+        return Err(ApiError::NoSuchBucket(bucket.to_string()));
+    }
+
+    let url_encode = match params.get("encoding-type").map(|e| e.as_str()) {
+        None => false,
+        Some("url") => true,
+        Some(other) => {
+            return Err(ApiError::InvalidArgument(format!("Invalid Encoding Method specified in Request: {}", other)));
+        }
+    };
+
+    let max_keys = match params.get("max-keys") {
+        Some(value) => value.parse::<usize>()
+            .map_err(|_| ApiError::InvalidArgument("max-keys must be a non-negative integer".to_string()))?
+            .min(1000),
+        None => 1000,
+    };
+
+    let key_marker = params.get("key-marker").map(|k| k.as_str()).filter(|k| !k.is_empty());
+    let version_id_marker = match params.get("version-id-marker").filter(|v| !v.is_empty()) {
+        Some(_) if key_marker.is_none() => {
+            return Err(ApiError::InvalidArgument(
+                "A version-id marker cannot be specified without a key marker".to_string()
+            ));
+        }
+        Some(v) => Some(storage::parse_version_id(v)
+            .ok_or_else(|| ApiError::InvalidArgument("Invalid version id specified".to_string()))?),
+        None => None,
+    };
+
+    let response = state.storage_engine.list_object_versions(
+        bucket,
+        params.get("prefix").map(|p| p.as_str()),
+        params.get("delimiter").map(|d| d.as_str()),
+        key_marker,
+        version_id_marker,
+        max_keys,
+    ).await?;
+
+    let xml = xml::serialize_list_object_versions(&response, |value| {
+        if url_encode { auth::uri_encode(value, false) } else { value.to_string() }
+    });
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
+
+/// POST on a bucket: currently only multi-object delete (`?delete`).
+pub async fn post_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(bucket): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    verify_body_hash(&auth, &body)?;
+    if params.contains_key("delete") {
+        let bypass_governance = object_lock::bypass_governance(&headers, &auth);
+        delete_objects(&state, &bucket, &auth, bypass_governance, body).await
+    } else {
+        Err(ApiError::InvalidRequest("Unsupported POST operation on bucket".to_string()))
+    }
+}
+
+/// Most keys a single DeleteObjects request may name.
+const MAX_DELETE_OBJECTS: usize = 1000;
+
+/// Deletes each named object the caller may delete; refused keys are
+/// reported as AccessDenied errors alongside the others.
+async fn delete_objects(
+    state: &AppState,
+    bucket: &str,
+    auth: &AuthContext,
+    bypass_governance: bool,
+    body: Bytes,
+) -> ApiResult<Response> {
+    check_write_enabled(state).await?;
+
+    let config = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let body = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::XmlError("Request body is not valid UTF-8".to_string()))?;
+    let DeleteRequest { objects, quiet } = xml::parse_delete_objects(body)?;
+
+    if objects.is_empty() || objects.len() > MAX_DELETE_OBJECTS {
+        return Err(ApiError::XmlError(
+            format!("A delete request must name between 1 and {} objects", MAX_DELETE_OBJECTS)
+        ));
+    }
+
+    let mut response = DeleteObjectsResponse { deleted: Vec::new(), errors: Vec::new() };
+
+    for ObjectIdentifier { key, version_id: version } in objects {
+        let params: HashMap<String, String> = version.iter()
+            .map(|v| ("versionId".to_string(), v.clone()))
+            .collect();
+        let action = policy::object_action(&Method::DELETE, &params);
+
+        let result = match version.as_deref().map(storage::parse_version_id) {
+            Some(None) => Err(ApiError::InvalidArgument("Invalid version id specified".to_string())),
+            _ if policy::check(state, Some(&config), auth, action, bucket, Some(&key), &params).await.is_err() => {
+                Err(ApiError::AccessDenied("Access Denied".to_string()))
+            }
+            Some(Some(version_id)) => delete_one(state, bucket, &key, Some(version_id), bypass_governance).await,
+            None => delete_one(state, bucket, &key, None, bypass_governance).await,
+        };
+
+        match result {
+            Ok(deleted) => {
+                if !quiet {
+                    let marker_version = deleted.version_id.as_ref().map(storage::format_version_id);
+                    response.deleted.push(DeletedObject {
+                        key,
+                        version_id: version,
+                        delete_marker: deleted.delete_marker,
+                        delete_marker_version_id: marker_version.filter(|_| deleted.delete_marker),
+                    });
+                }
+            }
+            Err(e) => {
+                let (_, code, message) = e.into_parts();
+                response.errors.push(DeleteError {
+                    key,
+                    version_id: version,
+                    code: code.to_string(),
+                    message,
+                });
+            }
+        }
+    }
+
+    let xml = xml::serialize_delete_objects(&response);
+
     Ok((
         StatusCode::OK,
         [("content-type", "application/xml")],
@@ -94,52 +515,146 @@ pub async fn create_bucket(
     ).into_response())
 }
 
+async fn delete_one(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    version_id: Option<Uuid>,
+    bypass_governance: bool,
+) -> ApiResult<storage::DeleteResult> {
+    let deleted = state.storage_engine.delete_object(bucket, key, version_id, bypass_governance).await?;
+    replicate_delete(state, bucket, key, deleted.version_id).await;
+    Ok(deleted)
+}
+
+/// Continuation tokens are the hex-encoded marker of the last entry returned.
+fn decode_continuation_token(token: &str) -> ApiResult<String> {
+    auth::hex_decode(token)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| ApiError::InvalidArgument("The continuation token provided is incorrect".to_string()))
+}
+
+/// GET on a bucket: lists objects, or in-progress uploads with `?uploads`.
+pub async fn get_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(bucket): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
+    uri: Uri,
+) -> ApiResult<Response> {
+    let action = policy::bucket_action(&Method::GET, &params);
+    policy::authorize(&state, &auth, action, &bucket, None, &params).await?;
+
+    if params.contains_key("uploads") {
+        return multipart::list_multipart_uploads(&state, &bucket, &params).await;
+    }
+    if params.contains_key("versions") {
+        return list_object_versions(&state, &bucket, &params).await;
+    }
+    if params.contains_key("location") {
+        return get_bucket_location(&state, &bucket).await;
+    }
+    if params.contains_key("versioning") {
+        return versioning::get_bucket_versioning(&state, &bucket).await;
+    }
+    if params.contains_key("object-lock") {
+        return object_lock::get_object_lock_configuration(&state, &bucket).await;
+    }
+    if params.contains_key("lifecycle") {
+        return lifecycle::get_bucket_lifecycle(&state, &bucket).await;
+    }
+    if params.contains_key("policy") {
+        return policy::get_bucket_policy(&state, &bucket).await;
+    }
+    if params.contains_key("acl") {
+        return acl::get_bucket_acl(&state, &bucket, &auth).await;
+    }
+    if params.contains_key("cors") {
+        return cors::get_bucket_cors(&state, &bucket).await;
+    }
+    if params.contains_key("encryption") {
+        return encryption::get_bucket_encryption(&state, &bucket).await;
+    }
+
+    let query = Query::<ListObjectsV2Query>::try_from_uri(&uri)
+        .map_err(|e| ApiError::InvalidRequest(e.to_string()))?;
+    list_objects_v2(State(state), Path(bucket), query, Extension(auth)).await
+}
+
 pub async fn list_objects_v2(
     State(state): State<Arc<AppState>>,
     Path(bucket): Path<String>,
     Query(query): Query<ListObjectsV2Query>,
-    headers: HeaderMap,
+    Extension(_auth): Extension<AuthContext>,
 ) -> ApiResult<Response> {
-    let _auth = extract_auth_info(&headers)?;
-    
-    if !state.storage_engine.bucket_exists(&bucket).await
-        .map_err(|e| ApiError::Storage(e.to_string()))? {
+    let Some(config) = state.storage_engine.get_bucket(&bucket).await? else {
         return Err(ApiError::NoSuchBucket(bucket));
-    }
+    };
     
-    let max_keys = query.max_keys.unwrap_or(1000).min(1000) as usize;
-    let prefix = query.prefix.as_deref();
+    // Requests without a list-type are answered as V2 as well
+    if let Some(list_type) = query.list_type.as_deref().filter(|list_type| *list_type != "2") {
+        return Err(ApiError::InvalidArgument(format!("Unsupported list-type: {}", list_type)));
+    }
+
+    let url_encode = match query.encoding_type.as_deref() {
+        None => false,
+        Some("url") => true,
+        Some(other) => {
+            return Err(ApiError::InvalidArgument(format!("Invalid Encoding Method specified in Request: {}", other)));
+        }
+    };
+    let encode = |value: String| if url_encode { auth::uri_encode(&value, false) } else { value };
+
+    // The continuation token wins over start-after when both are given
+    let after = match &query.continuation_token {
+        Some(token) => Some(decode_continuation_token(token)?),
+        None => query.start_after.clone(),
+    };
+
+    let max_keys = query.max_keys.unwrap_or(1000).min(1000);
+    let tags = query.tag_filter.as_deref().map(tagging::parse_tag_query).unwrap_or_default();
     
-    let objects = state.storage_engine.list_objects(&bucket, prefix, max_keys).await
-        .map_err(|e| ApiError::Storage(e.to_string()))?;
+    let page = state.storage_engine
+        .list_objects(&bucket, query.prefix.as_deref(), query.delimiter.as_deref(), after.as_deref(), max_keys as usize, &tags)
+        .await?;
+
+    // Objects stored before owners were recorded belong to the bucket owner
+    let fetch_owner = query.fetch_owner.unwrap_or(false);
+    let owner = |owner_id: Option<String>| {
+        owner_id.or_else(|| config.owner_id.clone())
+            .filter(|_| fetch_owner)
+            .map(|id| Owner { display_name: id.clone(), id })
+    };
     
-    let contents: Vec<ObjectInfo> = objects.into_iter().map(|obj| {
+    let contents: Vec<ObjectInfo> = page.objects.into_iter().map(|obj| {
         ObjectInfo {
-            key: obj.key,
+            key: encode(obj.key),
             last_modified: obj.last_modified,
             etag: obj.etag,
             size: obj.size,
             storage_class: "STANDARD".to_string(),
-            owner: Some(Owner {
-                id: "o3storage".to_string(),
-                display_name: "O3Storage System".to_string(),
-            }),
+            owner: owner(obj.owner_id),
         }
     }).collect();
+
+    let common_prefixes: Vec<CommonPrefix> = page.common_prefixes.into_iter()
+        .map(|prefix| CommonPrefix { prefix: encode(prefix) })
+        .collect();
     
+    let key_count = (contents.len() + common_prefixes.len()) as u32;
     let response = ListObjectsV2Response {
-        is_truncated: false, // TODO: Implement pagination
+        is_truncated: page.is_truncated,
         contents,
         name: bucket,
-        prefix: query.prefix,
-        delimiter: query.delimiter,
-        max_keys: query.max_keys.unwrap_or(1000),
-        common_prefixes: vec![], // TODO: Implement common prefixes
+        prefix: query.prefix.map(encode),
+        delimiter: query.delimiter.map(encode),
+        max_keys,
+        common_prefixes,
         encoding_type: query.encoding_type,
-        key_count: contents.len() as u32,
+        key_count,
         continuation_token: query.continuation_token,
-        next_continuation_token: None, // TODO: Implement pagination
-        start_after: query.start_after,
+        next_continuation_token: page.next_marker.map(|marker| auth::hex_encode(marker.as_bytes())),
+        start_after: query.start_after.map(encode),
     };
     
     let xml = xml::serialize_list_objects_v2(&response);
@@ -154,127 +669,433 @@ pub async fn list_objects_v2(
 pub async fn put_object(
     State(state): State<Arc<AppState>>,
     Path((bucket, key)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
     headers: HeaderMap,
-    body: Bytes,
+    body: Body,
 ) -> ApiResult<Response> {
-    let _auth = extract_auth_info(&headers)?;
-    
-    // Check if writes are enabled
-    let cluster_state = state.cluster_state.read().await;
-    if !cluster_state.is_write_enabled {
-        return Err(ApiError::InsufficientReplicas);
+    let action = policy::object_action(&Method::PUT, &params);
+    policy::authorize(&state, &auth, action, &bucket, Some(&key), &params).await?;
+
+    let is_copy = headers.contains_key("x-amz-copy-source");
+    if params.contains_key("uploadId") {
+        if is_copy {
+            return copy::upload_part_copy(&state, &bucket, &key, &params, &headers, &auth).await;
+        }
+        return multipart::upload_part(&state, &bucket, &key, &params, &headers, &auth, body).await;
+    }
+    if is_copy {
+        return copy::copy_object(&state, &bucket, &key, &headers, &auth).await;
+    }
+    if params.contains_key("retention") {
+        return object_lock::put_object_retention(&state, &bucket, &key, &params, &headers, &auth, body).await;
+    }
+    if params.contains_key("legal-hold") {
+        return object_lock::put_object_legal_hold(&state, &bucket, &key, &params, &auth, body).await;
+    }
+    if params.contains_key("tagging") {
+        return tagging::put_object_tagging(&state, &bucket, &key, &params, &auth, body).await;
+    }
+    if params.contains_key("acl") {
+        let body = read_small_body(&auth, body).await?;
+        return acl::put_object_acl(&state, &bucket, &key, &params, &auth, &headers, body).await;
+    }
+
+    check_write_enabled(&state).await?;
+    let lock = object_lock::requested_lock(&headers)?;
+    let tags = tagging::requested_tags(&headers)?;
+    let (owner_id, acl) = acl::requested_acl(&headers, &auth)?;
+    let sse = encryption::requested_encryption(&state, &bucket, &headers).await?;
+
+    let create_only = match headers.get("if-none-match").and_then(|h| h.to_str().ok()) {
+        Some(value) if value.trim() == "*" => true,
+        Some(_) => {
+            return Err(ApiError::InvalidRequest(
+                "Only If-None-Match: * is supported on PUT".to_string()
+            ));
+        }
+        None => false,
+    };
+
+    // Fail fast before reading the body; the commit checks again atomically
+    if create_only && state.storage_engine.get_object_metadata(&bucket, &key, None).await?.is_some() {
+        return Err(ApiError::PreconditionFailed(
+            format!("{}/{} already exists", bucket, key)
+        ));
     }
-    drop(cluster_state);
+
+    let staged = stage_body(&state, &auth, &headers, body, sse.as_ref()).await?;
+    let mut response_headers = encryption::encryption_headers(staged.encryption());
+    response_headers.extend(checksum::checksum_headers(staged.checksum().client.as_ref()));
     
     let content_type = headers.get("content-type")
         .and_then(|h| h.to_str().ok())
         .map(|s| s.to_string());
     
-    // Extract custom metadata from x-amz-meta-* headers
-    let mut custom_metadata = HashMap::new();
-    for (name, value) in headers.iter() {
-        if let Some(meta_key) = name.as_str().strip_prefix("x-amz-meta-") {
-            if let Ok(meta_value) = value.to_str() {
-                custom_metadata.insert(meta_key.to_string(), meta_value.to_string());
-            }
-        }
-    }
-    
-    let object_ref = state.storage_engine
-        .put_object(&bucket, &key, body, content_type, custom_metadata).await
-        .map_err(|e| ApiError::Storage(e.to_string()))?;
-    
-    // Trigger replication via consensus
-    let metadata = consensus::ObjectReplicationMetadata {
-        bucket: bucket.clone(),
-        key: key.clone(),
-        version_id: object_ref.version_id,
-        size: object_ref.size,
-        checksum: object_ref.etag.clone(),
-        target_nodes: vec![], // Will be filled by consensus manager
+    let attributes = storage::ObjectAttributes {
+        content_type,
+        custom_metadata: custom_metadata(&headers),
+        lock,
+        tags,
+        owner_id: Some(owner_id),
+        acl: Some(acl),
+    };
+    let object_ref = if create_only {
+        state.storage_engine.put_staged_object_if_absent(&bucket, &key, staged, attributes).await?
+    } else {
+        state.storage_engine.put_staged_object(&bucket, &key, staged, attributes).await?
     };
     
-    if let Err(e) = state.consensus_manager.request_replication(
-        object_ref.id.clone(),
-        consensus::ReplicationOperation::Store,
-        metadata,
-        None, // Data will be read from storage by other nodes
-    ).await {
-        tracing::warn!("Failed to initiate replication for {}: {}", object_ref.id, e);
-        // Continue anyway - the object is stored locally
-    }
+    // Trigger replication via consensus
+    replicate_store(&state, &object_ref).await;
     
     Ok((
         StatusCode::OK,
         [
             ("etag", object_ref.etag.as_str()),
-            ("x-amz-version-id", &object_ref.version_id.to_string()),
+            ("x-amz-version-id", &storage::format_version_id(&object_ref.version_id)),
         ],
+        AppendHeaders(response_headers),
     ).into_response())
 }
 
+/// POST on an object: starts (`?uploads`) or completes (`?uploadId=`) a
+/// multipart upload.
+pub async fn post_object(
+    State(state): State<Arc<AppState>>,
+    Path((bucket, key)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    let action = policy::object_action(&Method::POST, &params);
+    policy::authorize(&state, &auth, action, &bucket, Some(&key), &params).await?;
+    verify_body_hash(&auth, &body)?;
+
+    if params.contains_key("uploads") {
+        multipart::create_multipart_upload(&state, &bucket, &key, &headers, &auth).await
+    } else if params.contains_key("uploadId") {
+        multipart::complete_multipart_upload(&state, &bucket, &key, &params, &headers, body).await
+    } else {
+        Err(ApiError::InvalidRequest("Unsupported POST operation on object".to_string()))
+    }
+}
+
 pub async fn get_object(
     State(state): State<Arc<AppState>>,
     Path((bucket, key)): Path<(String, String)>,
     Query(query): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
     headers: HeaderMap,
 ) -> ApiResult<Response> {
-    let _auth = extract_auth_info(&headers)?;
-    
-    let version_id = query.get("versionId")
-        .and_then(|v| Uuid::parse_str(v).ok());
-    
-    let object = state.storage_engine
-        .get_object(&bucket, &key, version_id).await
-        .map_err(|e| ApiError::Storage(e.to_string()))?;
+    let action = policy::object_action(&Method::GET, &query);
+    policy::authorize(&state, &auth, action, &bucket, Some(&key), &query).await?;
+
+    if query.contains_key("uploadId") {
+        return multipart::list_parts(&state, &bucket, &key, &query).await;
+    }
+    if query.contains_key("retention") {
+        return object_lock::get_object_retention(&state, &bucket, &key, &query).await;
+    }
+    if query.contains_key("legal-hold") {
+        return object_lock::get_object_legal_hold(&state, &bucket, &key, &query).await;
+    }
+    if query.contains_key("tagging") {
+        return tagging::get_object_tagging(&state, &bucket, &key, &query).await;
+    }
+    if query.contains_key("acl") {
+        return acl::get_object_acl(&state, &bucket, &key, &query, &auth).await;
+    }
+
+    let version_id = version_id_param(&query)?;
+    let customer_key = encryption::customer_key(&headers, encryption::OBJECT_KEY_PREFIX)?;
     
-    let object = object.ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+    let Some(record) = state.storage_engine.get_object_record(&bucket, &key, version_id).await? else {
+        return object_not_found(&state, &bucket, &key, version_id).await;
+    };
+    storage::check_customer_key(record.metadata.encryption.as_ref(), customer_key.as_ref())?;
+
+    if let Some(not_modified) = check_preconditions(&headers, &record.metadata.etag, record.metadata.created_at)? {
+        return Ok(not_modified);
+    }
+    let metadata = &record.metadata;
+
+    let range_header = headers.get("range").and_then(|h| h.to_str().ok());
+    let range = match (query.get("partNumber"), range_header) {
+        (Some(_), Some(_)) => {
+            return Err(ApiError::InvalidRequest(
+                "Cannot specify both Range header and partNumber query parameter".to_string()
+            ));
+        }
+        (Some(part_number), None) => part_range(metadata, part_number)?,
+        (None, Some(range)) => parse_range(range, metadata.size)?,
+        (None, None) => None,
+    };
     
     let mut response_headers = vec![
-        ("content-type", object.metadata.content_type.clone()),
-        ("content-length", object.metadata.size.to_string()),
-        ("etag", object.metadata.etag.clone()),
-        ("last-modified", object.metadata.created_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
-        ("x-amz-version-id", object.metadata.version_id.to_string()),
+        ("content-type".to_string(), metadata.content_type.clone()),
+        ("accept-ranges".to_string(), "bytes".to_string()),
+        ("etag".to_string(), metadata.etag.clone()),
+        ("last-modified".to_string(), metadata.created_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        ("x-amz-version-id".to_string(), storage::format_version_id(&metadata.version_id)),
     ];
+    response_headers.extend(object_lock::lock_headers(&metadata.lock));
+    response_headers.extend(tagging::tag_count_header(&metadata.tags));
+    response_headers.extend(encryption::encryption_headers(metadata.encryption.as_ref()));
     
     // Add custom metadata headers
-    for (key, value) in &object.metadata.custom_metadata {
-        response_headers.push((&format!("x-amz-meta-{}", key), value.clone()));
+    for (key, value) in &metadata.custom_metadata {
+        response_headers.push((format!("x-amz-meta-{}", key), value.clone()));
+    }
+
+    // The checksum covers the whole object, so ranged reads go without
+    if range.is_none() && checksum::checksum_mode_enabled(&headers) {
+        response_headers.extend(checksum::checksum_headers(record.checksum.client.as_ref()));
     }
+
+    if query.contains_key("partNumber") {
+        let parts_count = metadata.parts.as_ref().map_or(1, |parts| parts.len());
+        response_headers.push(("x-amz-mp-parts-count".to_string(), parts_count.to_string()));
+    }
+
+    let Some((offset, length)) = range else {
+        response_headers.push(("content-length".to_string(), metadata.size.to_string()));
+        let file = state.storage_engine.read_range(&record, 0, metadata.size, customer_key.as_ref()).await?;
+
+        return Ok((
+            StatusCode::OK,
+            AppendHeaders(response_headers),
+            Body::from_stream(ReaderStream::new(file)),
+        ).into_response());
+    };
+
+    response_headers.push(("content-length".to_string(), length.to_string()));
+    response_headers.push((
+        "content-range".to_string(),
+        format!("bytes {}-{}/{}", offset, offset + length - 1, metadata.size),
+    ));
+    let file = state.storage_engine.read_range(&record, offset, length, customer_key.as_ref()).await?;
     
     Ok((
-        StatusCode::OK,
-        response_headers,
-        object.data,
+        StatusCode::PARTIAL_CONTENT,
+        AppendHeaders(response_headers),
+        Body::from_stream(ReaderStream::new(file)),
     ).into_response())
 }
 
+/// Evaluates conditional request headers for GET and HEAD. Returns a 304
+/// response when the client's copy is current.
+fn check_preconditions(
+    headers: &HeaderMap,
+    etag: &str,
+    last_modified: chrono::DateTime<chrono::Utc>,
+) -> ApiResult<Option<Response>> {
+    if !evaluate_preconditions(headers, "", etag, last_modified)? {
+        return Ok(None);
+    }
+
+    Ok(Some((
+        StatusCode::NOT_MODIFIED,
+        [
+            ("etag", etag.to_string()),
+            ("last-modified", last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        ],
+    ).into_response()))
+}
+
+/// Evaluates `<prefix>if-match`, `<prefix>if-none-match` and the date
+/// conditions in the order RFC 9110 gives. Fails with `PreconditionFailed`
+/// when If-Match or If-Unmodified-Since does not hold, and returns whether
+/// If-None-Match or If-Modified-Since says the client's copy is current.
+pub(crate) fn evaluate_preconditions(
+    headers: &HeaderMap,
+    prefix: &str,
+    etag: &str,
+    last_modified: chrono::DateTime<chrono::Utc>,
+) -> ApiResult<bool> {
+    let header = |name: &str| {
+        headers.get(format!("{}{}", prefix, name).as_str()).and_then(|h| h.to_str().ok())
+    };
+    // HTTP dates only carry whole seconds
+    let last_modified_secs = last_modified.timestamp();
+
+    if let Some(if_match) = header("if-match") {
+        if !etag_matches(if_match, etag) {
+            return Err(ApiError::PreconditionFailed("If-Match did not match".to_string()));
+        }
+    } else if let Some(since) = header("if-unmodified-since").and_then(parse_http_date) {
+        if last_modified_secs > since {
+            return Err(ApiError::PreconditionFailed("Object was modified".to_string()));
+        }
+    }
+
+    let not_modified = if let Some(if_none_match) = header("if-none-match") {
+        etag_matches(if_none_match, etag)
+    } else if let Some(since) = header("if-modified-since").and_then(parse_http_date) {
+        last_modified_secs <= since
+    } else {
+        false
+    };
+
+    Ok(not_modified)
+}
+
+/// Whether an If-Match / If-None-Match list names `etag`. Weak validators
+/// compare equal to strong ones, as for GET.
+fn etag_matches(list: &str, etag: &str) -> bool {
+    let etag = etag.trim_matches('"');
+    list.split(',')
+        .map(|candidate| candidate.trim())
+        .any(|candidate| {
+            candidate == "*"
+                || candidate.trim_start_matches("W/").trim_matches('"') == etag
+        })
+}
+
+/// Parses an HTTP date into Unix seconds. Unparseable dates are ignored, as
+/// RFC 9110 requires.
+fn parse_http_date(value: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|date| date.timestamp())
+}
+
+/// Resolves a `Range` header against an object of `size` bytes into an
+/// offset and length. Forms we don't support (multiple ranges, other units)
+/// and malformed headers are ignored, which means serving the whole object.
+fn parse_range(header: &str, size: u64) -> ApiResult<Option<(u64, u64)>> {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    let Some((first, last)) = spec.split_once('-') else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+
+    let unsatisfiable = || ApiError::InvalidRange(
+        format!("The requested range is not satisfiable for an object of {} bytes", size)
+    );
+
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        // Suffix range: the final N bytes
+        let Ok(suffix) = last.parse::<u64>() else {
+            return Ok(None);
+        };
+        if suffix == 0 || size == 0 {
+            return Err(unsatisfiable());
+        }
+        let length = suffix.min(size);
+        return Ok(Some((size - length, length)));
+    }
+
+    let Ok(start) = first.parse::<u64>() else {
+        return Ok(None);
+    };
+    let end = if last.is_empty() {
+        u64::MAX
+    } else {
+        match last.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return Ok(None),
+        }
+    };
+
+    if start >= size {
+        return Err(unsatisfiable());
+    }
+    let end = end.min(size - 1);
+    Ok(Some((start, end - start + 1)))
+}
+
+/// Byte range of one part of a multipart object. Objects written with a
+/// single PUT have exactly one part.
+fn part_range(metadata: &storage::ObjectMetadata, part_number: &str) -> ApiResult<Option<(u64, u64)>> {
+    let part_number = part_number.parse::<usize>()
+        .ok()
+        .filter(|n| *n >= 1)
+        .ok_or_else(|| ApiError::InvalidRequest("Part number must be a positive integer".to_string()))?;
+
+    let single = [metadata.size];
+    let sizes = metadata.parts.as_deref().unwrap_or(&single);
+    if part_number > sizes.len() {
+        return Err(ApiError::InvalidPartNumber(
+            format!("The object has {} parts", sizes.len())
+        ));
+    }
+
+    let offset = sizes[..part_number - 1].iter().sum();
+    let length = sizes[part_number - 1];
+    Ok((length > 0).then_some((offset, length)))
+}
+
 pub async fn head_object(
     State(state): State<Arc<AppState>>,
     Path((bucket, key)): Path<(String, String)>,
     Query(query): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
     headers: HeaderMap,
 ) -> ApiResult<Response> {
-    let _auth = extract_auth_info(&headers)?;
-    
-    let version_id = query.get("versionId")
-        .and_then(|v| Uuid::parse_str(v).ok());
-    
-    let object_ref = state.storage_engine
-        .get_object_metadata(&bucket, &key, version_id).await
-        .map_err(|e| ApiError::Storage(e.to_string()))?;
+    let action = policy::object_action(&Method::HEAD, &query);
+    policy::authorize(&state, &auth, action, &bucket, Some(&key), &query).await?;
+
+    let version_id = version_id_param(&query)?;
+    let customer_key = encryption::customer_key(&headers, encryption::OBJECT_KEY_PREFIX)?;
     
-    let object_ref = object_ref.ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+    let Some(record) = state.storage_engine.get_object_record(&bucket, &key, version_id).await? else {
+        return object_not_found(&state, &bucket, &key, version_id).await;
+    };
+    let metadata = record.metadata;
+    storage::check_customer_key(metadata.encryption.as_ref(), customer_key.as_ref())?;
+
+    if let Some(not_modified) = check_preconditions(&headers, &metadata.etag, metadata.created_at)? {
+        return Ok(not_modified);
+    }
+
+    let mut response_headers = vec![
+        ("content-length".to_string(), metadata.size.to_string()),
+        ("etag".to_string(), metadata.etag.clone()),
+        ("last-modified".to_string(), metadata.created_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        ("x-amz-version-id".to_string(), storage::format_version_id(&metadata.version_id)),
+    ];
+    response_headers.extend(object_lock::lock_headers(&metadata.lock));
+    response_headers.extend(tagging::tag_count_header(&metadata.tags));
+    response_headers.extend(encryption::encryption_headers(metadata.encryption.as_ref()));
+    if checksum::checksum_mode_enabled(&headers) {
+        response_headers.extend(checksum::checksum_headers(record.checksum.client.as_ref()));
+    }
     
+    Ok((StatusCode::OK, AppendHeaders(response_headers)).into_response())
+}
+
+/// Error for a GET or HEAD that found no object version. When a delete
+/// marker is in the way, S3 says so in the headers, and asking for a
+/// marker by version ID is not allowed at all.
+async fn object_not_found(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    version_id: Option<storage::Version>,
+) -> ApiResult<Response> {
+    let Some(marker) = state.storage_engine.get_delete_marker(bucket, key, version_id).await? else {
+        return Err(ApiError::NoSuchKey(format!("{}:{}", bucket, key)));
+    };
+
+    let error = if version_id.is_some() {
+        ApiError::MethodNotAllowed("The specified method is not allowed against a delete marker".to_string())
+    } else {
+        ApiError::NoSuchKey(format!("{}:{}", bucket, key))
+    };
+
     Ok((
-        StatusCode::OK,
         [
-            ("content-length", object_ref.size.to_string()),
-            ("etag", object_ref.etag),
-            ("last-modified", object_ref.last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
-            ("x-amz-version-id", object_ref.version_id.to_string()),
+            ("x-amz-delete-marker", "true".to_string()),
+            ("x-amz-version-id", storage::format_version_id(&marker.version_id)),
         ],
+        error,
     ).into_response())
 }
 
@@ -282,55 +1103,38 @@ pub async fn delete_object(
     State(state): State<Arc<AppState>>,
     Path((bucket, key)): Path<(String, String)>,
     Query(query): Query<HashMap<String, String>>,
+    Extension(auth): Extension<AuthContext>,
     headers: HeaderMap,
 ) -> ApiResult<Response> {
-    let _auth = extract_auth_info(&headers)?;
-    
-    // Check if writes are enabled
-    let cluster_state = state.cluster_state.read().await;
-    if !cluster_state.is_write_enabled {
-        return Err(ApiError::InsufficientReplicas);
+    let action = policy::object_action(&Method::DELETE, &query);
+    policy::authorize(&state, &auth, action, &bucket, Some(&key), &query).await?;
+
+    if query.contains_key("uploadId") {
+        return multipart::abort_multipart_upload(&state, &bucket, &key, &query).await;
+    }
+    if query.contains_key("tagging") {
+        return tagging::delete_object_tagging(&state, &bucket, &key, &query).await;
     }
-    drop(cluster_state);
+
+    check_write_enabled(&state).await?;
     
-    let version_id = query.get("versionId")
-        .and_then(|v| Uuid::parse_str(v).ok());
+    let version_id = version_id_param(&query)?;
+    let bypass_governance = object_lock::bypass_governance(&headers, &auth);
     
-    let deleted = state.storage_engine
-        .delete_object(&bucket, &key, version_id).await
-        .map_err(|e| ApiError::Storage(e.to_string()))?;
-    
-    if !deleted {
-        return Err(ApiError::NoSuchKey(format!("{}:{}", bucket, key)));
-    }
+    let deleted = state.storage_engine.delete_object(&bucket, &key, version_id, bypass_governance).await?;
     
     // Trigger replication via consensus
-    let metadata = consensus::ObjectReplicationMetadata {
-        bucket: bucket.clone(),
-        key: key.clone(),
-        version_id: version_id.unwrap_or_else(|| Uuid::new_v4()),
-        size: 0,
-        checksum: String::new(),
-        target_nodes: vec![],
-    };
-    
-    if let Err(e) = state.consensus_manager.request_replication(
-        format!("{}:{}", bucket, key),
-        consensus::ReplicationOperation::Delete,
-        metadata,
-        None,
-    ).await {
-        tracing::warn!("Failed to initiate delete replication for {}:{}: {}", bucket, key, e);
+    replicate_delete(&state, &bucket, &key, deleted.version_id).await;
+
+    let mut headers = Vec::new();
+    if deleted.delete_marker {
+        headers.push(("x-amz-delete-marker", "true".to_string()));
+    }
+    if let Some(version_id) = &deleted.version_id {
+        headers.push(("x-amz-version-id", storage::format_version_id(version_id)));
     }
     
-    let delete_marker = version_id.is_none(); // Delete marker created when no version specified
-    let xml = xml::serialize_delete_result(&key, version_id.as_ref().map(|v| v.to_string().as_str()), delete_marker);
-    
-    Ok((
-        StatusCode::NO_CONTENT,
-        [("content-type", "application/xml")],
-        xml,
-    ).into_response())
+    Ok((StatusCode::NO_CONTENT, AppendHeaders(headers)).into_response())
 }
 
 // Health check endpoint
@@ -359,4 +1163,389 @@ pub async fn health_check(
         [("content-type", "application/json")],
         health_info.to_string(),
     ).into_response())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    const SIZE: u64 = 1000;
+
+    /// A single-node state storing under `dir`.
+    pub(crate) async fn test_state(dir: &std::path::Path) -> AppState {
+        let storage_path = dir.to_str().unwrap().to_string();
+        let config = consensus::Config {
+            node_ip: "127.0.0.1".parse().unwrap(),
+            port: 0,
+            peers: vec![],
+            storage_path: storage_path.clone(),
+            max_storage_size: 1 << 30,
+            replication_factor: 1,
+            consensus_timeout_ms: 1000,
+            heartbeat_interval_ms: 1000,
+        };
+        let consensus_state = consensus::ClusterState { active_nodes: vec![], total_replicas: 1, is_write_enabled: true };
+        AppState {
+            storage_engine: Arc::new(storage::StorageEngine::new(&storage_path, 1 << 30).await.unwrap()),
+            consensus_manager: Arc::new(
+                consensus::ConsensusManager::new(config, Arc::new(tokio::sync::RwLock::new(consensus_state))).await.unwrap()
+            ),
+            cluster_state: Arc::new(tokio::sync::RwLock::new(crate::ClusterState {
+                active_nodes: vec![],
+                total_replicas: 1,
+                is_write_enabled: true,
+            })),
+            credentials: HashMap::new(),
+            allow_anonymous: false,
+            base_domains: vec![],
+        }
+    }
+
+    pub(crate) fn admin() -> AuthContext {
+        AuthContext {
+            access_key: "admin".to_string(),
+            owner_id: "admin".to_string(),
+            authenticated: true,
+            is_admin: true,
+            ..AuthContext::anonymous()
+        }
+    }
+
+    pub(crate) async fn body_string(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn byte_ranges_are_resolved_against_the_object_size() {
+        assert_eq!(parse_range("bytes=0-9", SIZE).unwrap(), Some((0, 10)));
+        assert_eq!(parse_range("bytes=990-", SIZE).unwrap(), Some((990, 10)));
+        assert_eq!(parse_range(" bytes=100-100 ", SIZE).unwrap(), Some((100, 1)));
+        // the end is clamped to the last byte
+        assert_eq!(parse_range("bytes=500-5000", SIZE).unwrap(), Some((500, 500)));
+    }
+
+    #[test]
+    fn suffix_ranges_are_the_final_bytes() {
+        assert_eq!(parse_range("bytes=-10", SIZE).unwrap(), Some((990, 10)));
+        assert_eq!(parse_range("bytes=-5000", SIZE).unwrap(), Some((0, SIZE)));
+        assert!(matches!(parse_range("bytes=-0", SIZE), Err(ApiError::InvalidRange(_))));
+        assert!(matches!(parse_range("bytes=-10", 0), Err(ApiError::InvalidRange(_))));
+    }
+
+    #[test]
+    fn unsatisfiable_ranges_are_416() {
+        for range in ["bytes=1000-", "bytes=1000-1010", "bytes=5000-"] {
+            let err = parse_range(range, SIZE).unwrap_err();
+            assert_eq!(err.into_response().status(), StatusCode::RANGE_NOT_SATISFIABLE, "{}", range);
+        }
+        assert!(matches!(parse_range("bytes=0-", 0), Err(ApiError::InvalidRange(_))));
+    }
+
+    #[test]
+    fn unsupported_or_malformed_ranges_are_ignored() {
+        for range in ["items=0-9", "bytes=0-9,20-29", "bytes=9-0", "bytes=a-9", "bytes=-a", "bytes=5", "0-9"] {
+            assert_eq!(parse_range(range, SIZE).unwrap(), None, "{}", range);
+        }
+    }
+
+    #[test]
+    fn continuation_tokens_round_trip_markers() {
+        for marker in ["photos/2024/cat.jpg", "sp ace/ü", ""] {
+            let token = auth::hex_encode(marker.as_bytes());
+            assert_eq!(decode_continuation_token(&token).unwrap(), marker);
+        }
+        for token in ["not-hex", "abc", "ff"] {
+            assert!(matches!(decode_continuation_token(token), Err(ApiError::InvalidArgument(_))), "{}", token);
+        }
+    }
+
+    pub(crate) fn conditions(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn etags_match_weak_strong_lists_and_wildcards() {
+        let etag = "\"abc123\"";
+        for list in ["\"abc123\"", "abc123", "W/\"abc123\"", "\"x\", \"abc123\"", " \"x\" ,W/\"abc123\" ", "*"] {
+            assert!(etag_matches(list, etag), "{}", list);
+        }
+        for list in ["\"abc12\"", "\"x\", \"y\"", "", "W/\"other\""] {
+            assert!(!etag_matches(list, etag), "{}", list);
+        }
+    }
+
+    #[test]
+    fn http_dates_parse_to_unix_seconds() {
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(784111777));
+        assert_eq!(parse_http_date(" Sun, 06 Nov 1994 08:49:37 +0000 "), Some(784111777));
+        for value in ["", "yesterday", "1994-11-06T08:49:37Z", "Sunday, 06-Nov-94 08:49:37 GMT"] {
+            assert_eq!(parse_http_date(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn preconditions_follow_rfc_9110() {
+        let etag = "\"abc123\"";
+        let modified = chrono::DateTime::from_timestamp(784111777, 500_000_000).unwrap();
+        let (before, at, after) = ("Sun, 06 Nov 1994 08:49:36 GMT", "Sun, 06 Nov 1994 08:49:37 GMT", "Sun, 06 Nov 1994 08:49:38 GMT");
+        let evaluate = |pairs: &[(&'static str, &str)]| evaluate_preconditions(&conditions(pairs), "", etag, modified);
+
+        assert!(!evaluate(&[]).unwrap());
+        assert!(!evaluate(&[("if-match", etag)]).unwrap());
+        assert!(matches!(evaluate(&[("if-match", "\"other\"")]), Err(ApiError::PreconditionFailed(_))));
+        assert!(matches!(evaluate(&[("if-unmodified-since", before)]), Err(ApiError::PreconditionFailed(_))));
+        // Dates compare at whole seconds
+        assert!(!evaluate(&[("if-unmodified-since", at)]).unwrap());
+        // If-Match takes precedence over If-Unmodified-Since
+        assert!(!evaluate(&[("if-match", "*"), ("if-unmodified-since", before)]).unwrap());
+
+        assert!(evaluate(&[("if-none-match", etag)]).unwrap());
+        assert!(!evaluate(&[("if-none-match", "\"other\"")]).unwrap());
+        assert!(evaluate(&[("if-modified-since", at)]).unwrap());
+        assert!(evaluate(&[("if-modified-since", after)]).unwrap());
+        assert!(!evaluate(&[("if-modified-since", before)]).unwrap());
+        // If-None-Match takes precedence over If-Modified-Since
+        assert!(!evaluate(&[("if-none-match", "\"other\""), ("if-modified-since", after)]).unwrap());
+        // Unparseable dates are ignored
+        assert!(!evaluate(&[("if-unmodified-since", "garbage"), ("if-modified-since", "garbage")]).unwrap());
+
+        // Copy-source conditions are read from their own headers
+        let headers = conditions(&[("if-match", "\"other\""), ("x-amz-copy-source-if-none-match", etag)]);
+        assert!(evaluate_preconditions(&headers, "x-amz-copy-source-", etag, modified).unwrap());
+    }
+
+    #[test]
+    fn failed_preconditions_win_over_not_modified() {
+        let etag = "\"abc123\"";
+        let modified = chrono::DateTime::from_timestamp(784111777, 0).unwrap();
+
+        let headers = conditions(&[("if-match", "\"other\""), ("if-none-match", etag)]);
+        let err = check_preconditions(&headers, etag, modified).unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::PRECONDITION_FAILED);
+
+        let headers = conditions(&[("if-unmodified-since", "Sun, 06 Nov 1994 08:49:36 GMT"), ("if-none-match", etag)]);
+        assert!(matches!(check_preconditions(&headers, etag, modified), Err(ApiError::PreconditionFailed(_))));
+
+        let headers = conditions(&[("if-match", etag), ("if-none-match", etag)]);
+        let response = check_preconditions(&headers, etag, modified).unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()["etag"], etag);
+        assert_eq!(response.headers()["last-modified"], "Sun, 06 Nov 1994 08:49:37 GMT");
+
+        assert!(check_preconditions(&conditions(&[("if-none-match", "\"other\"")]), etag, modified).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn conditional_gets_return_304_or_412() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", Uuid::new_v4()));
+        let state = Arc::new(test_state(&dir).await);
+        state.storage_engine.create_bucket("docs", None, Some("admin")).await.unwrap();
+        let stored = state.storage_engine.put_object("docs", "a", Bytes::from_static(b"data"), None, HashMap::new()).await.unwrap();
+        let object = || Path(("docs".to_string(), "a".to_string()));
+        let get = |headers: HeaderMap| get_object(State(state.clone()), object(), Query(HashMap::new()), Extension(admin()), headers);
+
+        let response = get(conditions(&[("if-none-match", &stored.etag)])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let result = get(conditions(&[("if-match", "\"other\""), ("if-none-match", &stored.etag)])).await;
+        assert!(matches!(result, Err(ApiError::PreconditionFailed(_))));
+        let response = get(conditions(&[("if-match", &stored.etag)])).await.unwrap();
+        assert_eq!(body_string(response).await, "data");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn create_only_puts_fail_if_the_key_exists() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", Uuid::new_v4()));
+        let state = Arc::new(test_state(&dir).await);
+        state.storage_engine.create_bucket("docs", None, Some("admin")).await.unwrap();
+        let object = || Path(("docs".to_string(), "a".to_string()));
+        let put = |headers: HeaderMap, body: &'static str| {
+            put_object(State(state.clone()), object(), Query(HashMap::new()), Extension(admin()), headers, Body::from(body))
+        };
+
+        put(conditions(&[("if-none-match", "*")]), "first").await.unwrap();
+        let err = put(conditions(&[("if-none-match", "*")]), "second").await.unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::PRECONDITION_FAILED);
+        let result = put(conditions(&[("if-none-match", "\"abc\"")]), "second").await;
+        assert!(matches!(result, Err(ApiError::InvalidRequest(_))));
+
+        let record = state.storage_engine.get_object_record("docs", "a", None).await.unwrap().unwrap();
+        assert_eq!(record.metadata.size, 5);
+
+        // Of two concurrent create-only writes to a new key, one wins
+        let create = || put_object(
+            State(state.clone()),
+            Path(("docs".to_string(), "b".to_string())),
+            Query(HashMap::new()),
+            Extension(admin()),
+            conditions(&[("if-none-match", "*")]),
+            Body::from("data"),
+        );
+        let (first, second) = tokio::join!(create(), create());
+        assert_eq!(first.is_ok() as u32 + second.is_ok() as u32, 1);
+        for result in [first, second] {
+            assert!(matches!(result, Ok(_) | Err(ApiError::PreconditionFailed(_))));
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn quiet_deletes_report_only_errors() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", Uuid::new_v4()));
+        let state = test_state(&dir).await;
+        let engine = &state.storage_engine;
+        engine.create_bucket("docs", None, Some("admin")).await.unwrap();
+        for key in ["a", "b", "c"] {
+            engine.put_object("docs", key, Bytes::from_static(b"data"), None, HashMap::new()).await.unwrap();
+        }
+
+        let body = "<Delete><Quiet>true</Quiet><Object><Key>a</Key></Object><Object><Key>b</Key></Object>\
+                    <Object><Key>c</Key><VersionId>bogus</VersionId></Object></Delete>";
+        let response = delete_objects(&state, "docs", &admin(), false, Bytes::from(body)).await.unwrap();
+        let xml = body_string(response).await;
+        assert!(!xml.contains("<Deleted>"), "{}", xml);
+        assert!(xml.contains("<Key>c</Key>") && xml.contains("<Code>InvalidArgument</Code>"), "{}", xml);
+        for key in ["a", "b"] {
+            assert!(engine.get_object_record("docs", key, None).await.unwrap().is_none());
+        }
+
+        let body = "<Delete><Quiet>false</Quiet><Object><Key>c</Key></Object></Delete>";
+        let response = delete_objects(&state, "docs", &admin(), false, Bytes::from(body)).await.unwrap();
+        let xml = body_string(response).await;
+        assert!(xml.contains("<Deleted>\n    <Key>c</Key>"), "{}", xml);
+        assert!(!xml.contains("<Error>"), "{}", xml);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn auto_created_buckets_belong_to_their_creator() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", Uuid::new_v4()));
+        let mut state = test_state(&dir).await;
+        let engine = storage::StorageEngine::new(dir.join("auto").to_str().unwrap(), 1 << 30).await.unwrap();
+        state.storage_engine = Arc::new(engine.with_auto_create_buckets(true));
+        let state = Arc::new(state);
+
+        let alice = AuthContext { owner_id: "alice".to_string(), authenticated: true, ..AuthContext::anonymous() };
+        let carol = AuthContext { owner_id: "carol".to_string(), authenticated: true, ..AuthContext::anonymous() };
+        let object = || Path(("photos".to_string(), "cat.jpg".to_string()));
+
+        put_object(State(state.clone()), object(), Query(HashMap::new()), Extension(alice.clone()), HeaderMap::new(), Body::from("meow"))
+            .await.unwrap();
+        let bucket = state.storage_engine.get_bucket("photos").await.unwrap().unwrap();
+        assert_eq!(bucket.owner_id.as_deref(), Some("alice"));
+
+        let response = get_object(State(state.clone()), object(), Query(HashMap::new()), Extension(alice), HeaderMap::new())
+            .await.unwrap();
+        assert_eq!(body_string(response).await, "meow");
+
+        for auth in [carol, AuthContext::anonymous()] {
+            let result = get_object(State(state.clone()), object(), Query(HashMap::new()), Extension(auth.clone()), HeaderMap::new()).await;
+            assert!(matches!(result, Err(ApiError::AccessDenied(_))), "{}", auth.owner_id);
+            let result = put_object(State(state.clone()), object(), Query(HashMap::new()), Extension(auth.clone()), HeaderMap::new(), Body::from("woof")).await;
+            assert!(matches!(result, Err(ApiError::AccessDenied(_))), "{}", auth.owner_id);
+            let params = HashMap::from([("policy".to_string(), String::new())]);
+            let result = policy::authorize(&state, &auth, policy::bucket_action(&Method::PUT, &params), "photos", None, &params).await;
+            assert!(matches!(result, Err(ApiError::AccessDenied(_))), "{}", auth.owner_id);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn buckets_are_listed_to_their_owners() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", Uuid::new_v4()));
+        let state = Arc::new(test_state(&dir).await);
+        state.storage_engine.create_bucket("alice-photos", None, Some("alice")).await.unwrap();
+        state.storage_engine.create_bucket("bob-photos", None, Some("bob")).await.unwrap();
+        state.storage_engine.create_bucket("legacy", None, None).await.unwrap();
+
+        let alice = AuthContext { owner_id: "alice".to_string(), authenticated: true, ..AuthContext::anonymous() };
+        let carol = AuthContext { owner_id: "carol".to_string(), authenticated: true, ..AuthContext::anonymous() };
+        for (auth, expected) in [(alice, vec!["alice-photos", "legacy"]), (carol, vec!["legacy"])] {
+            let response = list_buckets(State(state.clone()), Extension(auth)).await.unwrap();
+            let xml = body_string(response).await;
+            let mut names: Vec<_> = xml.split("<Name>").skip(1)
+                .filter_map(|rest| rest.split_once("</Name>").map(|(name, _)| name))
+                .collect();
+            names.sort();
+            assert_eq!(names, expected, "{}", xml);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn buffered_bodies_must_match_the_signed_hash() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", Uuid::new_v4()));
+        let state = Arc::new(test_state(&dir).await);
+        let engine = &state.storage_engine;
+        engine.create_bucket("docs", None, Some("admin")).await.unwrap();
+        put_object(
+            State(state.clone()),
+            Path(("docs".to_string(), "a".to_string())),
+            Query(HashMap::new()),
+            Extension(admin()),
+            HeaderMap::new(),
+            Body::from("data"),
+        ).await.unwrap();
+
+        let signed = |body: &str| AuthContext {
+            payload_hash: Some(auth::hex_encode(&Sha256::digest(body))),
+            ..admin()
+        };
+        let enable = "<VersioningConfiguration><Status>Enabled</Status></VersioningConfiguration>";
+        let suspend = "<VersioningConfiguration><Status>Suspended</Status></VersioningConfiguration>";
+        let delete = "<Delete><Object><Key>a</Key></Object></Delete>";
+        let params = |name: &str| Query(HashMap::from([(name.to_string(), String::new())]));
+        let bucket = || Path("docs".to_string());
+
+        let result = put_bucket(State(state.clone()), bucket(), params("versioning"), Extension(signed(enable)), HeaderMap::new(), Bytes::from(suspend)).await;
+        assert!(matches!(result, Err(ApiError::ContentSha256Mismatch(_))));
+        let result = post_bucket(State(state.clone()), bucket(), params("delete"), Extension(signed("<Delete/>")), HeaderMap::new(), Bytes::from(delete)).await;
+        assert!(matches!(result, Err(ApiError::ContentSha256Mismatch(_))));
+        let result = put_object(
+            State(state.clone()),
+            Path(("docs".to_string(), "a".to_string())),
+            params("tagging"),
+            Extension(signed("<Tagging><TagSet/></Tagging>")),
+            HeaderMap::new(),
+            Body::from("<Tagging><TagSet><Tag><Key>k</Key><Value>v</Value></Tag></TagSet></Tagging>"),
+        ).await;
+        assert!(matches!(result, Err(ApiError::ContentSha256Mismatch(_))));
+
+        let upload = engine.create_multipart_upload("docs", "big", storage::ObjectAttributes::default(), None).await.unwrap();
+        let result = post_object(
+            State(state.clone()),
+            Path(("docs".to_string(), "big".to_string())),
+            Query(HashMap::from([("uploadId".to_string(), upload.upload_id.clone())])),
+            Extension(signed("<CompleteMultipartUpload/>")),
+            HeaderMap::new(),
+            Bytes::from("<CompleteMultipartUpload></CompleteMultipartUpload>"),
+        ).await;
+        assert!(matches!(result, Err(ApiError::ContentSha256Mismatch(_))));
+
+        // Nothing was changed by the tampered requests
+        assert_eq!(engine.get_bucket("docs").await.unwrap().unwrap().versioning, storage::VersioningStatus::Unversioned);
+        let record = engine.get_object_record("docs", "a", None).await.unwrap().unwrap();
+        assert!(record.metadata.tags.is_empty());
+        assert!(engine.get_multipart_upload(&upload.upload_id).await.unwrap().is_some());
+
+        // The signed body, or any body sent as UNSIGNED-PAYLOAD, is accepted
+        put_bucket(State(state.clone()), bucket(), params("versioning"), Extension(signed(enable)), HeaderMap::new(), Bytes::from(enable))
+            .await.unwrap();
+        let unsigned = AuthContext { payload_hash: Some("UNSIGNED-PAYLOAD".to_string()), ..admin() };
+        post_bucket(State(state.clone()), bucket(), params("delete"), Extension(unsigned), HeaderMap::new(), Bytes::from(delete))
+            .await.unwrap();
+        assert!(engine.get_object_record("docs", "a", None).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
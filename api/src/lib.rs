@@ -3,9 +3,24 @@ mod handlers;
 mod auth;
 mod xml;
 mod error;
+mod admin;
+mod multipart;
+mod copy;
+mod versioning;
+mod object_lock;
+mod lifecycle;
+mod tagging;
+mod policy;
+mod acl;
+mod cors;
+mod encryption;
+mod checksum;
+mod chunked;
+mod virtual_host;
 
 pub use server::Server;
 pub use error::{ApiError, ApiResult};
+pub use auth::presign_url;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -24,6 +39,13 @@ pub struct Config {
     pub replication_factor: usize,
     pub consensus_timeout_ms: u64,
     pub heartbeat_interval_ms: u64,
+    /// Node-level access key -> secret key pairs; these act as admin keys.
+    pub credentials: HashMap<String, String>,
+    /// Whether unsigned requests are served at all.
+    pub allow_anonymous: bool,
+    /// Domains under which `<bucket>.<domain>` hosts address a bucket
+    /// (virtual-hosted style), e.g. `s3.example.com`.
+    pub base_domains: Vec<String>,
 }
 
 impl Config {
@@ -134,6 +156,42 @@ pub struct DeleteObjectResponse {
     pub delete_marker: Option<bool>,
 }
 
+/// A parsed DeleteObjects request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteRequest {
+    pub objects: Vec<ObjectIdentifier>,
+    /// Report only the keys that could not be deleted.
+    pub quiet: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectIdentifier {
+    pub key: String,
+    pub version_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteObjectsResponse {
+    pub deleted: Vec<DeletedObject>,
+    pub errors: Vec<DeleteError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedObject {
+    pub key: String,
+    pub version_id: Option<String>,
+    pub delete_marker: bool,
+    pub delete_marker_version_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteError {
+    pub key: String,
+    pub version_id: Option<String>,
+    pub code: String,
+    pub message: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeadObjectResponse {
     pub content_type: String,
@@ -0,0 +1,44 @@
+use axum::{
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::{ApiError, ApiResult};
+use crate::handlers::{self, AppState};
+use crate::xml;
+
+pub async fn get_bucket_lifecycle(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let lifecycle = bucket.lifecycle.ok_or_else(|| ApiError::NoSuchLifecycleConfiguration(
+        "The lifecycle configuration does not exist".to_string()
+    ))?;
+
+    let xml = xml::serialize_lifecycle_configuration(&lifecycle);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
+
+pub async fn put_bucket_lifecycle(state: &AppState, bucket: &str, body: Bytes) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let lifecycle = xml::parse_lifecycle_configuration(handlers::body_text(&body)?)?;
+
+    state.storage_engine.set_bucket_lifecycle(bucket, Some(lifecycle)).await?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+pub async fn delete_bucket_lifecycle(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    state.storage_engine.set_bucket_lifecycle(bucket, None).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -0,0 +1,217 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, StatusCode},
+    response::{AppendHeaders, IntoResponse, Response},
+};
+use std::collections::HashMap;
+
+use crate::{ApiError, ApiResult};
+use crate::acl;
+use crate::checksum;
+use crate::chunked;
+use crate::encryption;
+use crate::auth::{self, AuthContext};
+use crate::handlers::{self, AppState};
+use crate::object_lock;
+use crate::tagging;
+use crate::xml;
+
+/// Parses a page size parameter such as `max-parts`, capped at 1000.
+fn page_size(params: &HashMap<String, String>, name: &str) -> ApiResult<usize> {
+    match params.get(name) {
+        Some(value) => Ok(value.parse::<usize>()
+            .map_err(|_| ApiError::InvalidArgument(format!("{} must be a non-negative integer", name)))?
+            .min(1000)),
+        None => Ok(1000),
+    }
+}
+
+fn upload_id(params: &HashMap<String, String>) -> ApiResult<&str> {
+    params.get("uploadId")
+        .map(|s| s.as_str())
+        .ok_or_else(|| ApiError::InvalidRequest("Missing uploadId".to_string()))
+}
+
+/// Looks up an upload and checks it belongs to `bucket/key`.
+async fn find_upload(state: &AppState, bucket: &str, key: &str, upload_id: &str) -> ApiResult<storage::MultipartUpload> {
+    state.storage_engine.get_multipart_upload(upload_id).await?
+        .filter(|u| u.bucket == bucket && u.key == key)
+        .ok_or_else(|| ApiError::NoSuchUpload(upload_id.to_string()))
+}
+
+pub async fn create_multipart_upload(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    headers: &HeaderMap,
+    auth: &AuthContext,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let (owner_id, acl) = acl::requested_acl(headers, auth)?;
+    let content_type = headers.get("content-type")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+
+    let attributes = storage::ObjectAttributes {
+        content_type,
+        custom_metadata: handlers::custom_metadata(headers),
+        lock: object_lock::requested_lock(headers)?,
+        tags: tagging::requested_tags(headers)?,
+        owner_id: Some(owner_id),
+        acl: Some(acl),
+    };
+    let sse = encryption::requested_encryption(state, bucket, headers).await?;
+
+    let upload = state.storage_engine.create_multipart_upload(bucket, key, attributes, sse.as_ref()).await?;
+
+    let xml = xml::serialize_initiate_multipart_upload(bucket, key, &upload.upload_id);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        AppendHeaders(encryption::encryption_headers(upload.encryption.as_ref())),
+        xml,
+    ).into_response())
+}
+
+/// Stores a part. Parts of an SSE-C upload must come with its customer key.
+pub async fn upload_part(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+    auth: &AuthContext,
+    body: Body,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let upload_id = upload_id(params)?;
+    let part_number = params.get("partNumber")
+        .and_then(|n| n.parse::<u32>().ok())
+        .ok_or_else(|| ApiError::InvalidRequest("Missing or invalid partNumber".to_string()))?;
+
+    let upload = find_upload(state, bucket, key, upload_id).await?;
+    let customer_key = encryption::customer_key(headers, encryption::OBJECT_KEY_PREFIX)?;
+    let checksums = checksum::ExpectedChecksums::from_headers(headers)?;
+
+    let (reader, trailers) = chunked::body_reader(auth, headers, body)?;
+
+    let staged = state.storage_engine
+        .stage_part_data(upload_id, reader, customer_key.as_ref(), checksums.digests()).await?;
+    handlers::verify_payload_hash(auth, &staged.checksum().sha256)?;
+    checksums.verify(&staged, &trailers)?;
+    let checksum_headers = checksum::checksum_headers(staged.checksum().client.as_ref());
+    let part = state.storage_engine.upload_part(upload_id, part_number, staged).await?;
+
+    Ok((
+        StatusCode::OK,
+        [("etag", part.etag)],
+        AppendHeaders(encryption::encryption_headers(upload.encryption.as_ref())),
+        AppendHeaders(checksum_headers),
+    ).into_response())
+}
+
+pub async fn complete_multipart_upload(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let upload_id = upload_id(params)?;
+    let upload = find_upload(state, bucket, key, upload_id).await?;
+    let customer_key = encryption::customer_key(headers, encryption::OBJECT_KEY_PREFIX)?;
+
+    let body = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::XmlError("Request body is not valid UTF-8".to_string()))?;
+    let parts = xml::parse_complete_multipart_upload(body)?;
+
+    let object_ref = state.storage_engine.complete_multipart_upload(upload_id, &parts, customer_key.as_ref()).await?;
+
+    handlers::replicate_store(state, &object_ref).await;
+
+    let location = format!("/{}/{}", bucket, auth::uri_encode(key, false));
+    let xml = xml::serialize_complete_multipart_upload(&location, bucket, key, &object_ref.etag);
+
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", "application/xml".to_string()),
+            ("x-amz-version-id", storage::format_version_id(&object_ref.version_id)),
+        ],
+        AppendHeaders(encryption::encryption_headers(upload.encryption.as_ref())),
+        xml,
+    ).into_response())
+}
+
+pub async fn abort_multipart_upload(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let upload_id = upload_id(params)?;
+    find_upload(state, bucket, key, upload_id).await?;
+
+    state.storage_engine.abort_multipart_upload(upload_id).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn list_parts(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    let upload_id = upload_id(params)?;
+    let upload = find_upload(state, bucket, key, upload_id).await?;
+
+    let part_number_marker = match params.get("part-number-marker").filter(|m| !m.is_empty()) {
+        Some(marker) => marker.parse::<u32>()
+            .map_err(|_| ApiError::InvalidArgument("part-number-marker must be a non-negative integer".to_string()))?,
+        None => 0,
+    };
+    let max_parts = page_size(params, "max-parts")?;
+
+    let page = state.storage_engine.list_parts(upload_id, part_number_marker, max_parts).await?;
+    let xml = xml::serialize_list_parts(&upload, &page, part_number_marker, max_parts);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
+
+pub async fn list_multipart_uploads(
+    state: &AppState,
+    bucket: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    if !state.storage_engine.bucket_exists(bucket).await? {
+        return Err(ApiError::NoSuchBucket(bucket.to_string()));
+    }
+
+    let prefix = params.get("prefix").map(|s| s.as_str());
+    let key_marker = params.get("key-marker").map(|k| k.as_str()).filter(|k| !k.is_empty());
+    let upload_id_marker = params.get("upload-id-marker").map(|u| u.as_str()).filter(|u| !u.is_empty());
+    let max_uploads = page_size(params, "max-uploads")?;
+
+    let page = state.storage_engine
+        .list_multipart_uploads(bucket, prefix, key_marker, upload_id_marker, max_uploads).await?;
+    let xml = xml::serialize_list_multipart_uploads(bucket, prefix, key_marker, upload_id_marker, max_uploads, &page);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
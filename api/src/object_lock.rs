@@ -0,0 +1,187 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+
+use crate::{ApiError, ApiResult};
+use crate::auth::AuthContext;
+use crate::handlers::{self, AppState};
+use crate::xml;
+
+/// Reads the Object Lock settings a PUT, copy or multipart upload asks
+/// for: `x-amz-object-lock-mode` with `x-amz-object-lock-retain-until-date`,
+/// and `x-amz-object-lock-legal-hold`.
+pub(crate) fn requested_lock(headers: &HeaderMap) -> ApiResult<storage::ObjectLock> {
+    let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok()).map(str::trim);
+
+    let retention = match (header("x-amz-object-lock-mode"), header("x-amz-object-lock-retain-until-date")) {
+        (None, None) => None,
+        (Some(mode), Some(retain_until)) => Some(storage::Retention {
+            mode: storage::RetentionMode::parse(mode)
+                .ok_or_else(|| ApiError::InvalidArgument("Unknown wormMode directive".to_string()))?,
+            retain_until: xml::parse_timestamp(retain_until)
+                .ok_or_else(|| ApiError::InvalidArgument(
+                    "The retain until date must be provided in ISO 8601 format".to_string()
+                ))?,
+        }),
+        _ => {
+            return Err(ApiError::InvalidArgument(
+                "x-amz-object-lock-retain-until-date and x-amz-object-lock-mode must both be supplied".to_string()
+            ));
+        }
+    };
+
+    let legal_hold = match header("x-amz-object-lock-legal-hold") {
+        None | Some("OFF") => false,
+        Some("ON") => true,
+        Some(_) => {
+            return Err(ApiError::InvalidArgument("Legal Hold must be either of 'ON' or 'OFF'".to_string()));
+        }
+    };
+
+    Ok(storage::ObjectLock { retention, legal_hold })
+}
+
+/// Whether a request may lift governance retention: it must ask to with
+/// `x-amz-bypass-governance-retention` and be signed with an admin key.
+pub(crate) fn bypass_governance(headers: &HeaderMap, auth: &AuthContext) -> bool {
+    let requested = headers.get("x-amz-bypass-governance-retention")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"));
+
+    requested && auth.is_admin
+}
+
+/// Response headers describing the lock on an object version.
+pub(crate) fn lock_headers(lock: &storage::ObjectLock) -> Vec<(String, String)> {
+    let mut headers = Vec::new();
+    if let Some(retention) = &lock.retention {
+        headers.push(("x-amz-object-lock-mode".to_string(), retention.mode.as_str().to_string()));
+        headers.push((
+            "x-amz-object-lock-retain-until-date".to_string(),
+            retention.retain_until.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        ));
+    }
+    if lock.legal_hold {
+        headers.push(("x-amz-object-lock-legal-hold".to_string(), "ON".to_string()));
+    }
+    headers
+}
+
+pub async fn get_object_lock_configuration(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let config = bucket.object_lock.ok_or_else(|| ApiError::ObjectLockConfigurationNotFound(
+        "Object Lock configuration does not exist for this bucket".to_string()
+    ))?;
+
+    Ok(xml_response(xml::serialize_object_lock_configuration(&config)))
+}
+
+pub async fn put_object_lock_configuration(state: &AppState, bucket: &str, body: Bytes) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let config = xml::parse_object_lock_configuration(handlers::body_text(&body)?)?;
+    state.storage_engine.set_object_lock_configuration(bucket, config).await?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+pub async fn get_object_retention(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    let record = find_version(state, bucket, key, params).await?;
+
+    let retention = record.metadata.lock.retention.ok_or_else(|| ApiError::NoSuchObjectLockConfiguration(
+        "The specified object does not have a ObjectLock configuration".to_string()
+    ))?;
+
+    Ok(xml_response(xml::serialize_retention(&retention)))
+}
+
+pub async fn put_object_retention(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+    auth: &AuthContext,
+    body: Body,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let version_id = handlers::version_id_param(params)?;
+    let body = handlers::read_small_body(auth, body).await?;
+    let retention = xml::parse_retention(handlers::body_text(&body)?)?;
+
+    state.storage_engine
+        .set_object_retention(bucket, key, version_id, retention, bypass_governance(headers, auth)).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+pub async fn get_object_legal_hold(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    let record = find_version(state, bucket, key, params).await?;
+
+    Ok(xml_response(xml::serialize_legal_hold(record.metadata.lock.legal_hold)))
+}
+
+pub async fn put_object_legal_hold(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    auth: &AuthContext,
+    body: Body,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let version_id = handlers::version_id_param(params)?;
+    let body = handlers::read_small_body(auth, body).await?;
+    let legal_hold = xml::parse_legal_hold(handlers::body_text(&body)?)?;
+
+    state.storage_engine
+        .set_object_legal_hold(bucket, key, version_id, legal_hold).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+/// The version a retention or legal hold request names, on a bucket with
+/// Object Lock enabled.
+async fn find_version(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<storage::ObjectRecord> {
+    let config = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+    if config.object_lock.is_none() {
+        return Err(ApiError::InvalidRequest("Bucket is missing Object Lock Configuration".to_string()));
+    }
+
+    let version_id = handlers::version_id_param(params)?;
+    state.storage_engine.get_object_record(bucket, key, version_id).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))
+}
+
+pub(crate) fn xml_response(xml: String) -> Response {
+    (
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response()
+}
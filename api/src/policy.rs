@@ -0,0 +1,218 @@
+use axum::{
+    body::Bytes,
+    http::{Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use std::collections::HashMap;
+
+use crate::{ApiError, ApiResult};
+use crate::acl;
+use crate::auth::AuthContext;
+use crate::handlers::{self, AppState};
+
+/// Query parameters exposed to policies as `s3:` condition keys.
+const CONDITION_PARAMS: &[(&str, &str)] = &[
+    ("prefix", "s3:prefix"),
+    ("delimiter", "s3:delimiter"),
+    ("max-keys", "s3:max-keys"),
+    ("versionId", "s3:versionid"),
+];
+
+/// Actions the bucket owner may always perform, so that a bad policy can
+/// still be replaced.
+const OWNER_ACTIONS: &[&str] = &["s3:GetBucketPolicy", "s3:PutBucketPolicy", "s3:DeleteBucketPolicy"];
+
+/// The S3 action a request on a bucket performs, chosen by method and
+/// sub-resource.
+pub(crate) fn bucket_action(method: &Method, params: &HashMap<String, String>) -> &'static str {
+    let has = |name: &str| params.contains_key(name);
+
+    match *method {
+        Method::PUT if has("versioning") => "s3:PutBucketVersioning",
+        Method::PUT if has("object-lock") => "s3:PutBucketObjectLockConfiguration",
+        Method::PUT if has("lifecycle") => "s3:PutLifecycleConfiguration",
+        Method::PUT if has("policy") => "s3:PutBucketPolicy",
+        Method::PUT if has("acl") => "s3:PutBucketAcl",
+        Method::PUT if has("cors") => "s3:PutBucketCORS",
+        Method::PUT if has("encryption") => "s3:PutEncryptionConfiguration",
+        Method::PUT => "s3:CreateBucket",
+        Method::DELETE if has("lifecycle") => "s3:PutLifecycleConfiguration",
+        Method::DELETE if has("policy") => "s3:DeleteBucketPolicy",
+        Method::DELETE if has("cors") => "s3:PutBucketCORS",
+        Method::DELETE if has("encryption") => "s3:PutEncryptionConfiguration",
+        Method::DELETE => "s3:DeleteBucket",
+        _ if has("uploads") => "s3:ListBucketMultipartUploads",
+        _ if has("versions") => "s3:ListBucketVersions",
+        _ if has("location") => "s3:GetBucketLocation",
+        _ if has("versioning") => "s3:GetBucketVersioning",
+        _ if has("object-lock") => "s3:GetBucketObjectLockConfiguration",
+        _ if has("lifecycle") => "s3:GetLifecycleConfiguration",
+        _ if has("policy") => "s3:GetBucketPolicy",
+        _ if has("acl") => "s3:GetBucketAcl",
+        _ if has("cors") => "s3:GetBucketCORS",
+        _ if has("encryption") => "s3:GetEncryptionConfiguration",
+        _ => "s3:ListBucket",
+    }
+}
+
+/// The S3 action a request on an object performs. Requests naming a
+/// version use the `...Version` form of the action where S3 has one.
+pub(crate) fn object_action(method: &Method, params: &HashMap<String, String>) -> &'static str {
+    let has = |name: &str| params.contains_key(name);
+    let versioned = |plain, version| if has("versionId") { version } else { plain };
+
+    match *method {
+        Method::PUT | Method::POST if has("retention") => "s3:PutObjectRetention",
+        Method::PUT | Method::POST if has("legal-hold") => "s3:PutObjectLegalHold",
+        Method::PUT | Method::POST if has("tagging") => versioned("s3:PutObjectTagging", "s3:PutObjectVersionTagging"),
+        Method::PUT | Method::POST if has("acl") => versioned("s3:PutObjectAcl", "s3:PutObjectVersionAcl"),
+        Method::PUT | Method::POST => "s3:PutObject",
+        Method::DELETE if has("uploadId") => "s3:AbortMultipartUpload",
+        Method::DELETE if has("tagging") => versioned("s3:DeleteObjectTagging", "s3:DeleteObjectVersionTagging"),
+        Method::DELETE => versioned("s3:DeleteObject", "s3:DeleteObjectVersion"),
+        _ if has("uploadId") => "s3:ListMultipartUploadParts",
+        _ if has("retention") => "s3:GetObjectRetention",
+        _ if has("legal-hold") => "s3:GetObjectLegalHold",
+        _ if has("tagging") => versioned("s3:GetObjectTagging", "s3:GetObjectVersionTagging"),
+        _ if has("acl") => versioned("s3:GetObjectAcl", "s3:GetObjectVersionAcl"),
+        _ => versioned("s3:GetObject", "s3:GetObjectVersion"),
+    }
+}
+
+/// Checks that the bucket policy and ACLs let `auth` perform `action` on
+/// `bucket`, or on `key` within it.
+pub(crate) async fn authorize(
+    state: &AppState,
+    auth: &AuthContext,
+    action: &str,
+    bucket: &str,
+    key: Option<&str>,
+    params: &HashMap<String, String>,
+) -> ApiResult<()> {
+    if auth.is_admin {
+        return Ok(());
+    }
+
+    let config = state.storage_engine.get_bucket(bucket).await?;
+    check(state, config.as_ref(), auth, action, bucket, key, params).await
+}
+
+/// `authorize` against an already loaded bucket. A missing bucket is let
+/// through so the handler can report it.
+///
+/// An explicit Deny always wins and an Allow grants access. Otherwise the
+/// bucket and object ACLs decide, which leave the owner in control.
+pub(crate) async fn check(
+    state: &AppState,
+    config: Option<&storage::Bucket>,
+    auth: &AuthContext,
+    action: &str,
+    bucket: &str,
+    key: Option<&str>,
+    params: &HashMap<String, String>,
+) -> ApiResult<()> {
+    if auth.is_admin {
+        return Ok(());
+    }
+    let Some(config) = config else {
+        return Ok(());
+    };
+
+    // Buckets created before owners were recorded belong to everyone
+    let is_owner = config.owner_id.as_deref().is_none_or(|owner| owner == auth.owner_id);
+    if is_owner && OWNER_ACTIONS.contains(&action) {
+        return Ok(());
+    }
+
+    if let Some(policy) = &config.policy {
+        let request = policy_request(auth, action, bucket, key, params);
+        match policy.evaluate(&request) {
+            Some(storage::PolicyEffect::Deny) => {
+                return Err(ApiError::AccessDenied("Access Denied by bucket policy".to_string()));
+            }
+            Some(storage::PolicyEffect::Allow) => return Ok(()),
+            None => {}
+        }
+    }
+
+    acl::check(state, config, auth, action, key, params).await
+}
+
+fn policy_request<'a>(
+    auth: &AuthContext,
+    action: &'a str,
+    bucket: &str,
+    key: Option<&str>,
+    params: &HashMap<String, String>,
+) -> storage::PolicyRequest<'a> {
+    let principals = if auth.authenticated {
+        vec![
+            auth.owner_id.clone(),
+            auth.access_key.clone(),
+            format!("arn:aws:iam::{}:root", auth.owner_id),
+        ]
+    } else {
+        Vec::new()
+    };
+
+    let resource = match key {
+        Some(key) => format!("arn:aws:s3:::{}/{}", bucket, key),
+        None => format!("arn:aws:s3:::{}", bucket),
+    };
+
+    let now = Utc::now();
+    let mut conditions = HashMap::from([
+        ("aws:securetransport".to_string(), auth.secure_transport.to_string()),
+        ("aws:currenttime".to_string(), now.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+        ("aws:epochtime".to_string(), now.timestamp().to_string()),
+    ]);
+    if let Some(ip) = auth.source_ip {
+        conditions.insert("aws:sourceip".to_string(), ip.to_string());
+    }
+    if auth.authenticated {
+        conditions.insert("aws:userid".to_string(), auth.owner_id.clone());
+    }
+    for (param, key) in CONDITION_PARAMS {
+        if let Some(value) = params.get(*param) {
+            conditions.insert(key.to_string(), value.clone());
+        }
+    }
+
+    storage::PolicyRequest { principals, action, resource, conditions }
+}
+
+pub async fn get_bucket_policy(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let policy = bucket.policy.ok_or_else(|| ApiError::NoSuchBucketPolicy(
+        "The bucket policy does not exist".to_string()
+    ))?;
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/json")],
+        policy.document().to_string(),
+    ).into_response())
+}
+
+pub async fn put_bucket_policy(state: &AppState, bucket: &str, body: Bytes) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let document = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::MalformedPolicy("Policies must be valid UTF-8".to_string()))?;
+    let policy = storage::BucketPolicy::parse(document)?;
+
+    state.storage_engine.set_bucket_policy(bucket, Some(policy)).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn delete_bucket_policy(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    state.storage_engine.set_bucket_policy(bucket, None).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
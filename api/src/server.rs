@@ -1,19 +1,27 @@
 use axum::{
-    routing::{get, put, post, delete, head},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{get, put, post, delete, head, options},
     Router,
-    extract::State,
-};
-use tower::ServiceBuilder;
-use tower_http::{
-    cors::CorsLayer,
-    trace::TraceLayer,
+    ServiceExt,
 };
+use tower::{Layer, ServiceBuilder};
+use tower_http::trace::TraceLayer;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 
 use crate::{ApiResult, ApiError};
+use crate::admin;
+use crate::auth;
+use crate::cors;
+use crate::virtual_host;
 use crate::handlers::{AppState, *};
 
+/// Cap on request bodies buffered in memory, such as XML documents. Object
+/// data is streamed to disk and is not subject to it.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
 pub struct Server {
     config: crate::Config,
     app_state: Arc<AppState>,
@@ -30,6 +38,12 @@ impl Server {
             storage_engine,
             consensus_manager,
             cluster_state,
+            credentials: config.credentials.clone(),
+            allow_anonymous: config.allow_anonymous,
+            base_domains: config.base_domains.iter()
+                .map(|domain| domain.trim().trim_matches('.').to_ascii_lowercase())
+                .filter(|domain| !domain.is_empty())
+                .collect(),
         });
 
         Ok(Self {
@@ -39,7 +53,10 @@ impl Server {
     }
 
     pub async fn start(&self) -> ApiResult<()> {
-        let app = self.create_router();
+        // Virtual-hosted requests are rewritten before routing, so this
+        // wraps the router rather than being one of its layers
+        let app = middleware::from_fn_with_state(self.app_state.clone(), virtual_host::route)
+            .layer(create_router(self.app_state.clone()));
         
         let addr = self.config.bind_address();
         tracing::info!("Starting API server on {}", addr);
@@ -47,41 +64,115 @@ impl Server {
         let listener = TcpListener::bind(&addr).await
             .map_err(|e| ApiError::InternalError(format!("Failed to bind to {}: {}", addr, e)))?;
         
-        axum::serve(listener, app).await
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
             .map_err(|e| ApiError::InternalError(format!("Server error: {}", e)))?;
         
         Ok(())
     }
+}
 
-    fn create_router(&self) -> Router {
-        let api_routes = Router::new()
-            // Bucket operations
-            .route("/", get(list_buckets))
-            .route("/:bucket", put(create_bucket))
-            .route("/:bucket", get(list_objects_v2))
-            
-            // Object operations
-            .route("/:bucket/:key", put(put_object))
-            .route("/:bucket/:key", get(get_object))
-            .route("/:bucket/:key", head(head_object))
-            .route("/:bucket/:key", delete(delete_object))
-            
-            // Health check
-            .route("/health", get(health_check))
-            
-            .with_state(self.app_state.clone());
-
-        Router::new()
-            .nest("/", api_routes)
-            .layer(
-                ServiceBuilder::new()
-                    .layer(TraceLayer::new_for_http())
-                    .layer(CorsLayer::permissive())
-            )
-            .fallback(not_found)
-    }
+/// The S3 API routes over `state`, with authentication, CORS and the body
+/// limit applied.
+fn create_router(state: Arc<AppState>) -> Router {
+    let api_routes = Router::new()
+        // Bucket operations
+        .route("/", get(list_buckets))
+        .route("/:bucket", put(put_bucket))
+        .route("/:bucket", get(get_bucket))
+        .route("/:bucket", post(post_bucket))
+        .route("/:bucket", head(head_bucket))
+        .route("/:bucket", delete(delete_bucket))
+        
+        // Object operations; keys run to the end of the path, slashes
+        // included
+        .route("/:bucket/*key", put(put_object))
+        .route("/:bucket/*key", get(get_object))
+        .route("/:bucket/*key", head(head_object))
+        .route("/:bucket/*key", delete(delete_object))
+        .route("/:bucket/*key", post(post_object))
+        
+        // Access key management
+        .route("/_admin/keys", get(admin::list_keys).post(admin::create_key))
+        .route("/_admin/keys/:access_key", delete(admin::revoke_key))
+        .route("/_admin/keys/:access_key/rotate", post(admin::rotate_key))
+        .route("/_admin/presign", get(admin::presign))
+        
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::authenticate))
+        
+        // CORS preflights are unsigned and answered from bucket rules
+        .route("/:bucket", options(cors::preflight_bucket))
+        .route("/:bucket/*key", options(cors::preflight_object))
+        
+        // Health check
+        .route("/health", get(health_check))
+        .with_state(state.clone());
+
+    Router::new()
+        .merge(api_routes)
+        .layer(
+            ServiceBuilder::new()
+                .layer(TraceLayer::new_for_http())
+                .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+                .layer(middleware::from_fn_with_state(state.clone(), cors::apply))
+        )
+        .fallback(not_found)
 }
 
 async fn not_found() -> ApiResult<axum::response::Response> {
     Err(ApiError::InvalidRequest("Not found".to_string()))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::tests::{body_string, test_state};
+    use axum::{body::Body, http::{Request, StatusCode}};
+    use tower::ServiceExt as _;
+
+    async fn send(router: &Router, method: &str, uri: &str, body: &'static str) -> axum::response::Response {
+        let request = Request::builder().method(method).uri(uri).body(Body::from(body)).unwrap();
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn object_keys_are_taken_from_the_whole_decoded_path() {
+        let dir = std::env::temp_dir().join(format!("server-{}", uuid::Uuid::new_v4()));
+        let mut state = test_state(&dir).await;
+        state.allow_anonymous = true;
+        // A bucket without an owner is open to anonymous callers
+        state.storage_engine.create_bucket("docs", None, None).await.unwrap();
+        let state = Arc::new(state);
+        let router = create_router(state.clone());
+
+        for (uri, key) in [
+            ("/docs/a/b/c.txt", "a/b/c.txt"),
+            ("/docs/dir/", "dir/"),
+            ("/docs/a%2Fb", "a/b"),
+            ("/docs/%C3%BC", "ü"),
+        ] {
+            let response = send(&router, "PUT", uri, "data").await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert!(state.storage_engine.get_object_record("docs", key, None).await.unwrap().is_some(), "{}", uri);
+
+            let response = send(&router, "GET", uri, "").await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(body_string(response).await, "data", "{}", uri);
+        }
+
+        let response = send(&router, "GET", "/docs?list-type=2", "").await;
+        let xml = body_string(response).await;
+        for key in ["a/b", "a/b/c.txt", "dir/", "ü"] {
+            assert!(xml.contains(&format!("<Key>{}</Key>", key)), "{}", xml);
+        }
+        let response = send(&router, "GET", "/docs?list-type=1", "").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(body_string(response).await.contains("<Code>InvalidArgument</Code>"));
+
+        let uri = format!("/docs/{}", "k".repeat(1025));
+        let response = send(&router, "PUT", &uri, "data").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(body_string(response).await.contains("<Code>KeyTooLongError</Code>"));
+        assert_eq!(send(&router, "PUT", &format!("/docs/{}", "k".repeat(1024)), "data").await.status(), StatusCode::OK);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use axum::{
+    body::Body,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+
+use crate::{ApiError, ApiResult};
+use crate::auth::{self, AuthContext};
+use crate::handlers::{self, AppState};
+use crate::object_lock;
+use crate::xml;
+
+/// Parses tags in the URL query form used by `x-amz-tagging`:
+/// `key1=value1&key2=value2`, with keys and values URL-encoded.
+pub(crate) fn parse_tag_query(value: &str) -> storage::TagSet {
+    value.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (auth::percent_decode(key), auth::percent_decode(value))
+        })
+        .collect()
+}
+
+/// Tags a PUT, copy or multipart upload asks for in `x-amz-tagging`.
+pub(crate) fn requested_tags(headers: &HeaderMap) -> ApiResult<storage::TagSet> {
+    match headers.get("x-amz-tagging") {
+        None => Ok(storage::TagSet::new()),
+        Some(value) => value.to_str()
+            .map(parse_tag_query)
+            .map_err(|_| ApiError::InvalidTag("The x-amz-tagging header is not valid".to_string())),
+    }
+}
+
+/// `x-amz-tagging-count` for GET and HEAD, omitted when there are no tags.
+pub(crate) fn tag_count_header(tags: &storage::TagSet) -> Option<(String, String)> {
+    (!tags.is_empty()).then(|| ("x-amz-tagging-count".to_string(), tags.len().to_string()))
+}
+
+pub async fn get_object_tagging(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    let version_id = handlers::version_id_param(params)?;
+    let record = state.storage_engine.get_object_record(bucket, key, version_id).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+
+    Ok((
+        [("x-amz-version-id", storage::format_version_id(&record.metadata.version_id))],
+        object_lock::xml_response(xml::serialize_tagging(&record.metadata.tags)),
+    ).into_response())
+}
+
+pub async fn put_object_tagging(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+    auth: &AuthContext,
+    body: Body,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let version_id = handlers::version_id_param(params)?;
+    let body = handlers::read_small_body(auth, body).await?;
+    let tags = xml::parse_tagging(handlers::body_text(&body)?)?;
+
+    set_tags(state, bucket, key, version_id, tags, StatusCode::OK).await
+}
+
+pub async fn delete_object_tagging(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    params: &HashMap<String, String>,
+) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let version_id = handlers::version_id_param(params)?;
+    set_tags(state, bucket, key, version_id, storage::TagSet::new(), StatusCode::NO_CONTENT).await
+}
+
+async fn set_tags(
+    state: &AppState,
+    bucket: &str,
+    key: &str,
+    version_id: Option<storage::Version>,
+    tags: storage::TagSet,
+    status: StatusCode,
+) -> ApiResult<Response> {
+    let record = state.storage_engine
+        .set_object_tagging(bucket, key, version_id, tags).await?
+        .ok_or_else(|| ApiError::NoSuchKey(format!("{}:{}", bucket, key)))?;
+
+    Ok((
+        status,
+        [("x-amz-version-id", storage::format_version_id(&record.metadata.version_id))],
+    ).into_response())
+}
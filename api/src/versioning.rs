@@ -0,0 +1,34 @@
+use axum::{
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::{ApiError, ApiResult};
+use crate::handlers::{self, AppState};
+use crate::xml;
+
+pub async fn get_bucket_versioning(state: &AppState, bucket: &str) -> ApiResult<Response> {
+    let bucket = state.storage_engine.get_bucket(bucket).await?
+        .ok_or_else(|| ApiError::NoSuchBucket(bucket.to_string()))?;
+
+    let xml = xml::serialize_versioning_configuration(bucket.versioning);
+
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/xml")],
+        xml,
+    ).into_response())
+}
+
+pub async fn put_bucket_versioning(state: &AppState, bucket: &str, body: Bytes) -> ApiResult<Response> {
+    handlers::check_write_enabled(state).await?;
+
+    let body = std::str::from_utf8(&body)
+        .map_err(|_| ApiError::XmlError("Request body is not valid UTF-8".to_string()))?;
+    let status = xml::parse_versioning_configuration(body)?;
+
+    state.storage_engine.set_bucket_versioning(bucket, status).await?;
+
+    Ok(StatusCode::OK.into_response())
+}
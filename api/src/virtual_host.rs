@@ -0,0 +1,142 @@
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::{HeaderMap, Uri},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+use crate::{ApiError, ApiResult};
+use crate::handlers::AppState;
+
+/// Request middleware run ahead of routing: a request addressed to
+/// `<bucket>.<base domain>` is rewritten to the path-style `/<bucket>/<key>`
+/// the routes expect. The URI as sent stays available as `OriginalUri`,
+/// which is what the client's signature covers.
+pub async fn route(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    rewrite(&state.base_domains, &mut request)?;
+    Ok(next.run(request).await)
+}
+
+/// Gives a virtual-hosted request its path-style URI, keeping the original
+/// as `OriginalUri`. Other requests are left alone.
+fn rewrite(base_domains: &[String], request: &mut Request) -> ApiResult<()> {
+    if let Some(bucket) = host_bucket(base_domains, request.uri(), request.headers()) {
+        let original = request.uri().clone();
+        *request.uri_mut() = path_style_uri(&bucket, &original)?;
+        request.extensions_mut().insert(OriginalUri(original));
+    }
+    Ok(())
+}
+
+/// The bucket named by the request's host, if it is a subdomain of one of
+/// `base_domains`. The longest matching base domain wins.
+pub(crate) fn host_bucket(base_domains: &[String], uri: &Uri, headers: &HeaderMap) -> Option<String> {
+    let host = request_host(uri, headers)?;
+    base_domains.iter()
+        .filter_map(|domain| {
+            let bucket = host.strip_suffix(domain.as_str())?.strip_suffix('.')?;
+            (!bucket.is_empty()).then_some((domain.len(), bucket))
+        })
+        .max_by_key(|(length, _)| *length)
+        .map(|(_, bucket)| bucket.to_string())
+}
+
+/// Whether the request's host is one of `base_domains` itself.
+pub(crate) fn is_base_domain(base_domains: &[String], uri: &Uri, headers: &HeaderMap) -> bool {
+    request_host(uri, headers).is_some_and(|host| base_domains.contains(&host))
+}
+
+/// The lowercased request host without its port, from the `Host` header or,
+/// for HTTP/2, the URI authority.
+fn request_host(uri: &Uri, headers: &HeaderMap) -> Option<String> {
+    let host = headers.get("host")
+        .and_then(|h| h.to_str().ok())
+        .or_else(|| uri.authority().map(|authority| authority.as_str()))?;
+    // IP literals never name a bucket
+    if host.starts_with('[') {
+        return None;
+    }
+    let host = host.rsplit_once(':').map_or(host, |(name, _)| name);
+    Some(host.trim_end_matches('.').to_ascii_lowercase())
+}
+
+fn path_style_uri(bucket: &str, original: &Uri) -> ApiResult<Uri> {
+    let path = match original.path() {
+        "" | "/" => format!("/{}", bucket),
+        path => format!("/{}{}", bucket, path),
+    };
+    let path_and_query = match original.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path,
+    };
+
+    let mut parts = original.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse()
+        .map_err(|_| ApiError::InvalidRequest("Invalid request URI".to_string()))?);
+    Uri::from_parts(parts).map_err(|_| ApiError::InvalidRequest("Invalid request URI".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn base_domains() -> Vec<String> {
+        vec!["s3.test".to_string(), "eu.s3.test".to_string()]
+    }
+
+    fn request(host: &str, uri: &str) -> Request {
+        Request::builder().uri(uri).header("host", host).body(Body::empty()).unwrap()
+    }
+
+    /// The URI `request` is routed by and the one it was sent with, if it
+    /// was rewritten.
+    fn routed(host: &str, uri: &str) -> (String, Option<String>) {
+        let mut request = request(host, uri);
+        rewrite(&base_domains(), &mut request).unwrap();
+        let original = request.extensions().get::<OriginalUri>().map(|OriginalUri(uri)| uri.to_string());
+        (request.uri().to_string(), original)
+    }
+
+    #[test]
+    fn bucket_subdomains_are_rewritten_to_path_style() {
+        assert_eq!(routed("photos.s3.test", "/cat.jpg").0, "/photos/cat.jpg");
+        assert_eq!(routed("photos.s3.test", "/").0, "/photos");
+        assert_eq!(routed("photos.s3.test", "/?list-type=2&prefix=a%2F").0, "/photos?list-type=2&prefix=a%2F");
+        assert_eq!(routed("Photos.S3.Test.", "/a/b").0, "/photos/a/b");
+        // dotted bucket names, and the longest base domain wins
+        assert_eq!(routed("my.photos.s3.test", "/a").0, "/my.photos/a");
+        assert_eq!(routed("photos.eu.s3.test", "/a").0, "/photos/a");
+    }
+
+    #[test]
+    fn ports_are_ignored() {
+        assert_eq!(routed("photos.s3.test:9000", "/cat.jpg").0, "/photos/cat.jpg");
+        assert_eq!(routed("s3.test:9000", "/photos/cat.jpg").0, "/photos/cat.jpg");
+
+        let request = request("s3.test:9000", "/");
+        assert!(is_base_domain(&base_domains(), request.uri(), request.headers()));
+    }
+
+    #[test]
+    fn base_domains_and_other_hosts_pass_through() {
+        for host in ["s3.test", "example.com", "photos.s3.test.example.com", "xs3.test", "127.0.0.1:9000", "[::1]:9000"] {
+            assert_eq!(routed(host, "/photos/cat.jpg"), ("/photos/cat.jpg".to_string(), None), "{}", host);
+        }
+
+        let request = request("example.com", "/");
+        assert!(!is_base_domain(&base_domains(), request.uri(), request.headers()));
+    }
+
+    #[test]
+    fn original_uri_is_kept_for_signing() {
+        let (uri, original) = routed("photos.s3.test", "/sp%20ace/cat.jpg?versionId=1");
+        assert_eq!(uri, "/photos/sp%20ace/cat.jpg?versionId=1");
+        assert_eq!(original.as_deref(), Some("/sp%20ace/cat.jpg?versionId=1"));
+    }
+}
@@ -1,5 +1,7 @@
-use crate::{ListBucketsResponse, ListObjectsV2Response, BucketInfo, ObjectInfo, Owner, CommonPrefix};
+use crate::{ListBucketsResponse, ListObjectsV2Response, DeleteObjectsResponse};
+use crate::{DeleteRequest, ObjectIdentifier};
 use chrono::{DateTime, Utc};
+use crate::{ApiError, ApiResult};
 
 pub fn serialize_list_buckets(response: &ListBucketsResponse) -> String {
     let buckets_xml = response.buckets
@@ -141,32 +143,905 @@ pub fn serialize_create_bucket() -> String {
 </CreateBucketConfiguration>"#.to_string()
 }
 
-pub fn serialize_delete_result(key: &str, version_id: Option<&str>, delete_marker: bool) -> String {
-    let version_xml = version_id
-        .map(|v| format!("  <VersionId>{}</VersionId>", escape_xml(v)))
+pub fn serialize_versioning_configuration(status: storage::VersioningStatus) -> String {
+    let status_xml = status.as_str()
+        .map(|s| format!("\n  <Status>{}</Status>", s))
         .unwrap_or_default();
 
-    let delete_marker_xml = if delete_marker {
-        "  <DeleteMarker>true</DeleteMarker>".to_string()
-    } else {
-        String::new()
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<VersioningConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">{}
+</VersioningConfiguration>"#,
+        status_xml
+    )
+}
+
+/// Reads the `Status` of a PutBucketVersioning body.
+pub fn parse_versioning_configuration(body: &str) -> ApiResult<storage::VersioningStatus> {
+    let root = element(body, "VersioningConfiguration")
+        .ok_or_else(|| ApiError::XmlError("Missing VersioningConfiguration element".to_string()))?;
+
+    element(root, "Status")
+        .and_then(|status| storage::VersioningStatus::parse(status.trim()))
+        .ok_or_else(|| ApiError::XmlError("Status must be Enabled or Suspended".to_string()))
+}
+
+/// `GetBucketLocation` reports `us-east-1` as an empty constraint.
+pub fn serialize_location_constraint(region: &str) -> String {
+    let region = if region == storage::DEFAULT_REGION { "" } else { region };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<LocationConstraint xmlns="http://s3.amazonaws.com/doc/2006-03-01/">{}</LocationConstraint>"#,
+        escape_xml(region)
+    )
+}
+
+/// Reads the optional `LocationConstraint` from a CreateBucket body.
+pub fn parse_create_bucket_configuration(body: &str) -> Option<String> {
+    element(body, "LocationConstraint")
+        .map(|region| unescape_xml(region.trim()))
+        .filter(|region| !region.is_empty())
+}
+
+pub fn serialize_object_lock_configuration(config: &storage::ObjectLockConfiguration) -> String {
+    let rule_xml = config.default_retention
+        .map(|default| {
+            let period = match (default.days, default.years) {
+                (Some(days), _) => format!("<Days>{}</Days>", days),
+                (None, Some(years)) => format!("<Years>{}</Years>", years),
+                (None, None) => String::new(),
+            };
+            format!(
+                "\n  <Rule>\n    <DefaultRetention>\n      <Mode>{}</Mode>\n      {}\n    </DefaultRetention>\n  </Rule>",
+                default.mode.as_str(),
+                period
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ObjectLockConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <ObjectLockEnabled>Enabled</ObjectLockEnabled>{}
+</ObjectLockConfiguration>"#,
+        rule_xml
+    )
+}
+
+/// Reads a PutObjectLockConfiguration body. A configuration without a
+/// `Rule` clears the default retention.
+pub fn parse_object_lock_configuration(body: &str) -> ApiResult<storage::ObjectLockConfiguration> {
+    let root = element(body, "ObjectLockConfiguration")
+        .ok_or_else(|| ApiError::XmlError("Missing ObjectLockConfiguration element".to_string()))?;
+
+    if element(root, "ObjectLockEnabled").map(str::trim) != Some("Enabled") {
+        return Err(ApiError::XmlError("ObjectLockEnabled must be Enabled".to_string()));
+    }
+
+    let Some(default) = element(root, "DefaultRetention") else {
+        return Ok(storage::ObjectLockConfiguration { default_retention: None });
+    };
+
+    let mode = element(default, "Mode")
+        .and_then(|mode| storage::RetentionMode::parse(mode.trim()))
+        .ok_or_else(|| ApiError::XmlError("Mode must be GOVERNANCE or COMPLIANCE".to_string()))?;
+    let period = |tag: &str, max: u32| element(default, tag)
+        .map(|value| {
+            let period = value.trim().parse::<u32>().ok().filter(|n| *n > 0)
+                .ok_or_else(|| ApiError::InvalidArgument(format!("{} must be a positive integer", tag)))?;
+            if period > max {
+                return Err(ApiError::InvalidArgument(format!("{} must not exceed {}", tag, max)));
+            }
+            Ok(period)
+        })
+        .transpose();
+    let (days, years) = (period("Days", storage::MAX_RETENTION_DAYS)?, period("Years", storage::MAX_RETENTION_YEARS)?);
+    if days.is_some() == years.is_some() {
+        return Err(ApiError::XmlError("DefaultRetention must specify exactly one of Days and Years".to_string()));
+    }
+
+    Ok(storage::ObjectLockConfiguration {
+        default_retention: Some(storage::DefaultRetention { mode, days, years }),
+    })
+}
+
+pub fn serialize_retention(retention: &storage::Retention) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Retention xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Mode>{}</Mode>
+  <RetainUntilDate>{}</RetainUntilDate>
+</Retention>"#,
+        retention.mode.as_str(),
+        retention.retain_until.format("%Y-%m-%dT%H:%M:%S%.3fZ")
+    )
+}
+
+/// Reads a PutObjectRetention body. An empty `Retention` element removes
+/// the retention.
+pub fn parse_retention(body: &str) -> ApiResult<Option<storage::Retention>> {
+    let root = element(body, "Retention")
+        .ok_or_else(|| ApiError::XmlError("Missing Retention element".to_string()))?;
+
+    let (mode, retain_until) = match (element(root, "Mode"), element(root, "RetainUntilDate")) {
+        (None, None) => return Ok(None),
+        (Some(mode), Some(retain_until)) => (mode, retain_until),
+        _ => return Err(ApiError::XmlError("Mode and RetainUntilDate must be given together".to_string())),
     };
 
+    Ok(Some(storage::Retention {
+        mode: storage::RetentionMode::parse(mode.trim())
+            .ok_or_else(|| ApiError::XmlError("Mode must be GOVERNANCE or COMPLIANCE".to_string()))?,
+        retain_until: parse_timestamp(retain_until.trim())
+            .ok_or_else(|| ApiError::InvalidArgument("RetainUntilDate must be an ISO 8601 date".to_string()))?,
+    }))
+}
+
+pub fn serialize_legal_hold(legal_hold: bool) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<LegalHold xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Status>{}</Status>
+</LegalHold>"#,
+        if legal_hold { "ON" } else { "OFF" }
+    )
+}
+
+/// Reads the `Status` of a PutObjectLegalHold body.
+pub fn parse_legal_hold(body: &str) -> ApiResult<bool> {
+    let root = element(body, "LegalHold")
+        .ok_or_else(|| ApiError::XmlError("Missing LegalHold element".to_string()))?;
+
+    match element(root, "Status").map(str::trim) {
+        Some("ON") => Ok(true),
+        Some("OFF") => Ok(false),
+        _ => Err(ApiError::XmlError("Status must be ON or OFF".to_string())),
+    }
+}
+
+pub fn serialize_lifecycle_configuration(config: &storage::LifecycleConfiguration) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>
+<LifecycleConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">"#);
+
+    for rule in &config.rules {
+        xml.push_str("\n  <Rule>");
+        if let Some(id) = &rule.id {
+            xml.push_str(&format!("\n    <ID>{}</ID>", escape_xml(id)));
+        }
+
+        let prefix_xml = format!("<Prefix>{}</Prefix>", escape_xml(&rule.filter.prefix));
+        let tags_xml = serialize_tags(&rule.filter.tags);
+        match rule.filter.tags.len() {
+            0 => xml.push_str(&format!("\n    <Filter>{}</Filter>", prefix_xml)),
+            1 if rule.filter.prefix.is_empty() => xml.push_str(&format!("\n    <Filter>{}</Filter>", tags_xml)),
+            _ => xml.push_str(&format!("\n    <Filter><And>{}{}</And></Filter>", prefix_xml, tags_xml)),
+        }
+
+        xml.push_str(&format!("\n    <Status>{}</Status>", if rule.enabled { "Enabled" } else { "Disabled" }));
+
+        if let Some(expiration) = &rule.expiration {
+            let action = match (expiration.days, expiration.date) {
+                (Some(days), _) => format!("<Days>{}</Days>", days),
+                (None, Some(date)) => format!("<Date>{}</Date>", date.format("%Y-%m-%dT%H:%M:%S%.3fZ")),
+                (None, None) => "<ExpiredObjectDeleteMarker>true</ExpiredObjectDeleteMarker>".to_string(),
+            };
+            xml.push_str(&format!("\n    <Expiration>{}</Expiration>", action));
+        }
+
+        if let Some(noncurrent) = &rule.noncurrent_version_expiration {
+            let newer = noncurrent.newer_noncurrent_versions
+                .map(|n| format!("<NewerNoncurrentVersions>{}</NewerNoncurrentVersions>", n))
+                .unwrap_or_default();
+            xml.push_str(&format!(
+                "\n    <NoncurrentVersionExpiration><NoncurrentDays>{}</NoncurrentDays>{}</NoncurrentVersionExpiration>",
+                noncurrent.noncurrent_days,
+                newer
+            ));
+        }
+
+        if let Some(days) = rule.abort_incomplete_multipart_upload_days {
+            xml.push_str(&format!(
+                "\n    <AbortIncompleteMultipartUpload><DaysAfterInitiation>{}</DaysAfterInitiation></AbortIncompleteMultipartUpload>",
+                days
+            ));
+        }
+
+        xml.push_str("\n  </Rule>");
+    }
+
+    xml.push_str("\n</LifecycleConfiguration>");
+    xml
+}
+
+/// Reads a PutBucketLifecycleConfiguration body. Rules are checked for
+/// well-formedness only; `LifecycleConfiguration::validate` checks the rest.
+pub fn parse_lifecycle_configuration(body: &str) -> ApiResult<storage::LifecycleConfiguration> {
+    let root = element(body, "LifecycleConfiguration")
+        .ok_or_else(|| ApiError::XmlError("Missing LifecycleConfiguration element".to_string()))?;
+
+    let mut rules = Vec::new();
+    for rule in elements(root, "Rule") {
+        if element(rule, "Transition").is_some() || element(rule, "NoncurrentVersionTransition").is_some() {
+            return Err(ApiError::InvalidRequest("Transition actions are not supported".to_string()));
+        }
+
+        let enabled = match element(rule, "Status").map(str::trim) {
+            Some("Enabled") => true,
+            Some("Disabled") => false,
+            _ => return Err(ApiError::XmlError("Status must be Enabled or Disabled".to_string())),
+        };
+
+        let filter = match element(rule, "Filter") {
+            Some(filter) => {
+                if element(filter, "ObjectSizeGreaterThan").is_some() || element(filter, "ObjectSizeLessThan").is_some() {
+                    return Err(ApiError::InvalidRequest("Object size filters are not supported".to_string()));
+                }
+                storage::LifecycleFilter {
+                    prefix: element(filter, "Prefix").map(unescape_xml).unwrap_or_default(),
+                    tags: parse_tags(filter)?,
+                }
+            }
+            // Rules written before Filter existed put Prefix directly in the rule
+            None => storage::LifecycleFilter {
+                prefix: element(rule, "Prefix").map(unescape_xml).unwrap_or_default(),
+                tags: Vec::new(),
+            },
+        };
+
+        let expiration = element(rule, "Expiration")
+            .map(|expiration| Ok::<_, ApiError>(storage::Expiration {
+                days: number_element(expiration, "Days")?,
+                date: element(expiration, "Date")
+                    .map(|date| parse_timestamp(date.trim())
+                        .ok_or_else(|| ApiError::InvalidArgument("Date must be an ISO 8601 date".to_string())))
+                    .transpose()?,
+                expired_object_delete_marker: element(expiration, "ExpiredObjectDeleteMarker")
+                    .is_some_and(|value| value.trim() == "true"),
+            }))
+            .transpose()?;
+
+        let noncurrent_version_expiration = element(rule, "NoncurrentVersionExpiration")
+            .map(|noncurrent| Ok::<_, ApiError>(storage::NoncurrentVersionExpiration {
+                noncurrent_days: number_element(noncurrent, "NoncurrentDays")?
+                    .ok_or_else(|| ApiError::XmlError("NoncurrentVersionExpiration requires NoncurrentDays".to_string()))?,
+                newer_noncurrent_versions: number_element(noncurrent, "NewerNoncurrentVersions")?,
+            }))
+            .transpose()?;
+
+        let abort_incomplete_multipart_upload_days = match element(rule, "AbortIncompleteMultipartUpload") {
+            Some(abort) => Some(number_element(abort, "DaysAfterInitiation")?
+                .ok_or_else(|| ApiError::XmlError("AbortIncompleteMultipartUpload requires DaysAfterInitiation".to_string()))?),
+            None => None,
+        };
+
+        rules.push(storage::LifecycleRule {
+            id: element(rule, "ID").map(unescape_xml).filter(|id| !id.is_empty()),
+            enabled,
+            filter,
+            expiration,
+            noncurrent_version_expiration,
+            abort_incomplete_multipart_upload_days,
+        });
+    }
+
+    Ok(storage::LifecycleConfiguration { rules })
+}
+
+pub fn serialize_cors_configuration(config: &storage::CorsConfiguration) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>
+<CORSConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">"#);
+
+    for rule in &config.rules {
+        xml.push_str("\n  <CORSRule>");
+        if let Some(id) = &rule.id {
+            xml.push_str(&format!("\n    <ID>{}</ID>", escape_xml(id)));
+        }
+        let lists = [
+            ("AllowedOrigin", &rule.allowed_origins),
+            ("AllowedMethod", &rule.allowed_methods),
+            ("AllowedHeader", &rule.allowed_headers),
+            ("ExposeHeader", &rule.expose_headers),
+        ];
+        for (tag, values) in lists {
+            for value in values {
+                xml.push_str(&format!("\n    <{}>{}</{}>", tag, escape_xml(value), tag));
+            }
+        }
+        if let Some(max_age) = rule.max_age_seconds {
+            xml.push_str(&format!("\n    <MaxAgeSeconds>{}</MaxAgeSeconds>", max_age));
+        }
+        xml.push_str("\n  </CORSRule>");
+    }
+
+    xml.push_str("\n</CORSConfiguration>");
+    xml
+}
+
+/// Reads a PutBucketCors body; `CorsConfiguration::validate` checks the
+/// rules themselves.
+pub fn parse_cors_configuration(body: &str) -> ApiResult<storage::CorsConfiguration> {
+    let root = element(body, "CORSConfiguration")
+        .ok_or_else(|| ApiError::XmlError("Missing CORSConfiguration element".to_string()))?;
+
+    let values = |rule: &str, tag: &str| -> Vec<String> {
+        elements(rule, tag).into_iter().map(|value| unescape_xml(value.trim())).collect()
+    };
+
+    let rules = elements(root, "CORSRule").into_iter()
+        .map(|rule| Ok(storage::CorsRule {
+            id: element(rule, "ID").map(unescape_xml).filter(|id| !id.is_empty()),
+            allowed_origins: values(rule, "AllowedOrigin"),
+            allowed_methods: values(rule, "AllowedMethod"),
+            allowed_headers: values(rule, "AllowedHeader"),
+            expose_headers: values(rule, "ExposeHeader"),
+            max_age_seconds: number_element(rule, "MaxAgeSeconds")?,
+        }))
+        .collect::<ApiResult<_>>()?;
+
+    Ok(storage::CorsConfiguration { rules })
+}
+
+pub fn serialize_bucket_encryption(config: &storage::BucketEncryption) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ServerSideEncryptionConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Rule>
+    <ApplyServerSideEncryptionByDefault>
+      <SSEAlgorithm>{}</SSEAlgorithm>
+    </ApplyServerSideEncryptionByDefault>
+    <BucketKeyEnabled>{}</BucketKeyEnabled>
+  </Rule>
+</ServerSideEncryptionConfiguration>"#,
+        storage::SSE_ALGORITHM,
+        config.bucket_key_enabled
+    )
+}
+
+/// Reads a PutBucketEncryption body. Only the `AES256` (SSE-S3) algorithm
+/// is available as a default.
+pub fn parse_bucket_encryption(body: &str) -> ApiResult<storage::BucketEncryption> {
+    let root = element(body, "ServerSideEncryptionConfiguration")
+        .ok_or_else(|| ApiError::XmlError("Missing ServerSideEncryptionConfiguration element".to_string()))?;
+
+    let rules = elements(root, "Rule");
+    let [rule] = rules.as_slice() else {
+        return Err(ApiError::XmlError("Exactly one encryption rule must be given".to_string()));
+    };
+
+    let algorithm = element(rule, "ApplyServerSideEncryptionByDefault")
+        .and_then(|default| element(default, "SSEAlgorithm"))
+        .map(str::trim)
+        .ok_or_else(|| ApiError::XmlError("Missing ApplyServerSideEncryptionByDefault/SSEAlgorithm".to_string()))?;
+    if algorithm != storage::SSE_ALGORITHM {
+        return Err(ApiError::InvalidArgument(
+            format!("The encryption algorithm {} is not supported", unescape_xml(algorithm))
+        ));
+    }
+
+    let bucket_key_enabled = match element(rule, "BucketKeyEnabled").map(str::trim) {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(_) => return Err(ApiError::XmlError("BucketKeyEnabled must be true or false".to_string())),
+    };
+
+    Ok(storage::BucketEncryption { bucket_key_enabled })
+}
+
+pub fn serialize_tagging(tags: &[(String, String)]) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Tagging xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <TagSet>{}</TagSet>
+</Tagging>"#,
+        serialize_tags(tags)
+    )
+}
+
+/// Reads the tag set of a PutObjectTagging body.
+pub fn parse_tagging(body: &str) -> ApiResult<storage::TagSet> {
+    let tag_set = element(body, "Tagging")
+        .and_then(|root| element(root, "TagSet"))
+        .ok_or_else(|| ApiError::XmlError("Missing Tagging/TagSet element".to_string()))?;
+
+    parse_tags(tag_set)
+}
+
+fn serialize_tags(tags: &[(String, String)]) -> String {
+    tags.iter()
+        .map(|(key, value)| format!("<Tag><Key>{}</Key><Value>{}</Value></Tag>", escape_xml(key), escape_xml(value)))
+        .collect()
+}
+
+/// Reads the `Tag` elements directly inside `xml`.
+fn parse_tags(xml: &str) -> ApiResult<storage::TagSet> {
+    elements(xml, "Tag").into_iter()
+        .map(|tag| Ok((
+            element(tag, "Key").map(unescape_xml)
+                .ok_or_else(|| ApiError::XmlError("Tag is missing its Key".to_string()))?,
+            element(tag, "Value").map(unescape_xml).unwrap_or_default(),
+        )))
+        .collect()
+}
+
+pub fn serialize_access_control_policy(owner_id: &str, acl: &storage::AccessControlList) -> String {
+    let grants: String = acl.grants.iter()
+        .map(|grant| {
+            let grantee = match &grant.grantee {
+                storage::Grantee::CanonicalUser(id) => format!(
+                    r#"<Grantee xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="CanonicalUser"><ID>{}</ID><DisplayName>{}</DisplayName></Grantee>"#,
+                    escape_xml(id),
+                    escape_xml(id)
+                ),
+                group => format!(
+                    r#"<Grantee xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Group"><URI>{}</URI></Grantee>"#,
+                    group.group_uri().unwrap_or_default()
+                ),
+            };
+            format!("\n    <Grant>{}<Permission>{}</Permission></Grant>", grantee, grant.permission.as_str())
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<AccessControlPolicy xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Owner>
+    <ID>{}</ID>
+    <DisplayName>{}</DisplayName>
+  </Owner>
+  <AccessControlList>{}
+  </AccessControlList>
+</AccessControlPolicy>"#,
+        escape_xml(owner_id),
+        escape_xml(owner_id),
+        grants
+    )
+}
+
+/// Reads a PutBucketAcl or PutObjectAcl body: the owner ID it names, if
+/// any, and its grants.
+pub fn parse_access_control_policy(body: &str) -> ApiResult<(Option<String>, storage::AccessControlList)> {
+    let policy = element(body, "AccessControlPolicy")
+        .ok_or_else(|| ApiError::XmlError("Missing AccessControlPolicy element".to_string()))?;
+
+    let owner_id = element(policy, "Owner")
+        .and_then(|owner| element(owner, "ID"))
+        .map(|id| unescape_xml(id.trim()));
+
+    let list = element(policy, "AccessControlList").unwrap_or_default();
+    let grants = elements(list, "Grant").into_iter()
+        .map(|grant| {
+            let grantee_xml = element(grant, "Grantee")
+                .ok_or_else(|| ApiError::XmlError("Grant is missing its Grantee".to_string()))?;
+            let grantee = if let Some(uri) = element(grantee_xml, "URI") {
+                storage::Grantee::group(uri.trim())
+                    .ok_or_else(|| ApiError::InvalidArgument(format!("Unsupported grantee group: {}", uri.trim())))?
+            } else if let Some(id) = element(grantee_xml, "ID") {
+                storage::Grantee::CanonicalUser(unescape_xml(id.trim()))
+            } else {
+                return Err(ApiError::InvalidArgument(
+                    "Grantees must be given by canonical ID or group URI".to_string()
+                ));
+            };
+
+            let permission = element(grant, "Permission")
+                .and_then(|p| storage::Permission::parse(p.trim()))
+                .ok_or_else(|| ApiError::XmlError("Grant has a missing or unknown Permission".to_string()))?;
+
+            Ok(storage::Grant { grantee, permission })
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    Ok((owner_id, storage::AccessControlList { grants }))
+}
+
+/// Reads a non-negative integer element, if present.
+fn number_element(xml: &str, tag: &str) -> ApiResult<Option<u32>> {
+    element(xml, tag)
+        .map(|value| value.trim().parse::<u32>()
+            .map_err(|_| ApiError::XmlError(format!("{} must be a non-negative integer", tag))))
+        .transpose()
+}
+
+/// Parses an ISO 8601 timestamp such as `2030-01-01T00:00:00.000Z`.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+pub fn serialize_delete_objects(response: &DeleteObjectsResponse) -> String {
+    let deleted_xml = response.deleted
+        .iter()
+        .map(|d| {
+            let version_xml = d.version_id.as_ref()
+                .map(|v| format!("\n    <VersionId>{}</VersionId>", escape_xml(v)))
+                .unwrap_or_default();
+            let delete_marker_xml = if d.delete_marker {
+                "\n    <DeleteMarker>true</DeleteMarker>"
+            } else {
+                ""
+            };
+            let marker_version_xml = d.delete_marker_version_id.as_ref()
+                .map(|v| format!("\n    <DeleteMarkerVersionId>{}</DeleteMarkerVersionId>", escape_xml(v)))
+                .unwrap_or_default();
+            format!(
+                "  <Deleted>\n    <Key>{}</Key>{}{}{}\n  </Deleted>",
+                escape_xml(&d.key),
+                version_xml,
+                delete_marker_xml,
+                marker_version_xml
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let errors_xml = response.errors
+        .iter()
+        .map(|e| {
+            let version_xml = e.version_id.as_ref()
+                .map(|v| format!("\n    <VersionId>{}</VersionId>", escape_xml(v)))
+                .unwrap_or_default();
+            format!(
+                "  <Error>\n    <Key>{}</Key>{}\n    <Code>{}</Code>\n    <Message>{}</Message>\n  </Error>",
+                escape_xml(&e.key),
+                version_xml,
+                escape_xml(&e.code),
+                escape_xml(&e.message)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
     format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
 <DeleteResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
-  <Deleted>
-    <Key>{}</Key>
 {}
 {}
-  </Deleted>
 </DeleteResult>"#,
+        deleted_xml,
+        errors_xml
+    )
+}
+
+/// Keys and optional version IDs from a `<Delete>` request body, plus
+/// whether quiet mode was requested.
+pub fn parse_delete_objects(body: &str) -> ApiResult<DeleteRequest> {
+    let root = element(body, "Delete")
+        .ok_or_else(|| ApiError::XmlError("Missing Delete element".to_string()))?;
+
+    let quiet = element(root, "Quiet")
+        .map(|q| q.trim().eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+
+    let objects = elements(root, "Object")
+        .into_iter()
+        .map(|object| {
+            let key = element(object, "Key")
+                .ok_or_else(|| ApiError::XmlError("Object is missing a Key".to_string()))?;
+            let version_id = element(object, "VersionId").map(|v| unescape_xml(v.trim()));
+            Ok(ObjectIdentifier { key: unescape_xml(key), version_id })
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    Ok(DeleteRequest { objects, quiet })
+}
+
+pub fn serialize_initiate_multipart_upload(bucket: &str, key: &str, upload_id: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<InitiateMultipartUploadResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Bucket>{}</Bucket>
+  <Key>{}</Key>
+  <UploadId>{}</UploadId>
+</InitiateMultipartUploadResult>"#,
+        escape_xml(bucket),
+        escape_xml(key),
+        escape_xml(upload_id)
+    )
+}
+
+pub fn serialize_complete_multipart_upload(location: &str, bucket: &str, key: &str, etag: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<CompleteMultipartUploadResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Location>{}</Location>
+  <Bucket>{}</Bucket>
+  <Key>{}</Key>
+  <ETag>{}</ETag>
+</CompleteMultipartUploadResult>"#,
+        escape_xml(location),
+        escape_xml(bucket),
         escape_xml(key),
-        version_xml,
-        delete_marker_xml
+        escape_xml(etag)
     )
 }
 
+/// Body of a CopyObject (`CopyObjectResult`) or UploadPartCopy
+/// (`CopyPartResult`) response.
+pub fn serialize_copy_result(element: &str, etag: &str, last_modified: chrono::DateTime<chrono::Utc>) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<{0} xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <ETag>{1}</ETag>
+  <LastModified>{2}</LastModified>
+</{0}>"#,
+        element,
+        escape_xml(etag),
+        last_modified.format("%Y-%m-%dT%H:%M:%S%.3fZ")
+    )
+}
+
+/// Body of a ListParts response for one page of parts, listed after
+/// `part_number_marker`.
+pub fn serialize_list_parts(
+    upload: &storage::MultipartUpload,
+    page: &storage::ListPartsPage,
+    part_number_marker: u32,
+    max_parts: usize,
+) -> String {
+    let parts_xml = page.parts
+        .iter()
+        .map(|part| format!(
+            r#"  <Part>
+    <PartNumber>{}</PartNumber>
+    <LastModified>{}</LastModified>
+    <ETag>{}</ETag>
+    <Size>{}</Size>
+  </Part>"#,
+            part.part_number,
+            part.last_modified.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            escape_xml(&part.etag),
+            part.size
+        ))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListPartsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Bucket>{}</Bucket>
+  <Key>{}</Key>
+  <UploadId>{}</UploadId>
+  <StorageClass>STANDARD</StorageClass>
+  <PartNumberMarker>{}</PartNumberMarker>
+{}
+  <MaxParts>{}</MaxParts>
+  <IsTruncated>{}</IsTruncated>
+{}
+</ListPartsResult>"#,
+        escape_xml(&upload.bucket),
+        escape_xml(&upload.key),
+        escape_xml(&upload.upload_id),
+        part_number_marker,
+        page.next_part_number_marker
+            .map(|marker| format!("  <NextPartNumberMarker>{}</NextPartNumberMarker>", marker))
+            .unwrap_or_default(),
+        max_parts,
+        page.is_truncated,
+        parts_xml
+    )
+}
+
+/// Body of a ListMultipartUploads response for one page of uploads, listed
+/// after the given markers.
+pub fn serialize_list_multipart_uploads(
+    bucket: &str,
+    prefix: Option<&str>,
+    key_marker: Option<&str>,
+    upload_id_marker: Option<&str>,
+    max_uploads: usize,
+    page: &storage::ListUploadsPage,
+) -> String {
+    let uploads_xml = page.uploads
+        .iter()
+        .map(|upload| format!(
+            r#"  <Upload>
+    <Key>{}</Key>
+    <UploadId>{}</UploadId>
+    <StorageClass>STANDARD</StorageClass>
+    <Initiated>{}</Initiated>
+  </Upload>"#,
+            escape_xml(&upload.key),
+            escape_xml(&upload.upload_id),
+            upload.initiated_at.format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        ))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let next_markers_xml = match (&page.next_key_marker, &page.next_upload_id_marker) {
+        (Some(key), Some(upload_id)) => format!(
+            "  <NextKeyMarker>{}</NextKeyMarker>\n  <NextUploadIdMarker>{}</NextUploadIdMarker>",
+            escape_xml(key),
+            escape_xml(upload_id)
+        ),
+        _ => String::new(),
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListMultipartUploadsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Bucket>{}</Bucket>
+  <Prefix>{}</Prefix>
+  <KeyMarker>{}</KeyMarker>
+  <UploadIdMarker>{}</UploadIdMarker>
+{}
+  <MaxUploads>{}</MaxUploads>
+  <IsTruncated>{}</IsTruncated>
+{}
+</ListMultipartUploadsResult>"#,
+        escape_xml(bucket),
+        escape_xml(prefix.unwrap_or("")),
+        escape_xml(key_marker.unwrap_or("")),
+        escape_xml(upload_id_marker.unwrap_or("")),
+        next_markers_xml,
+        max_uploads,
+        page.is_truncated,
+        uploads_xml
+    )
+}
+
+/// Body of a ListObjectVersions response. `encode` is applied to keys and
+/// prefixes, for `encoding-type=url`.
+pub fn serialize_list_object_versions(response: &storage::ListVersionsResponse, encode: impl Fn(&str) -> String) -> String {
+    // S3 interleaves versions and delete markers in key order, newest first
+    let mut entries: Vec<(&str, chrono::DateTime<chrono::Utc>, String)> = response.versions
+        .iter()
+        .map(|v| (v.key.as_str(), v.last_modified, format!(
+            r#"  <Version>
+    <Key>{}</Key>
+    <VersionId>{}</VersionId>
+    <IsLatest>{}</IsLatest>
+    <LastModified>{}</LastModified>
+    <ETag>{}</ETag>
+    <Size>{}</Size>
+    <StorageClass>STANDARD</StorageClass>
+  </Version>"#,
+            escape_xml(&encode(&v.key)),
+            storage::format_version_id(&v.version_id),
+            v.is_latest,
+            v.last_modified.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            escape_xml(&v.etag),
+            v.size
+        )))
+        .collect();
+
+    entries.extend(response.delete_markers.iter().map(|m| (m.key.as_str(), m.created_at, format!(
+        r#"  <DeleteMarker>
+    <Key>{}</Key>
+    <VersionId>{}</VersionId>
+    <IsLatest>{}</IsLatest>
+    <LastModified>{}</LastModified>
+  </DeleteMarker>"#,
+        escape_xml(&encode(&m.key)),
+        storage::format_version_id(&m.version_id),
+        m.is_latest,
+        m.created_at.format("%Y-%m-%dT%H:%M:%S%.3fZ")
+    ))));
+
+    entries.sort_by(|a, b| a.0.cmp(b.0).then(b.1.cmp(&a.1)));
+
+    let entries_xml = entries.into_iter()
+        .map(|(_, _, xml)| xml)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let common_prefixes_xml = response.common_prefixes
+        .iter()
+        .map(|prefix| format!(
+            r#"  <CommonPrefixes>
+    <Prefix>{}</Prefix>
+  </CommonPrefixes>"#,
+            escape_xml(&encode(prefix))
+        ))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let delimiter_xml = response.delimiter.as_ref()
+        .map(|d| format!("  <Delimiter>{}</Delimiter>", escape_xml(&encode(d))))
+        .unwrap_or_default();
+
+    let next_markers_xml = match (&response.next_key_marker, &response.next_version_id_marker) {
+        (Some(key), Some(version_id)) => format!(
+            "  <NextKeyMarker>{}</NextKeyMarker>\n  <NextVersionIdMarker>{}</NextVersionIdMarker>",
+            escape_xml(&encode(key)),
+            storage::format_version_id(version_id)
+        ),
+        (Some(key), None) => format!("  <NextKeyMarker>{}</NextKeyMarker>", escape_xml(&encode(key))),
+        _ => String::new(),
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListVersionsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>{}</Name>
+  <Prefix>{}</Prefix>
+  <KeyMarker>{}</KeyMarker>
+  <VersionIdMarker>{}</VersionIdMarker>
+{}
+  <MaxKeys>{}</MaxKeys>
+  <IsTruncated>{}</IsTruncated>
+{}
+{}
+{}
+</ListVersionsResult>"#,
+        escape_xml(&response.bucket),
+        escape_xml(&encode(response.prefix.as_deref().unwrap_or(""))),
+        escape_xml(&encode(response.key_marker.as_deref().unwrap_or(""))),
+        response.version_id_marker.as_ref().map(storage::format_version_id).unwrap_or_default(),
+        delimiter_xml,
+        response.max_keys,
+        response.is_truncated,
+        next_markers_xml,
+        entries_xml,
+        common_prefixes_xml
+    )
+}
+
+/// Parses a `CompleteMultipartUpload` body into `(part number, etag)` pairs.
+pub fn parse_complete_multipart_upload(body: &str) -> ApiResult<Vec<(u32, String)>> {
+    let root = element(body, "CompleteMultipartUpload")
+        .ok_or_else(|| ApiError::XmlError("Missing CompleteMultipartUpload element".to_string()))?;
+
+    elements(root, "Part")
+        .into_iter()
+        .map(|part| {
+            let part_number = element(part, "PartNumber")
+                .and_then(|n| n.trim().parse::<u32>().ok())
+                .ok_or_else(|| ApiError::XmlError("Part is missing a valid PartNumber".to_string()))?;
+            let etag = element(part, "ETag")
+                .ok_or_else(|| ApiError::XmlError("Part is missing an ETag".to_string()))?;
+            Ok((part_number, unescape_xml(etag.trim())))
+        })
+        .collect()
+}
+
+/// Inner text of every `<tag>...</tag>` element directly searchable in
+/// `xml`, in document order. Good enough for the flat request bodies S3
+/// clients send; nested elements of the same name are not supported.
+pub fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut found = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        let after_name = &rest[start + open.len()..];
+        let Some(tag_end) = after_name.find('>') else { break };
+
+        // Skip longer tag names that merely share a prefix, e.g. <PartNumber> for <Part>
+        if !after_name.starts_with(['>', '/', ' ', '\t', '\r', '\n']) {
+            rest = after_name;
+            continue;
+        }
+
+        if after_name[..tag_end].ends_with('/') {
+            found.push("");
+            rest = &after_name[tag_end + 1..];
+            continue;
+        }
+
+        let content = &after_name[tag_end + 1..];
+        let Some(end) = content.find(&close) else { break };
+        found.push(&content[..end]);
+        rest = &content[end + close.len()..];
+    }
+
+    found
+}
+
+pub fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    elements(xml, tag).into_iter().next()
+}
+
+pub fn unescape_xml(input: &str) -> String {
+    input
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
 fn escape_xml(input: &str) -> String {
     input
         .replace('&', "&amp;")
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::net::IpAddr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +12,16 @@ pub struct Config {
     pub replication_factor: usize,
     pub consensus_timeout_ms: u64,
     pub heartbeat_interval_ms: u64,
+    pub credentials: HashMap<String, String>,
+    pub allow_anonymous: bool,
+    /// Create buckets on first write instead of rejecting with NoSuchBucket.
+    pub auto_create_buckets: bool,
+    /// Seconds between passes of the bucket lifecycle worker.
+    pub lifecycle_interval_secs: u64,
+    /// Domains whose subdomains address buckets (virtual-hosted style).
+    pub base_domains: Vec<String>,
+    /// File holding the SSE-S3 master key. Without it SSE-S3 is refused.
+    pub master_key_file: Option<String>,
 }
 
 impl Config {
@@ -24,6 +35,12 @@ impl Config {
             replication_factor: 3,
             consensus_timeout_ms: 5000,
             heartbeat_interval_ms: 1000,
+            credentials: HashMap::new(),
+            allow_anonymous: true,
+            auto_create_buckets: false,
+            lifecycle_interval_secs: 3600,
+            base_domains: Vec::new(),
+            master_key_file: None,
         }
     }
 
@@ -71,6 +88,9 @@ impl From<Config> for api::Config {
             replication_factor: config.replication_factor,
             consensus_timeout_ms: config.consensus_timeout_ms,
             heartbeat_interval_ms: config.heartbeat_interval_ms,
+            credentials: config.credentials,
+            allow_anonymous: config.allow_anonymous,
+            base_domains: config.base_domains,
         }
     }
 }
\ No newline at end of file
@@ -12,6 +12,10 @@ use config::Config;
 use node::Node;
 use error::O3StorageError;
 
+/// Environment variable holding the secret for `--access-key`, kept off the
+/// command line where other users could read it.
+const SECRET_KEY_ENV: &str = "O3_SECRET_KEY";
+
 #[tokio::main]
 async fn main() -> Result<(), O3StorageError> {
     tracing_subscriber::init();
@@ -37,13 +41,48 @@ async fn main() -> Result<(), O3StorageError> {
                 .help("Port to listen on")
                 .default_value("8080")
         )
+        .arg(
+            Arg::new("access-key")
+                .long("access-key")
+                .help("Access key accepted for signed S3 requests; its secret is read from O3_SECRET_KEY")
+        )
+        .arg(
+            Arg::new("no-anonymous")
+                .long("no-anonymous")
+                .help("Reject requests that are not signed")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("auto-create-buckets")
+                .long("auto-create-buckets")
+                .help("Create missing buckets on first write instead of returning NoSuchBucket")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("lifecycle-interval")
+                .long("lifecycle-interval")
+                .help("Seconds between bucket lifecycle evaluations")
+                .default_value("3600")
+        )
+        .arg(
+            Arg::new("master-key-file")
+                .long("master-key-file")
+                .help("File holding the 32-byte SSE-S3 master key; give every node the same file, kept off the data disk")
+                .required(false)
+        )
+        .arg(
+            Arg::new("domain")
+                .long("domain")
+                .help("Comma-separated base domains for virtual-hosted-style bucket addressing")
+                .required(false)
+        )
         .get_matches();
 
     info!("Starting O3Storage distributed object storage system");
 
     system::hardware_check().map_err(|e| O3StorageError::System(e.to_string()))?;
 
-    let config = if let Some(ip_str) = matches.get_one::<String>("ip") {
+    let mut config = if let Some(ip_str) = matches.get_one::<String>("ip") {
         let ip = IpAddr::from_str(ip_str)
             .map_err(|e| O3StorageError::InvalidConfig(format!("Invalid IP address: {}", e)))?;
         
@@ -67,7 +106,61 @@ async fn main() -> Result<(), O3StorageError> {
         interactive_setup().await?
     };
 
-    info!("Node configuration: {:?}", config);
+    if let Some(access_key) = matches.get_one::<String>("access-key") {
+        let secret_key = std::env::var(SECRET_KEY_ENV)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .ok_or_else(|| O3StorageError::InvalidConfig(
+                format!("--access-key needs its secret key in {}", SECRET_KEY_ENV)
+            ))?;
+        config.credentials.insert(access_key.clone(), secret_key);
+    }
+
+    if matches.get_flag("no-anonymous") {
+        config.allow_anonymous = false;
+    }
+
+    if matches.get_flag("auto-create-buckets") {
+        config.auto_create_buckets = true;
+    }
+
+    config.lifecycle_interval_secs = matches.get_one::<String>("lifecycle-interval")
+        .unwrap()
+        .parse::<u64>()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .ok_or_else(|| O3StorageError::InvalidConfig("Invalid lifecycle interval".to_string()))?;
+
+    if let Some(domains) = matches.get_one::<String>("domain") {
+        config.base_domains = domains
+            .split(',')
+            .map(|domain| domain.trim().to_string())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+    }
+
+    config.master_key_file = matches.get_one::<String>("master-key-file").cloned();
+    match &config.master_key_file {
+        None => {
+            warn!("No --master-key-file given: SSE-S3 requests and bucket default encryption will be refused");
+            // Earlier versions generated a key here when none was given
+            let legacy = std::path::Path::new(&config.storage_path).join("master.key");
+            if legacy.exists() {
+                warn!(
+                    "Found {}; SSE-S3 objects written with it stay unreadable until it is moved off the data disk \
+                     and passed with --master-key-file",
+                    legacy.display()
+                );
+            }
+        }
+        Some(path) if is_within(path, &config.storage_path) => warn!(
+            "The SSE-S3 master key file {} is inside the storage directory {}; anyone with the data disk also has the key",
+            path, config.storage_path
+        ),
+        Some(_) => {}
+    }
+
+    info!("Node configuration: {} (peers: {:?})", config.bind_address(), config.peers);
 
     let node = Node::new(config).await?;
     node.start().await?;
@@ -75,6 +168,12 @@ async fn main() -> Result<(), O3StorageError> {
     Ok(())
 }
 
+/// Whether `path` lies under `dir`, following symlinks where both exist.
+fn is_within(path: &str, dir: &str) -> bool {
+    let resolve = |p: &str| std::fs::canonicalize(p).unwrap_or_else(|_| std::path::PathBuf::from(p));
+    resolve(path).starts_with(resolve(dir))
+}
+
 async fn interactive_setup() -> Result<Config, O3StorageError> {
     use std::io::{self, Write};
 
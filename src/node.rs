@@ -40,9 +40,13 @@ impl Node {
     pub async fn new(config: Config) -> Result<Self> {
         info!("Initializing O3Storage node at {}", config.bind_address());
 
-        let storage_engine = Arc::new(
-            storage::StorageEngine::new(&config.storage_path, config.max_storage_size).await?
-        );
+        let mut storage_engine = storage::StorageEngine::new(&config.storage_path, config.max_storage_size).await?
+            .with_auto_create_buckets(config.auto_create_buckets)
+            .with_lifecycle_interval(std::time::Duration::from_secs(config.lifecycle_interval_secs));
+        if let Some(path) = &config.master_key_file {
+            storage_engine = storage_engine.with_master_key(storage::read_master_key(std::path::Path::new(path)).await?);
+        }
+        let storage_engine = Arc::new(storage_engine);
 
         let cluster_state = Arc::new(RwLock::new(ClusterState {
             active_nodes: Vec::new(),
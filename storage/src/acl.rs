@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, StorageError};
+
+/// Most grants one access control list may hold.
+pub const MAX_ACL_GRANTS: usize = 100;
+
+pub const ALL_USERS_GROUP: &str = "http://acs.amazonaws.com/groups/global/AllUsers";
+pub const AUTHENTICATED_USERS_GROUP: &str = "http://acs.amazonaws.com/groups/global/AuthenticatedUsers";
+
+/// What a grant allows. On a bucket, `Read` lists it and `Write` creates,
+/// overwrites and deletes objects in it; on an object, `Read` reads its
+/// data. `ReadAcp` and `WriteAcp` read and replace the ACL itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Permission {
+    Read,
+    Write,
+    ReadAcp,
+    WriteAcp,
+    FullControl,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Read => "READ",
+            Permission::Write => "WRITE",
+            Permission::ReadAcp => "READ_ACP",
+            Permission::WriteAcp => "WRITE_ACP",
+            Permission::FullControl => "FULL_CONTROL",
+        }
+    }
+
+    pub fn parse(permission: &str) -> Option<Self> {
+        match permission {
+            "READ" => Some(Permission::Read),
+            "WRITE" => Some(Permission::Write),
+            "READ_ACP" => Some(Permission::ReadAcp),
+            "WRITE_ACP" => Some(Permission::WriteAcp),
+            "FULL_CONTROL" => Some(Permission::FullControl),
+            _ => None,
+        }
+    }
+}
+
+/// Who a grant applies to: one account by canonical ID, or a predefined
+/// group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Grantee {
+    CanonicalUser(String),
+    AllUsers,
+    AuthenticatedUsers,
+}
+
+impl Grantee {
+    /// The grantee for a predefined group URI.
+    pub fn group(uri: &str) -> Option<Self> {
+        match uri {
+            ALL_USERS_GROUP => Some(Grantee::AllUsers),
+            AUTHENTICATED_USERS_GROUP => Some(Grantee::AuthenticatedUsers),
+            _ => None,
+        }
+    }
+
+    /// The URI of a group grantee; `None` for a single account.
+    pub fn group_uri(&self) -> Option<&'static str> {
+        match self {
+            Grantee::CanonicalUser(_) => None,
+            Grantee::AllUsers => Some(ALL_USERS_GROUP),
+            Grantee::AuthenticatedUsers => Some(AUTHENTICATED_USERS_GROUP),
+        }
+    }
+
+    fn includes(&self, caller: Option<&str>) -> bool {
+        match self {
+            Grantee::CanonicalUser(id) => caller == Some(id.as_str()),
+            Grantee::AllUsers => true,
+            Grantee::AuthenticatedUsers => caller.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grant {
+    pub grantee: Grantee,
+    pub permission: Permission,
+}
+
+/// A predefined ACL, as named by `x-amz-acl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CannedAcl {
+    Private,
+    PublicRead,
+    PublicReadWrite,
+    AuthenticatedRead,
+}
+
+impl CannedAcl {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "private" => Some(CannedAcl::Private),
+            "public-read" => Some(CannedAcl::PublicRead),
+            "public-read-write" => Some(CannedAcl::PublicReadWrite),
+            "authenticated-read" => Some(CannedAcl::AuthenticatedRead),
+            _ => None,
+        }
+    }
+}
+
+/// Grants on a bucket or object version. The owner is recorded separately
+/// and, like in S3, keeps the right to read and replace the ACL whatever it
+/// says.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessControlList {
+    pub grants: Vec<Grant>,
+}
+
+impl AccessControlList {
+    /// The grants a canned ACL stands for: full control for `owner_id`, plus
+    /// any group grants.
+    pub fn canned(canned: CannedAcl, owner_id: &str) -> Self {
+        let mut grants = vec![Grant {
+            grantee: Grantee::CanonicalUser(owner_id.to_string()),
+            permission: Permission::FullControl,
+        }];
+
+        let group = |grantee: Grantee, permission| Grant { grantee, permission };
+        match canned {
+            CannedAcl::Private => {}
+            CannedAcl::PublicRead => grants.push(group(Grantee::AllUsers, Permission::Read)),
+            CannedAcl::PublicReadWrite => {
+                grants.push(group(Grantee::AllUsers, Permission::Read));
+                grants.push(group(Grantee::AllUsers, Permission::Write));
+            }
+            CannedAcl::AuthenticatedRead => grants.push(group(Grantee::AuthenticatedUsers, Permission::Read)),
+        }
+
+        Self { grants }
+    }
+
+    pub fn private(owner_id: &str) -> Self {
+        Self::canned(CannedAcl::Private, owner_id)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.grants.len() > MAX_ACL_GRANTS {
+            return Err(StorageError::InvalidArgument(
+                format!("An access control list may hold at most {} grants", MAX_ACL_GRANTS)
+            ));
+        }
+        let empty_id = self.grants.iter()
+            .any(|g| matches!(&g.grantee, Grantee::CanonicalUser(id) if id.is_empty()));
+        if empty_id {
+            return Err(StorageError::InvalidArgument("Grantee ID must not be empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether the grants give `caller` `permission`. `caller` is the
+    /// canonical ID of an authenticated caller, or `None` for anonymous
+    /// requests, which only match grants to all users.
+    pub fn allows(&self, caller: Option<&str>, permission: Permission) -> bool {
+        self.grants.iter().any(|grant| {
+            grant.grantee.includes(caller)
+                && (grant.permission == permission || grant.permission == Permission::FullControl)
+        })
+    }
+}
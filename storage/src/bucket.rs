@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::net::Ipv4Addr;
+
+use crate::{Result, StorageError};
+use crate::acl::AccessControlList;
+use crate::cors::CorsConfiguration;
+use crate::encryption::BucketEncryption;
+use crate::lifecycle::LifecycleConfiguration;
+use crate::lock::ObjectLockConfiguration;
+use crate::policy::BucketPolicy;
+
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+const RESERVED_PREFIXES: &[&str] = &["xn--", "sthree-", "amzn-s3-demo-"];
+const RESERVED_SUFFIXES: &[&str] = &["-s3alias", "--ol-s3", ".mrap", "--x-s3", "--table-s3"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bucket {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub region: String,
+    /// Canonical ID of the creating account; `None` for buckets created
+    /// before ownership was recorded.
+    pub owner_id: Option<String>,
+    pub versioning: VersioningStatus,
+    /// Set when Object Lock was enabled at creation.
+    pub object_lock: Option<ObjectLockConfiguration>,
+    pub lifecycle: Option<LifecycleConfiguration>,
+    pub policy: Option<BucketPolicy>,
+    /// `None` for buckets created before ACLs were recorded.
+    pub acl: Option<AccessControlList>,
+    pub cors: Option<CorsConfiguration>,
+    /// Default encryption for writes that do not request any.
+    pub encryption: Option<BucketEncryption>,
+}
+
+/// Versioning state of a bucket. Once versioning has been enabled a bucket
+/// can only be suspended, never returned to `Unversioned`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VersioningStatus {
+    #[default]
+    Unversioned,
+    Enabled,
+    Suspended,
+}
+
+impl VersioningStatus {
+    /// The `Status` value S3 uses; `None` for a bucket never configured.
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            VersioningStatus::Unversioned => None,
+            VersioningStatus::Enabled => Some("Enabled"),
+            VersioningStatus::Suspended => Some("Suspended"),
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "Enabled" => Some(VersioningStatus::Enabled),
+            "Suspended" => Some(VersioningStatus::Suspended),
+            _ => None,
+        }
+    }
+}
+
+impl Bucket {
+    pub fn new(name: String, region: Option<String>, owner_id: Option<String>) -> Self {
+        let acl = owner_id.as_deref().map(AccessControlList::private);
+        Self {
+            name,
+            created_at: Utc::now(),
+            region: region.unwrap_or_else(|| DEFAULT_REGION.to_string()),
+            owner_id,
+            versioning: VersioningStatus::Unversioned,
+            object_lock: None,
+            lifecycle: None,
+            policy: None,
+            acl,
+            cors: None,
+            encryption: None,
+        }
+    }
+
+    /// Enables Object Lock, which requires versioning to stay enabled.
+    pub fn with_object_lock(mut self) -> Self {
+        self.versioning = VersioningStatus::Enabled;
+        self.object_lock = Some(ObjectLockConfiguration::default());
+        self
+    }
+}
+
+/// Checks `name` against the S3 general purpose bucket naming rules.
+pub fn validate_bucket_name(name: &str) -> Result<()> {
+    let invalid = |reason: &str| Err(StorageError::InvalidBucketName(format!("{}: {}", name, reason)));
+
+    if name.len() < 3 || name.len() > 63 {
+        return invalid("must be between 3 and 63 characters long");
+    }
+    if !name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'.' || b == b'-') {
+        return invalid("may only contain lowercase letters, numbers, dots and hyphens");
+    }
+    let alphanumeric = |b: u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+    if !alphanumeric(name.as_bytes()[0]) || !alphanumeric(name.as_bytes()[name.len() - 1]) {
+        return invalid("must begin and end with a letter or number");
+    }
+    if name.contains("..") {
+        return invalid("must not contain two adjacent periods");
+    }
+    if name.parse::<Ipv4Addr>().is_ok() {
+        return invalid("must not be formatted as an IP address");
+    }
+    if RESERVED_PREFIXES.iter().any(|p| name.starts_with(p))
+        || RESERVED_SUFFIXES.iter().any(|s| name.ends_with(s))
+    {
+        return invalid("uses a reserved prefix or suffix");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use openssl::base64;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Algorithms a client may checksum object data with, as S3's
+/// `x-amz-checksum-*` headers name them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChecksumAlgorithm {
+    Crc32,
+    Crc32c,
+    Sha1,
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub const ALL: [ChecksumAlgorithm; 4] = [Self::Crc32, Self::Crc32c, Self::Sha1, Self::Sha256];
+
+    /// The name used in `x-amz-sdk-checksum-algorithm`, e.g. `CRC32C`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Crc32 => "CRC32",
+            Self::Crc32c => "CRC32C",
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+        }
+    }
+
+    /// Parses an algorithm name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|algorithm| algorithm.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Length in bytes of a digest, before base64 encoding.
+    pub fn digest_length(&self) -> usize {
+        match self {
+            Self::Crc32 | Self::Crc32c => 4,
+            Self::Sha1 => 20,
+            Self::Sha256 => 32,
+        }
+    }
+}
+
+/// A checksum in the form a client supplies it: the base64 encoding of the
+/// big-endian digest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientChecksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub value: String,
+}
+
+/// Digests to compute while staging data, beyond the `Checksum` every
+/// object gets, so they can be checked against the ones a client sent.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UploadDigests {
+    pub content_md5: bool,
+    pub checksum: Option<ChecksumAlgorithm>,
+}
+
+/// Incrementally computes one client checksum.
+pub(crate) enum ChecksumHasher {
+    Crc32(crc32fast::Hasher),
+    Crc32c(u32),
+    Sha1(openssl::sha::Sha1),
+    Sha256(Sha256),
+}
+
+impl ChecksumHasher {
+    pub(crate) fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Crc32 => Self::Crc32(crc32fast::Hasher::new()),
+            ChecksumAlgorithm::Crc32c => Self::Crc32c(!0),
+            ChecksumAlgorithm::Sha1 => Self::Sha1(openssl::sha::Sha1::new()),
+            ChecksumAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Crc32(hasher) => hasher.update(data),
+            Self::Crc32c(crc) => *crc = crc32c_update(*crc, data),
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    pub(crate) fn finish(self) -> ClientChecksum {
+        let (algorithm, digest) = match self {
+            Self::Crc32(hasher) => (ChecksumAlgorithm::Crc32, hasher.finalize().to_be_bytes().to_vec()),
+            Self::Crc32c(crc) => (ChecksumAlgorithm::Crc32c, (!crc).to_be_bytes().to_vec()),
+            Self::Sha1(hasher) => (ChecksumAlgorithm::Sha1, hasher.finish().to_vec()),
+            Self::Sha256(hasher) => (ChecksumAlgorithm::Sha256, hasher.finalize().to_vec()),
+        };
+        ClientChecksum { algorithm, value: base64::encode_block(&digest) }
+    }
+}
+
+/// CRC-32C (Castagnoli), the reflected form of polynomial 0x1EDC6F41.
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32c_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = CRC32C_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{Result, StorageError};
+
+/// Most rules one CORS configuration may hold.
+pub const MAX_CORS_RULES: usize = 100;
+
+/// Methods a CORS rule may allow.
+pub const CORS_METHODS: &[&str] = &["GET", "PUT", "POST", "DELETE", "HEAD"];
+
+/// A bucket's cross-origin resource sharing rules. Requests are matched
+/// against the rules in order and the first match applies.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorsConfiguration {
+    pub rules: Vec<CorsRule>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorsRule {
+    pub id: Option<String>,
+    /// Origins the rule applies to; each may hold one `*` wildcard.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    /// Headers a preflight may ask for; each may hold one `*` wildcard.
+    pub allowed_headers: Vec<String>,
+    /// Response headers browsers may expose to the calling script.
+    pub expose_headers: Vec<String>,
+    /// How long browsers may cache a preflight response.
+    pub max_age_seconds: Option<u32>,
+}
+
+impl CorsConfiguration {
+    /// Checks the constraints S3 places on a PutBucketCors.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| Err(StorageError::InvalidArgument(message));
+
+        if self.rules.is_empty() || self.rules.len() > MAX_CORS_RULES {
+            return invalid(format!("A CORS configuration must have between 1 and {} rules", MAX_CORS_RULES));
+        }
+
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if let Some(id) = &rule.id {
+                if id.len() > 255 {
+                    return invalid("ID length should not exceed allowed limit of 255".to_string());
+                }
+                if !ids.insert(id) {
+                    return invalid("Rule ID must be unique. Found same ID for more than one rule".to_string());
+                }
+            }
+            if rule.allowed_origins.is_empty() || rule.allowed_methods.is_empty() {
+                return invalid("Each CORS rule needs at least one AllowedOrigin and AllowedMethod".to_string());
+            }
+            if let Some(method) = rule.allowed_methods.iter().find(|m| !CORS_METHODS.contains(&m.as_str())) {
+                return invalid(format!("Found unsupported HTTP method in CORS config. Unsupported method is {}", method));
+            }
+            let wildcards = |values: &[String]| values.iter().find(|v| v.matches('*').count() > 1).cloned();
+            if let Some(origin) = wildcards(&rule.allowed_origins) {
+                return invalid(format!("AllowedOrigin \"{}\" can not have more than one wildcard", origin));
+            }
+            if let Some(header) = wildcards(&rule.allowed_headers) {
+                return invalid(format!("AllowedHeader \"{}\" can not have more than one wildcard", header));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The first rule allowing a request from `origin` using `method` that
+    /// sends `request_headers`, which are only known for preflights.
+    pub fn find_rule(&self, origin: &str, method: &str, request_headers: &[&str]) -> Option<&CorsRule> {
+        self.rules.iter().find(|rule| {
+            rule.allows_origin(origin)
+                && rule.allowed_methods.iter().any(|m| m == method)
+                && request_headers.iter().all(|header| rule.allows_header(header))
+        })
+    }
+}
+
+impl CorsRule {
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|pattern| wildcard_match(pattern, origin))
+    }
+
+    /// Whether the rule lets any origin in, in which case responses need not
+    /// name the caller's origin.
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|pattern| pattern == "*")
+    }
+
+    /// Header names are compared case-insensitively.
+    pub fn allows_header(&self, header: &str) -> bool {
+        let header = header.to_ascii_lowercase();
+        self.allowed_headers.iter().any(|pattern| wildcard_match(&pattern.to_ascii_lowercase(), &header))
+    }
+}
+
+/// Matches `value` against a pattern holding at most one `*`, which stands
+/// for any run of characters.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            value.len() >= prefix.len() + suffix.len() && value.starts_with(prefix) && value.ends_with(suffix)
+        }
+        None => pattern == value,
+    }
+}
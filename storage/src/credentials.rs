@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+
+const ACCESS_KEY_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const SECRET_KEY_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// An access-key/secret pair used to sign S3 requests.
+///
+/// The secret is kept in the clear because SigV4 verification needs it to
+/// derive the signing key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credential {
+    pub access_key: String,
+    pub secret_key: String,
+    pub owner_id: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Credential {
+    pub fn generate(owner_id: &str) -> Self {
+        Self {
+            access_key: format!("O3{}", random_string(ACCESS_KEY_ALPHABET, 18)),
+            secret_key: random_string(SECRET_KEY_ALPHABET, 40),
+            owner_id: owner_id.to_string(),
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Same key and owner with a freshly generated secret.
+    pub fn rotated(&self) -> Self {
+        Self {
+            secret_key: random_string(SECRET_KEY_ALPHABET, 40),
+            ..self.clone()
+        }
+    }
+}
+
+fn random_string(alphabet: &[u8], len: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..len)
+        .map(|_| alphabet[rng.gen_range(0..alphabet.len())] as char)
+        .collect()
+}
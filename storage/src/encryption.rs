@@ -0,0 +1,390 @@
+use openssl::base64;
+use openssl::hash::{hash, MessageDigest};
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, ReadBuf};
+
+use crate::{Result, StorageError};
+
+/// Plaintext bytes sealed under one authentication tag. Encrypted data is
+/// stored as a random nonce followed by one sealed segment per
+/// `SEGMENT_SIZE` bytes, so ranges can be read without decrypting what
+/// precedes them.
+pub(crate) const SEGMENT_SIZE: usize = 64 * 1024;
+pub const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+
+/// The only algorithm offered, as named by `x-amz-server-side-encryption`.
+pub const SSE_ALGORITHM: &str = "AES256";
+
+/// How a client asks for new data to be encrypted.
+#[derive(Debug, Clone)]
+pub enum ServerSideEncryption {
+    /// SSE-S3: the data key is wrapped by the node's master key.
+    S3,
+    /// SSE-C: the data key is wrapped by a key the client supplies with
+    /// every request that reads or writes the data.
+    Customer(CustomerKey),
+}
+
+/// A customer-supplied 256-bit key. Only its MD5 digest is ever stored.
+#[derive(Clone)]
+pub struct CustomerKey {
+    key: [u8; KEY_LENGTH],
+    key_md5: String,
+}
+
+impl CustomerKey {
+    pub fn new(key: &[u8]) -> Result<Self> {
+        let key: [u8; KEY_LENGTH] = key.try_into().map_err(|_| StorageError::InvalidArgument(
+            "The secret key was invalid for the specified algorithm".to_string()
+        ))?;
+        let digest = hash(MessageDigest::md5(), &key).map_err(encryption_error)?;
+
+        Ok(Self { key, key_md5: base64::encode_block(&digest) })
+    }
+
+    /// Base64 MD5 digest of the key, as sent in
+    /// `x-amz-server-side-encryption-customer-key-MD5`.
+    pub fn key_md5(&self) -> &str {
+        &self.key_md5
+    }
+}
+
+impl std::fmt::Debug for CustomerKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomerKey").field("key_md5", &self.key_md5).finish_non_exhaustive()
+    }
+}
+
+/// Which key protects an object's data key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EncryptionMode {
+    S3,
+    Customer { key_md5: String },
+}
+
+/// Encryption of an object version, or of a multipart upload's parts: the
+/// data key, wrapped by the master key or the customer's key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectEncryption {
+    pub mode: EncryptionMode,
+    wrapped_key: String,
+}
+
+impl ObjectEncryption {
+    pub fn customer_key_md5(&self) -> Option<&str> {
+        match &self.mode {
+            EncryptionMode::S3 => None,
+            EncryptionMode::Customer { key_md5 } => Some(key_md5),
+        }
+    }
+}
+
+/// A bucket's default encryption, applied to writes that do not ask for
+/// any. Only SSE-S3 can be a default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketEncryption {
+    pub bucket_key_enabled: bool,
+}
+
+/// Checks that `customer_key` is what reading data stored with `encryption`
+/// requires: SSE-C data needs the key it was written with, and other data
+/// must not be given one.
+pub fn check_customer_key(encryption: Option<&ObjectEncryption>, customer_key: Option<&CustomerKey>) -> Result<()> {
+    match (encryption.and_then(ObjectEncryption::customer_key_md5), customer_key) {
+        (None, None) => Ok(()),
+        (Some(_), None) => Err(StorageError::InvalidRequest(
+            "The object was stored using a form of Server Side Encryption. \
+             The correct parameters must be provided to retrieve the object.".to_string()
+        )),
+        (None, Some(_)) => Err(StorageError::InvalidRequest(
+            "The encryption parameters are not applicable to this object.".to_string()
+        )),
+        (Some(stored), Some(key)) if stored != key.key_md5() => Err(StorageError::InvalidEncryptionKey(
+            "The provided encryption key does not match the one used to encrypt the object".to_string()
+        )),
+        (Some(_), Some(_)) => Ok(()),
+    }
+}
+
+/// The node's key-encryption key for SSE-S3.
+#[derive(Clone)]
+pub(crate) struct MasterKey([u8; KEY_LENGTH]);
+
+impl MasterKey {
+    pub(crate) fn from_bytes(key: [u8; KEY_LENGTH]) -> Self {
+        Self(key)
+    }
+
+    /// The node's master key, or an error if it was not given one. SSE-S3
+    /// is refused rather than protected by a key kept beside the data.
+    pub(crate) fn required(key: Option<&MasterKey>) -> Result<&MasterKey> {
+        key.ok_or_else(|| StorageError::InvalidRequest(
+            "Server-side encryption with AES256 is not available: this node has no master key configured".to_string()
+        ))
+    }
+}
+
+/// Reads a master key for SSE-S3: a file of exactly `KEY_LENGTH` random
+/// bytes, e.g. from `head -c 32 /dev/urandom`.
+pub async fn read_master_key(path: &Path) -> Result<[u8; KEY_LENGTH]> {
+    fs::read(path).await?.try_into().map_err(|_| StorageError::Corruption(
+        format!("Master key {} is not {} bytes", path.display(), KEY_LENGTH)
+    ))
+}
+
+/// The key data is encrypted with. Each object gets its own.
+#[derive(Clone)]
+pub(crate) struct DataKey([u8; KEY_LENGTH]);
+
+impl DataKey {
+    /// A fresh data key for `sse`, and the encryption record holding it
+    /// wrapped for storage.
+    pub(crate) fn generate(sse: &ServerSideEncryption, master_key: Option<&MasterKey>) -> Result<(Self, ObjectEncryption)> {
+        let key = Self(random_bytes()?);
+        let encryption = key.wrap(sse, master_key)?;
+        Ok((key, encryption))
+    }
+
+    /// Recovers the data key of `encryption`, checking `customer_key` first.
+    /// Only SSE-S3 data needs the master key.
+    pub(crate) fn unwrap(
+        encryption: &ObjectEncryption,
+        master_key: Option<&MasterKey>,
+        customer_key: Option<&CustomerKey>,
+    ) -> Result<Self> {
+        check_customer_key(Some(encryption), customer_key)?;
+        let wrapping_key = match customer_key {
+            Some(customer_key) => &customer_key.key,
+            None => &MasterKey::required(master_key)?.0,
+        };
+
+        let wrapped = base64::decode_block(&encryption.wrapped_key)
+            .map_err(|_| StorageError::Corruption("Wrapped data key is not valid base64".to_string()))?;
+        if wrapped.len() != NONCE_LENGTH + KEY_LENGTH + TAG_LENGTH {
+            return Err(StorageError::Corruption("Wrapped data key has the wrong length".to_string()));
+        }
+        let (nonce, rest) = wrapped.split_at(NONCE_LENGTH);
+        let (sealed, tag) = rest.split_at(KEY_LENGTH);
+
+        let key = decrypt_aead(Cipher::aes_256_gcm(), wrapping_key, Some(nonce), &[], sealed, tag)
+            .map_err(|_| StorageError::InvalidEncryptionKey("The data key could not be unwrapped".to_string()))?;
+        Ok(Self(key.try_into().map_err(|_| StorageError::Corruption("Data key has the wrong length".to_string()))?))
+    }
+
+    /// This key wrapped as `sse` asks, for storage alongside the data.
+    pub(crate) fn wrap(&self, sse: &ServerSideEncryption, master_key: Option<&MasterKey>) -> Result<ObjectEncryption> {
+        let (wrapping_key, mode) = match sse {
+            ServerSideEncryption::S3 => (&MasterKey::required(master_key)?.0, EncryptionMode::S3),
+            ServerSideEncryption::Customer(customer_key) => (
+                &customer_key.key,
+                EncryptionMode::Customer { key_md5: customer_key.key_md5.clone() },
+            ),
+        };
+
+        let nonce = random_bytes::<NONCE_LENGTH>()?;
+        let mut tag = [0u8; TAG_LENGTH];
+        let sealed = encrypt_aead(Cipher::aes_256_gcm(), wrapping_key, Some(&nonce), &[], &self.0, &mut tag)
+            .map_err(encryption_error)?;
+
+        Ok(ObjectEncryption {
+            mode,
+            wrapped_key: base64::encode_block(&[&nonce[..], &sealed, &tag].concat()),
+        })
+    }
+}
+
+/// Encrypts a stream of plaintext into the segmented format. Segment `i` is
+/// sealed under the stream's nonce with `i` folded into its last eight
+/// bytes, and the final segment is marked in its associated data so that
+/// truncated data fails to decrypt.
+pub(crate) struct Sealer {
+    key: DataKey,
+    nonce: [u8; NONCE_LENGTH],
+    index: u64,
+    pending: Vec<u8>,
+    header_written: bool,
+}
+
+impl Sealer {
+    pub(crate) fn new(key: DataKey) -> Result<Self> {
+        Ok(Self {
+            key,
+            nonce: random_bytes()?,
+            index: 0,
+            pending: Vec::with_capacity(SEGMENT_SIZE),
+            header_written: false,
+        })
+    }
+
+    /// Takes more plaintext, returning whatever ciphertext is ready. A full
+    /// segment is held back until it is known not to be the last.
+    pub(crate) fn update(&mut self, mut data: &[u8]) -> Result<Vec<u8>> {
+        let mut output = self.header();
+        while !data.is_empty() {
+            if self.pending.len() == SEGMENT_SIZE {
+                let mut segment = std::mem::take(&mut self.pending);
+                output.extend(self.seal(&segment, false)?);
+                segment.clear();
+                self.pending = segment;
+            }
+            let n = (SEGMENT_SIZE - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..n]);
+            data = &data[n..];
+        }
+        Ok(output)
+    }
+
+    /// Seals the remaining plaintext as the final segment, which may be
+    /// empty.
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>> {
+        let mut output = self.header();
+        let segment = std::mem::take(&mut self.pending);
+        output.extend(self.seal(&segment, true)?);
+        Ok(output)
+    }
+
+    fn header(&mut self) -> Vec<u8> {
+        if std::mem::replace(&mut self.header_written, true) {
+            Vec::new()
+        } else {
+            self.nonce.to_vec()
+        }
+    }
+
+    fn seal(&mut self, segment: &[u8], last: bool) -> Result<Vec<u8>> {
+        let mut tag = [0u8; TAG_LENGTH];
+        let mut sealed = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key.0,
+            Some(&segment_nonce(&self.nonce, self.index)),
+            &[last as u8],
+            segment,
+            &mut tag,
+        ).map_err(encryption_error)?;
+        sealed.extend_from_slice(&tag);
+        self.index += 1;
+        Ok(sealed)
+    }
+}
+
+/// Decrypts data in the segmented format as it is read. It knows the
+/// plaintext size, so a segment cut short or missing fails the read.
+pub(crate) struct DecryptingReader<R> {
+    inner: R,
+    key: DataKey,
+    nonce: [u8; NONCE_LENGTH],
+    size: u64,
+    index: u64,
+    /// Plaintext bytes to drop from the first segment, for ranges.
+    skip: usize,
+    sealed: Vec<u8>,
+    filled: usize,
+    output: Vec<u8>,
+    position: usize,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> DecryptingReader<R> {
+    /// Positions `inner`, which holds `size` bytes of encrypted plaintext,
+    /// to decrypt from plaintext `offset` onwards.
+    pub(crate) async fn open(mut inner: R, key: DataKey, size: u64, offset: u64) -> Result<Self> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        inner.read_exact(&mut nonce).await?;
+
+        let index = offset / SEGMENT_SIZE as u64;
+        let start = NONCE_LENGTH as u64 + index * (SEGMENT_SIZE + TAG_LENGTH) as u64;
+        inner.seek(io::SeekFrom::Start(start)).await?;
+
+        Ok(Self {
+            inner,
+            key,
+            nonce,
+            size,
+            index,
+            skip: (offset % SEGMENT_SIZE as u64) as usize,
+            sealed: vec![0u8; SEGMENT_SIZE + TAG_LENGTH],
+            filled: 0,
+            output: Vec::new(),
+            position: 0,
+        })
+    }
+}
+
+impl<R> DecryptingReader<R> {
+    fn last_index(&self) -> u64 {
+        self.size.saturating_sub(1) / SEGMENT_SIZE as u64
+    }
+
+    fn segment_length(&self) -> usize {
+        let start = self.index * SEGMENT_SIZE as u64;
+        (self.size - start.min(self.size)).min(SEGMENT_SIZE as u64) as usize
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for DecryptingReader<R> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.position < this.output.len() {
+                let n = buf.remaining().min(this.output.len() - this.position);
+                buf.put_slice(&this.output[this.position..this.position + n]);
+                this.position += n;
+                return Poll::Ready(Ok(()));
+            }
+            if this.index > this.last_index() {
+                return Poll::Ready(Ok(()));
+            }
+
+            let wanted = this.segment_length() + TAG_LENGTH;
+            while this.filled < wanted {
+                let mut read = ReadBuf::new(&mut this.sealed[this.filled..wanted]);
+                ready!(Pin::new(&mut this.inner).poll_read(cx, &mut read))?;
+                if read.filled().is_empty() {
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Encrypted data is truncated")));
+                }
+                this.filled += read.filled().len();
+            }
+
+            let last = this.index == this.last_index();
+            let (sealed, tag) = this.sealed[..wanted].split_at(wanted - TAG_LENGTH);
+            this.output = decrypt_aead(
+                Cipher::aes_256_gcm(),
+                &this.key.0,
+                Some(&segment_nonce(&this.nonce, this.index)),
+                &[last as u8],
+                sealed,
+                tag,
+            ).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Encrypted data failed authentication"))?;
+            this.position = std::mem::take(&mut this.skip);
+            this.filled = 0;
+            this.index += 1;
+        }
+    }
+}
+
+fn segment_nonce(nonce: &[u8; NONCE_LENGTH], index: u64) -> [u8; NONCE_LENGTH] {
+    let mut segment_nonce = *nonce;
+    for (byte, index_byte) in segment_nonce[NONCE_LENGTH - 8..].iter_mut().zip(index.to_be_bytes()) {
+        *byte ^= index_byte;
+    }
+    segment_nonce
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    rand_bytes(&mut bytes).map_err(encryption_error)?;
+    Ok(bytes)
+}
+
+fn encryption_error(e: openssl::error::ErrorStack) -> StorageError {
+    StorageError::Encryption(e.to_string())
+}
@@ -1,12 +1,53 @@
 mod engine;
+mod bucket;
 mod object;
 mod metadata;
 mod versioning;
+mod credentials;
+mod multipart;
+mod staging;
+mod lock;
+mod lifecycle;
+mod tagging;
+mod policy;
+mod acl;
+mod cors;
+mod encryption;
+mod checksum;
 
-pub use engine::StorageEngine;
-pub use object::{Object, ObjectId, ObjectMetadata, ObjectReference};
+pub use engine::{ObjectReader, StorageEngine};
+pub use bucket::{validate_bucket_name, Bucket, VersioningStatus, DEFAULT_REGION};
+pub use object::{
+    validate_object_key, Checksum, ListObjectsPage, Object, ObjectAttributes, ObjectId, ObjectMetadata, ObjectRecord,
+    ObjectReference, MAX_KEY_LENGTH,
+};
 pub use metadata::MetadataStore;
-pub use versioning::{Version, VersionedObject};
+pub use versioning::{
+    format_version_id, parse_version_id, DeleteMarker, DeleteResult, ListVersionsResponse, ObjectVersion,
+    Version, VersionedObject, NULL_VERSION,
+};
+pub use credentials::Credential;
+pub use multipart::{ListPartsPage, ListUploadsPage, MultipartUpload, PartInfo, MIN_PART_SIZE, MAX_PART_NUMBER};
+pub use staging::StagedData;
+pub use lifecycle::{
+    Expiration, LifecycleConfiguration, LifecycleFilter, LifecycleReport, LifecycleRule, NoncurrentVersionExpiration,
+    MAX_LIFECYCLE_DAYS, MAX_LIFECYCLE_RULES,
+};
+pub use lock::{
+    DefaultRetention, ObjectLock, ObjectLockConfiguration, Retention, RetentionMode, MAX_RETENTION_DAYS, MAX_RETENTION_YEARS,
+};
+pub use acl::{
+    AccessControlList, CannedAcl, Grant, Grantee, Permission, ALL_USERS_GROUP, AUTHENTICATED_USERS_GROUP,
+    MAX_ACL_GRANTS,
+};
+pub use cors::{CorsConfiguration, CorsRule, CORS_METHODS, MAX_CORS_RULES};
+pub use encryption::{
+    check_customer_key, read_master_key, BucketEncryption, CustomerKey, EncryptionMode, ObjectEncryption,
+    ServerSideEncryption, KEY_LENGTH, SSE_ALGORITHM,
+};
+pub use checksum::{ChecksumAlgorithm, ClientChecksum, UploadDigests};
+pub use policy::{BucketPolicy, PolicyEffect, PolicyRequest, MAX_POLICY_SIZE};
+pub use tagging::{validate_tags, TagSet, MAX_OBJECT_TAGS, MAX_TAG_KEY_LENGTH, MAX_TAG_VALUE_LENGTH};
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -53,6 +94,60 @@ pub enum StorageError {
     
     #[error("Invalid object: {0}")]
     InvalidObject(String),
+    
+    #[error("Multipart upload not found: {0}")]
+    NoSuchUpload(String),
+    
+    #[error("Invalid part: {0}")]
+    InvalidPart(String),
+    
+    #[error("Invalid part order: {0}")]
+    InvalidPartOrder(String),
+    
+    #[error("Entity too small: {0}")]
+    EntityTooSmall(String),
+    
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    
+    #[error("Bucket not found: {0}")]
+    NoSuchBucket(String),
+    
+    #[error("Bucket already exists: {0}")]
+    BucketAlreadyExists(String),
+    
+    #[error("Bucket not empty: {0}")]
+    BucketNotEmpty(String),
+    
+    #[error("Invalid bucket name: {0}")]
+    InvalidBucketName(String),
+    
+    #[error("Invalid bucket state: {0}")]
+    InvalidBucketState(String),
+    
+    #[error("Key too long: {0}")]
+    KeyTooLong(String),
+    
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    
+    #[error("Object locked: {0}")]
+    ObjectLocked(String),
+    
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+    
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+    
+    #[error("Malformed policy: {0}")]
+    MalformedPolicy(String),
+    
+    #[error("Invalid encryption key: {0}")]
+    InvalidEncryptionKey(String),
+    
+    #[error("Encryption error: {0}")]
+    Encryption(String),
 }
 
 impl From<bincode::Error> for StorageError {
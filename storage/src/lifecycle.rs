@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+
+use crate::{Result, StorageError};
+use crate::versioning::Version;
+
+/// Most rules one lifecycle configuration may hold.
+pub const MAX_LIFECYCLE_RULES: usize = 1000;
+
+/// Longest period, in days, a lifecycle action may wait: the 100 years S3
+/// allows for Object Lock retention, which keeps due dates representable.
+pub const MAX_LIFECYCLE_DAYS: u32 = 36500;
+
+/// A bucket's lifecycle rules, evaluated periodically by the storage engine.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleConfiguration {
+    pub rules: Vec<LifecycleRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleRule {
+    pub id: Option<String>,
+    pub enabled: bool,
+    pub filter: LifecycleFilter,
+    pub expiration: Option<Expiration>,
+    pub noncurrent_version_expiration: Option<NoncurrentVersionExpiration>,
+    /// Days after initiation at which incomplete multipart uploads are aborted.
+    pub abort_incomplete_multipart_upload_days: Option<u32>,
+}
+
+/// Objects a rule applies to: keys starting with `prefix` that carry every
+/// tag in `tags`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleFilter {
+    pub prefix: String,
+    pub tags: Vec<(String, String)>,
+}
+
+/// Expiration of current versions. Exactly one of the fields is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Expiration {
+    pub days: Option<u32>,
+    pub date: Option<DateTime<Utc>>,
+    /// Remove delete markers that no longer hide any version.
+    pub expired_object_delete_marker: bool,
+}
+
+/// Permanent removal of versions some days after they stopped being current.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoncurrentVersionExpiration {
+    pub noncurrent_days: u32,
+    /// How many of the newest noncurrent versions to keep regardless of age.
+    pub newer_noncurrent_versions: Option<u32>,
+}
+
+impl LifecycleConfiguration {
+    /// Checks the constraints S3 places on a PutBucketLifecycleConfiguration.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(StorageError::InvalidArgument(message.to_string()));
+
+        if self.rules.is_empty() || self.rules.len() > MAX_LIFECYCLE_RULES {
+            return invalid("A lifecycle configuration must have between 1 and 1000 rules");
+        }
+
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if let Some(id) = &rule.id {
+                if id.len() > 255 {
+                    return invalid("ID length should not exceed allowed limit of 255");
+                }
+                if !ids.insert(id) {
+                    return invalid("Rule ID must be unique. Found same ID for more than one rule");
+                }
+            }
+
+            if rule.expiration.is_none()
+                && rule.noncurrent_version_expiration.is_none()
+                && rule.abort_incomplete_multipart_upload_days.is_none()
+            {
+                return invalid("At least one action needs to be specified in a rule");
+            }
+
+            if let Some(expiration) = &rule.expiration {
+                let actions = expiration.days.is_some() as u8
+                    + expiration.date.is_some() as u8
+                    + expiration.expired_object_delete_marker as u8;
+                if actions != 1 {
+                    return invalid("Expiration must specify exactly one of Days, Date and ExpiredObjectDeleteMarker");
+                }
+                if expiration.days == Some(0) {
+                    return invalid("'Days' for Expiration action must be a positive integer");
+                }
+                if expiration.days.is_some_and(|days| days > MAX_LIFECYCLE_DAYS) {
+                    return invalid("'Days' for Expiration action must not exceed 36500");
+                }
+                if expiration.date.is_some_and(|date| date != start_of_day(date)) {
+                    return invalid("'Date' must be at midnight GMT");
+                }
+                if expiration.expired_object_delete_marker && !rule.filter.tags.is_empty() {
+                    return invalid("ExpiredObjectDeleteMarker cannot be specified with a tag filter");
+                }
+            }
+
+            if rule.noncurrent_version_expiration.as_ref().is_some_and(|e| e.noncurrent_days == 0) {
+                return invalid("'NoncurrentDays' for NoncurrentVersionExpiration action must be a positive integer");
+            }
+            if rule.noncurrent_version_expiration.as_ref().is_some_and(|e| e.noncurrent_days > MAX_LIFECYCLE_DAYS) {
+                return invalid("'NoncurrentDays' for NoncurrentVersionExpiration action must not exceed 36500");
+            }
+
+            if rule.abort_incomplete_multipart_upload_days == Some(0) {
+                return invalid("'DaysAfterInitiation' for AbortIncompleteMultipartUpload action must be a positive integer");
+            }
+            if rule.abort_incomplete_multipart_upload_days.is_some_and(|days| days > MAX_LIFECYCLE_DAYS) {
+                return invalid("'DaysAfterInitiation' for AbortIncompleteMultipartUpload action must not exceed 36500");
+            }
+            if rule.abort_incomplete_multipart_upload_days.is_some() && !rule.filter.tags.is_empty() {
+                return invalid("AbortIncompleteMultipartUpload cannot be specified with a tag filter");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// One stored version as seen by lifecycle evaluation.
+#[derive(Debug, Clone)]
+pub(crate) struct LifecycleVersion {
+    pub key: String,
+    pub version_id: Version,
+    pub created_at: DateTime<Utc>,
+    pub is_delete_marker: bool,
+    /// 1 for the current version, counting up through older versions.
+    pub rank: u64,
+    /// When the next newer version was written, i.e. when this one became
+    /// noncurrent.
+    pub superseded_at: Option<DateTime<Utc>>,
+    /// Number of versions (including delete markers) stored for the key.
+    pub version_count: u64,
+    pub tags: Vec<(String, String)>,
+}
+
+/// Something a lifecycle rule wants done to a key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum LifecycleAction {
+    /// Expire the current version, as a delete without a version ID would.
+    ExpireCurrent { key: String },
+    /// Remove one version or delete marker permanently.
+    RemoveVersion { key: String, version_id: Version },
+}
+
+impl LifecycleRule {
+    pub(crate) fn matches(&self, key: &str, tags: &[(String, String)]) -> bool {
+        key.starts_with(&self.filter.prefix)
+            && self.filter.tags.iter().all(|wanted| tags.contains(wanted))
+    }
+
+    /// Actions due under this rule at `now`, given every version of the keys
+    /// under the rule's prefix.
+    pub(crate) fn due_actions(&self, versions: &[LifecycleVersion], now: DateTime<Utc>) -> Vec<LifecycleAction> {
+        let mut actions = Vec::new();
+        if !self.enabled {
+            return actions;
+        }
+
+        for version in versions.iter().filter(|v| self.matches(&v.key, &v.tags)) {
+            if version.rank == 1 {
+                let Some(expiration) = &self.expiration else { continue };
+
+                if version.is_delete_marker {
+                    if expiration.expired_object_delete_marker && version.version_count == 1 {
+                        actions.push(LifecycleAction::RemoveVersion {
+                            key: version.key.clone(),
+                            version_id: version.version_id,
+                        });
+                    }
+                    continue;
+                }
+
+                let expires_at = match (expiration.days, expiration.date) {
+                    (Some(days), _) => expiry_after(version.created_at, days),
+                    (None, Some(date)) => Some(date),
+                    (None, None) => None,
+                };
+                if expires_at.is_some_and(|at| at <= now) {
+                    actions.push(LifecycleAction::ExpireCurrent { key: version.key.clone() });
+                }
+                continue;
+            }
+
+            let Some(noncurrent) = &self.noncurrent_version_expiration else { continue };
+            let Some(superseded_at) = version.superseded_at else { continue };
+
+            // Ranks 2..=N+1 are the N newest noncurrent versions
+            let kept = noncurrent.newer_noncurrent_versions.unwrap_or(0) as u64;
+            if version.rank <= kept + 1 {
+                continue;
+            }
+            if expiry_after(superseded_at, noncurrent.noncurrent_days).is_some_and(|at| at <= now) {
+                actions.push(LifecycleAction::RemoveVersion {
+                    key: version.key.clone(),
+                    version_id: version.version_id,
+                });
+            }
+        }
+
+        actions
+    }
+}
+
+/// When something `days` days after `from` is due. Like S3, the result is
+/// rounded up to the following midnight UTC. `None` if that lies beyond
+/// the representable range, i.e. never.
+pub(crate) fn expiry_after(from: DateTime<Utc>, days: u32) -> Option<DateTime<Utc>> {
+    let due = from.checked_add_signed(Duration::days(days as i64))?;
+    let midnight = start_of_day(due);
+    if midnight == due {
+        Some(due)
+    } else {
+        midnight.checked_add_signed(Duration::days(1))
+    }
+}
+
+fn start_of_day(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+/// What one lifecycle pass did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleReport {
+    pub expired_objects: u64,
+    pub removed_versions: u64,
+    pub aborted_uploads: u64,
+    /// Actions skipped because Object Lock protects the version.
+    pub skipped_locked: u64,
+}
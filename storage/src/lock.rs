@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{Result, StorageError};
+
+/// Longest default retention S3 accepts in `Days`, and the furthest ahead a
+/// retain-until date may be.
+pub const MAX_RETENTION_DAYS: u32 = 36500;
+
+/// Longest default retention S3 accepts in `Years`.
+pub const MAX_RETENTION_YEARS: u32 = 100;
+
+/// How strictly a retention period is enforced. Governance retention can be
+/// lifted by privileged callers; compliance retention cannot be lifted or
+/// shortened by anyone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionMode {
+    Governance,
+    Compliance,
+}
+
+impl RetentionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetentionMode::Governance => "GOVERNANCE",
+            RetentionMode::Compliance => "COMPLIANCE",
+        }
+    }
+
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "GOVERNANCE" => Some(RetentionMode::Governance),
+            "COMPLIANCE" => Some(RetentionMode::Compliance),
+            _ => None,
+        }
+    }
+}
+
+/// Retention period of one object version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    pub mode: RetentionMode,
+    pub retain_until: DateTime<Utc>,
+}
+
+impl Retention {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.retain_until > now
+    }
+
+    /// Fails unless the retention runs out in the future, but no more than
+    /// `MAX_RETENTION_DAYS` from `now`.
+    pub fn check_period(&self, now: DateTime<Utc>) -> Result<()> {
+        if !self.is_active(now) {
+            return Err(StorageError::InvalidArgument(
+                "The retain until date must be in the future".to_string()
+            ));
+        }
+        if self.retain_until > now + Duration::days(MAX_RETENTION_DAYS as i64) {
+            return Err(StorageError::InvalidArgument(
+                "The retain until date must be no more than 100 years in the future".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Object Lock state of one object version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectLock {
+    pub retention: Option<Retention>,
+    pub legal_hold: bool,
+}
+
+impl ObjectLock {
+    pub fn is_empty(&self) -> bool {
+        self.retention.is_none() && !self.legal_hold
+    }
+
+    /// Fails with `ObjectLocked` if the version may not be deleted or
+    /// overwritten. Active governance retention yields to
+    /// `bypass_governance`; a legal hold and compliance retention never do.
+    pub fn check_removable(&self, now: DateTime<Utc>, bypass_governance: bool) -> Result<()> {
+        if self.legal_hold {
+            return Err(StorageError::ObjectLocked("The object version is under legal hold".to_string()));
+        }
+
+        match self.retention {
+            Some(retention) if retention.is_active(now) => match retention.mode {
+                RetentionMode::Governance if bypass_governance => Ok(()),
+                mode => Err(StorageError::ObjectLocked(format!(
+                    "The object version is under {} retention until {}",
+                    mode.as_str(),
+                    retention.retain_until.to_rfc3339()
+                ))),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that replacing this version's retention with `new` does not
+    /// weaken an active retention period. Compliance retention may only be
+    /// extended; governance retention may be shortened or removed only with
+    /// `bypass_governance`.
+    pub fn check_retention_change(
+        &self,
+        new: Option<&Retention>,
+        now: DateTime<Utc>,
+        bypass_governance: bool,
+    ) -> Result<()> {
+        let Some(current) = self.retention.filter(|r| r.is_active(now)) else {
+            return Ok(());
+        };
+
+        let weakened = match new {
+            None => true,
+            Some(new) => {
+                new.retain_until < current.retain_until
+                    || (current.mode == RetentionMode::Compliance && new.mode != RetentionMode::Compliance)
+            }
+        };
+        if !weakened {
+            return Ok(());
+        }
+
+        match current.mode {
+            RetentionMode::Governance if bypass_governance => Ok(()),
+            mode => Err(StorageError::ObjectLocked(format!(
+                "{} retention until {} cannot be shortened or removed",
+                mode.as_str(),
+                current.retain_until.to_rfc3339()
+            ))),
+        }
+    }
+}
+
+/// Default retention applied to new versions written to a bucket without
+/// explicit retention settings. Exactly one of `days` and `years` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefaultRetention {
+    pub mode: RetentionMode,
+    pub days: Option<u32>,
+    pub years: Option<u32>,
+}
+
+impl DefaultRetention {
+    /// Checks that exactly one period is set, within S3's limits.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(StorageError::InvalidArgument(message.to_string()));
+
+        match (self.days, self.years) {
+            (Some(0), None) | (None, Some(0)) => invalid("Default retention period must be a positive integer value"),
+            (Some(days), None) if days > MAX_RETENTION_DAYS => invalid("Default retention period is too large"),
+            (None, Some(years)) if years > MAX_RETENTION_YEARS => invalid("Default retention period is too large"),
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => invalid("DefaultRetention must specify exactly one of Days and Years"),
+        }
+    }
+
+    /// Retention for a version created at `created_at`. A period running
+    /// past the representable range retains until the end of it.
+    pub fn retention_from(&self, created_at: DateTime<Utc>) -> Retention {
+        let days = self.days.unwrap_or(0) as i64 + self.years.unwrap_or(0) as i64 * 365;
+        Retention {
+            mode: self.mode,
+            retain_until: Duration::try_days(days)
+                .and_then(|period| created_at.checked_add_signed(period))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+}
+
+/// Object Lock settings of a bucket. Its presence on a bucket means Object
+/// Lock is enabled, which is only possible at creation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectLockConfiguration {
+    pub default_retention: Option<DefaultRetention>,
+}
+
+impl ObjectLockConfiguration {
+    pub fn validate(&self) -> Result<()> {
+        self.default_retention.as_ref().map_or(Ok(()), DefaultRetention::validate)
+    }
+}
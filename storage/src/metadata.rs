@@ -1,8 +1,11 @@
-use arrow::array::{StringArray, UInt64Array, BooleanArray, TimestampMillisecondArray};
+use arrow::array::{new_null_array, Array, ArrayRef, StringArray, UInt32Array, UInt64Array, Int64Array, BooleanArray, TimestampMillisecondArray};
 use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use arrow::record_batch::RecordBatch;
+use datafusion::datasource::file_format::options::ReadOptions;
+use datafusion::datasource::listing::{ListingTable, ListingTableConfig, ListingTableUrl};
 use datafusion::prelude::*;
 use datafusion::execution::context::SessionContext;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::arrow_writer::ArrowWriter;
 use parquet::file::properties::WriterProperties;
 use std::path::{Path, PathBuf};
@@ -11,10 +14,62 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde_json;
 use tokio::fs;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use crate::{Result, StorageError};
-use crate::object::{Object, ObjectReference};
-use crate::versioning::{VersionedObject, Version};
+use crate::bucket::{Bucket, VersioningStatus};
+use crate::credentials::Credential;
+use crate::lifecycle::LifecycleVersion;
+use crate::acl::AccessControlList;
+use crate::checksum::ClientChecksum;
+use crate::encryption::ObjectEncryption;
+use crate::policy::BucketPolicy;
+use crate::lock::{ObjectLock, Retention, RetentionMode};
+use crate::multipart::{MultipartUpload, PartInfo};
+use crate::tagging::TagSet;
+use crate::object::{Checksum, ListObjectsPage, Object, ObjectId, ObjectMetadata, ObjectRecord, ObjectReference};
+use crate::versioning::{DeleteMarker, ListVersionsResponse, ObjectVersion, VersionedObject, Version};
+
+/// A table of the store, kept in `<name>.parquet` and registered with
+/// DataFusion under `name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Table {
+    Objects,
+    Buckets,
+    Replication,
+    Credentials,
+    MultipartUploads,
+    MultipartParts,
+}
+
+impl Table {
+    const ALL: [Table; 6] = [
+        Table::Objects,
+        Table::Buckets,
+        Table::Replication,
+        Table::Credentials,
+        Table::MultipartUploads,
+        Table::MultipartParts,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Table::Objects => "objects",
+            Table::Buckets => "buckets",
+            Table::Replication => "replication",
+            Table::Credentials => "credentials",
+            Table::MultipartUploads => "multipart_uploads",
+            Table::MultipartParts => "multipart_parts",
+        }
+    }
+}
+
+/// Exclusive hold on one table, which every write to it requires. A writer
+/// keeps it from the reads its change depends on through the rewrite.
+struct TableWriter<'a> {
+    table: Table,
+    _guard: RwLockWriteGuard<'a, ()>,
+}
 
 pub struct MetadataStore {
     storage_path: PathBuf,
@@ -22,6 +77,14 @@ pub struct MetadataStore {
     objects_schema: Arc<Schema>,
     buckets_schema: Arc<Schema>,
     replication_schema: Arc<Schema>,
+    credentials_schema: Arc<Schema>,
+    uploads_schema: Arc<Schema>,
+    parts_schema: Arc<Schema>,
+    /// One lock per table, indexed by `Table`. Tables are rewritten whole on
+    /// every change, so writers hold the lock exclusively across each
+    /// read-modify-write and never lose each other's rows; readers share it
+    /// so no query sees the table while it is being swapped.
+    table_locks: [RwLock<()>; Table::ALL.len()],
 }
 
 impl MetadataStore {
@@ -31,7 +94,11 @@ impl MetadataStore {
         // Create metadata directory
         fs::create_dir_all(&storage_path).await?;
         
-        let ctx = SessionContext::new();
+        // Read strings back as plain Utf8 so they match our schemas and
+        // downcast to StringArray.
+        let config = SessionConfig::new()
+            .set_bool("datafusion.execution.parquet.schema_force_view_types", false);
+        let ctx = SessionContext::new_with_config(config);
         
         // Define schemas for our parquet files
         let objects_schema = Arc::new(Schema::new(vec![
@@ -47,12 +114,45 @@ impl MetadataStore {
             Field::new("checksum_sha256", DataType::Utf8, false),
             Field::new("checksum_blake3", DataType::Utf8, false),
             Field::new("is_delete_marker", DataType::Boolean, false),
+            // JSON array of part sizes for objects assembled by multipart upload
+            Field::new("parts", DataType::Utf8, true),
+            // Object Lock: retention mode and date, and legal hold
+            Field::new("lock_mode", DataType::Utf8, true),
+            Field::new("retain_until", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+            Field::new("legal_hold", DataType::Boolean, true),
+            // JSON array of [key, value] tag pairs; null when untagged
+            Field::new("tags", DataType::Utf8, true),
+            // Writer of the version, and its JSON access control list; null
+            // for versions written before ACLs and for delete markers
+            Field::new("owner_id", DataType::Utf8, true),
+            Field::new("acl", DataType::Utf8, true),
+            // JSON encryption record holding the wrapped data key; null for
+            // unencrypted versions and delete markers
+            Field::new("encryption", DataType::Utf8, true),
+            // JSON checksum the client uploaded the data with; null when it
+            // sent none and for delete markers
+            Field::new("checksum_client", DataType::Utf8, true),
         ]));
 
         let buckets_schema = Arc::new(Schema::new(vec![
             Field::new("name", DataType::Utf8, false),
             Field::new("created_at", DataType::Timestamp(TimeUnit::Millisecond, None), false),
             Field::new("region", DataType::Utf8, false),
+            Field::new("owner_id", DataType::Utf8, true),
+            // "Enabled" or "Suspended"; null until versioning is configured
+            Field::new("versioning", DataType::Utf8, true),
+            // JSON Object Lock configuration; null unless enabled at creation
+            Field::new("object_lock", DataType::Utf8, true),
+            // JSON lifecycle configuration; null when none is set
+            Field::new("lifecycle", DataType::Utf8, true),
+            // Bucket policy document as given; null when none is set
+            Field::new("policy", DataType::Utf8, true),
+            // JSON access control list; null for buckets created before ACLs
+            Field::new("acl", DataType::Utf8, true),
+            // JSON CORS configuration; null when none is set
+            Field::new("cors", DataType::Utf8, true),
+            // JSON default encryption configuration; null when none is set
+            Field::new("encryption", DataType::Utf8, true),
         ]));
 
         let replication_schema = Arc::new(Schema::new(vec![
@@ -62,12 +162,48 @@ impl MetadataStore {
             Field::new("is_fully_replicated", DataType::Boolean, false),
         ]));
 
+        let credentials_schema = Arc::new(Schema::new(vec![
+            Field::new("access_key", DataType::Utf8, false),
+            Field::new("secret_key", DataType::Utf8, false),
+            Field::new("owner_id", DataType::Utf8, false),
+            Field::new("enabled", DataType::Boolean, false),
+            Field::new("created_at", DataType::Timestamp(TimeUnit::Millisecond, None), false),
+        ]));
+
+        let uploads_schema = Arc::new(Schema::new(vec![
+            Field::new("upload_id", DataType::Utf8, false),
+            Field::new("bucket", DataType::Utf8, false),
+            Field::new("key", DataType::Utf8, false),
+            Field::new("initiated_at", DataType::Timestamp(TimeUnit::Millisecond, None), false),
+            Field::new("content_type", DataType::Utf8, false),
+            Field::new("custom_metadata", DataType::Utf8, false),
+            Field::new("lock_mode", DataType::Utf8, true),
+            Field::new("retain_until", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+            Field::new("legal_hold", DataType::Boolean, true),
+            Field::new("tags", DataType::Utf8, true),
+            Field::new("owner_id", DataType::Utf8, true),
+            Field::new("acl", DataType::Utf8, true),
+            Field::new("encryption", DataType::Utf8, true),
+        ]));
+
+        let parts_schema = Arc::new(Schema::new(vec![
+            Field::new("upload_id", DataType::Utf8, false),
+            Field::new("part_number", DataType::UInt32, false),
+            Field::new("size", DataType::UInt64, false),
+            Field::new("etag", DataType::Utf8, false),
+            Field::new("last_modified", DataType::Timestamp(TimeUnit::Millisecond, None), false),
+        ]));
+
         let store = Self {
             storage_path,
             ctx,
             objects_schema,
             buckets_schema,
             replication_schema,
+            credentials_schema,
+            uploads_schema,
+            parts_schema,
+            table_locks: Default::default(),
         };
 
         // Initialize parquet files if they don't exist
@@ -77,34 +213,85 @@ impl MetadataStore {
     }
 
     async fn init_parquet_files(&self) -> Result<()> {
-        let objects_path = self.storage_path.join("objects.parquet");
-        let buckets_path = self.storage_path.join("buckets.parquet");
-        let replication_path = self.storage_path.join("replication.parquet");
+        for table in Table::ALL {
+            let schema = self.schema(table);
+            let path = self.storage_path.join(format!("{}.parquet", table.name()));
 
-        // Create empty parquet files if they don't exist
-        if !objects_path.exists() {
-            self.create_empty_parquet(&objects_path, &self.objects_schema).await?;
+            // Create empty parquet files if they don't exist, and bring
+            // files written by older versions up to the current schema
+            if !path.exists() {
+                self.create_empty_parquet(&path, schema).await?;
+            } else {
+                self.migrate_parquet(table, &path, schema)?;
+            }
+
+            // Register parquet files with DataFusion
+            self.ctx.register_parquet(table.name(), path.to_str().unwrap(), ParquetReadOptions::default()).await
+                .map_err(|e| StorageError::Database(format!("Failed to register {} table: {}", table.name(), e)))?;
         }
-        
-        if !buckets_path.exists() {
-            self.create_empty_parquet(&buckets_path, &self.buckets_schema).await?;
+
+        Ok(())
+    }
+
+    fn schema(&self, table: Table) -> &Arc<Schema> {
+        match table {
+            Table::Objects => &self.objects_schema,
+            Table::Buckets => &self.buckets_schema,
+            Table::Replication => &self.replication_schema,
+            Table::Credentials => &self.credentials_schema,
+            Table::MultipartUploads => &self.uploads_schema,
+            Table::MultipartParts => &self.parts_schema,
         }
-        
-        if !replication_path.exists() {
-            self.create_empty_parquet(&replication_path, &self.replication_schema).await?;
+    }
+
+    /// Takes `table` for writing, once current readers and writers are done.
+    async fn write_table(&self, table: Table) -> TableWriter<'_> {
+        TableWriter {
+            table,
+            _guard: self.table_locks[table as usize].write().await,
         }
+    }
 
-        // Register parquet files with DataFusion
-        self.ctx.register_parquet("objects", objects_path.to_str().unwrap(), ParquetReadOptions::default()).await
-            .map_err(|e| StorageError::Database(format!("Failed to register objects table: {}", e)))?;
-            
-        self.ctx.register_parquet("buckets", buckets_path.to_str().unwrap(), ParquetReadOptions::default()).await
-            .map_err(|e| StorageError::Database(format!("Failed to register buckets table: {}", e)))?;
-            
-        self.ctx.register_parquet("replication", replication_path.to_str().unwrap(), ParquetReadOptions::default()).await
-            .map_err(|e| StorageError::Database(format!("Failed to register replication table: {}", e)))?;
+    /// Shares `table` with other readers, keeping writers out while held.
+    async fn read_table(&self, table: Table) -> RwLockReadGuard<'_, ()> {
+        self.table_locks[table as usize].read().await
+    }
 
-        Ok(())
+    /// Adds columns missing from an existing file, filled by
+    /// `missing_column`. New columns are always nullable so this is lossless.
+    fn migrate_parquet(&self, table: Table, path: &Path, schema: &Arc<Schema>) -> Result<()> {
+        let file = std::fs::File::open(path)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+            .map_err(|e| StorageError::Database(format!("Failed to open parquet file: {}", e)))?;
+
+        let file_fields: Vec<&String> = builder.schema().fields().iter().map(|f| f.name()).collect();
+        let schema_fields: Vec<&String> = schema.fields().iter().map(|f| f.name()).collect();
+        if file_fields == schema_fields {
+            return Ok(());
+        }
+
+        tracing::info!("Migrating {:?} to the current schema", path);
+
+        let reader = builder.build()
+            .map_err(|e| StorageError::Database(format!("Failed to read parquet file: {}", e)))?;
+
+        let mut batches = Vec::new();
+        for batch in reader {
+            let batch = batch
+                .map_err(|e| StorageError::Database(format!("Failed to read batch: {}", e)))?;
+
+            let columns = schema.fields().iter()
+                .map(|field| match batch.column_by_name(field.name()) {
+                    Some(column) => column.clone(),
+                    None => missing_column(table, field, batch.num_rows()),
+                })
+                .collect();
+
+            batches.push(RecordBatch::try_new(schema.clone(), columns)
+                .map_err(|e| StorageError::Database(format!("Failed to migrate batch: {}", e)))?);
+        }
+
+        Self::write_parquet_file(path, schema.clone(), batches)
     }
 
     async fn create_empty_parquet(&self, path: &Path, schema: &Schema) -> Result<()> {
@@ -127,24 +314,75 @@ impl MetadataStore {
     }
 
     pub async fn store_object(&self, object: &Object) -> Result<()> {
-        let custom_metadata_json = serde_json::to_string(&object.metadata.custom_metadata)
+        self.store_object_record(&object.id, &object.metadata, &object.checksum).await?;
+        Ok(())
+    }
+
+    /// Records a stored object version whose data is already on disk. A
+    /// version with the same ID (in practice, the null version) is replaced;
+    /// the data ID it pointed at is returned so the caller can reclaim it.
+    pub async fn store_object_record(&self, id: &str, metadata: &ObjectMetadata, checksum: &Checksum) -> Result<Option<ObjectId>> {
+        let batch = self.object_batch(id, metadata, checksum)?;
+
+        let table = self.write_table(Table::Objects).await;
+        self.replace_version(&table, &metadata.bucket, &metadata.key, metadata.version_id, batch).await
+    }
+
+    /// Like `store_object_record`, but fails with `PreconditionFailed` if the
+    /// key already has a current version that is not a delete marker. The
+    /// check and the write happen under one lock, so of two concurrent
+    /// create-only writes at most one succeeds.
+    pub async fn store_new_object_record(&self, id: &str, metadata: &ObjectMetadata, checksum: &Checksum) -> Result<Option<ObjectId>> {
+        let batch = self.object_batch(id, metadata, checksum)?;
+
+        let table = self.write_table(Table::Objects).await;
+        let sql = format!(
+            "SELECT COUNT(*) as count FROM (
+                 SELECT is_delete_marker, ROW_NUMBER() OVER (ORDER BY created_at DESC) AS version_rank
+                 FROM objects WHERE bucket = {} AND key = {}
+             ) WHERE version_rank = 1 AND is_delete_marker = false",
+            sql_string(&metadata.bucket),
+            sql_string(&metadata.key)
+        );
+        if self.any_rows(&sql).await? {
+            return Err(StorageError::PreconditionFailed(
+                format!("{}/{} already exists", metadata.bucket, metadata.key)
+            ));
+        }
+
+        self.replace_version(&table, &metadata.bucket, &metadata.key, metadata.version_id, batch).await
+    }
+
+    fn object_batch(&self, id: &str, metadata: &ObjectMetadata, checksum: &Checksum) -> Result<RecordBatch> {
+        let custom_metadata_json = serde_json::to_string(&metadata.custom_metadata)
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+        let parts_json = metadata.parts.as_ref()
+            .map(serde_json::to_string)
+            .transpose()
             .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
 
         // Create arrays for the new record
-        let ids = StringArray::from(vec![object.id.as_str()]);
-        let buckets = StringArray::from(vec![object.metadata.bucket.as_str()]);
-        let keys = StringArray::from(vec![object.metadata.key.as_str()]);
-        let version_ids = StringArray::from(vec![object.metadata.version_id.to_string()]);
-        let sizes = UInt64Array::from(vec![object.metadata.size]);
-        let etags = StringArray::from(vec![object.metadata.etag.as_str()]);
-        let content_types = StringArray::from(vec![object.metadata.content_type.as_str()]);
-        let created_ats = TimestampMillisecondArray::from(vec![object.metadata.created_at.timestamp_millis()]);
+        let ids = StringArray::from(vec![id]);
+        let buckets = StringArray::from(vec![metadata.bucket.as_str()]);
+        let keys = StringArray::from(vec![metadata.key.as_str()]);
+        let version_ids = StringArray::from(vec![metadata.version_id.to_string()]);
+        let sizes = UInt64Array::from(vec![metadata.size]);
+        let etags = StringArray::from(vec![metadata.etag.as_str()]);
+        let content_types = StringArray::from(vec![metadata.content_type.as_str()]);
+        let created_ats = TimestampMillisecondArray::from(vec![metadata.created_at.timestamp_millis()]);
         let custom_metadatas = StringArray::from(vec![custom_metadata_json.as_str()]);
-        let checksum_sha256s = StringArray::from(vec![object.checksum.sha256.as_str()]);
-        let checksum_blake3s = StringArray::from(vec![object.checksum.blake3.as_str()]);
+        let checksum_sha256s = StringArray::from(vec![checksum.sha256.as_str()]);
+        let checksum_blake3s = StringArray::from(vec![checksum.blake3.as_str()]);
         let is_delete_markers = BooleanArray::from(vec![false]);
+        let parts = StringArray::from(vec![parts_json]);
+        let (lock_modes, retain_untils, legal_holds) = lock_arrays(Some(&metadata.lock));
+        let tags = tags_array(&metadata.tags)?;
+        let owner_ids = StringArray::from(vec![metadata.owner_id.as_deref()]);
+        let acls = acl_array(metadata.acl.as_ref())?;
+        let encryptions = encryption_array(metadata.encryption.as_ref())?;
+        let client_checksums = client_checksum_array(checksum.client.as_ref())?;
 
-        let batch = RecordBatch::try_new(
+        RecordBatch::try_new(
             self.objects_schema.clone(),
             vec![
                 Arc::new(ids),
@@ -159,15 +397,108 @@ impl MetadataStore {
                 Arc::new(checksum_sha256s),
                 Arc::new(checksum_blake3s),
                 Arc::new(is_delete_markers),
+                Arc::new(parts),
+                Arc::new(lock_modes),
+                Arc::new(retain_untils),
+                Arc::new(legal_holds),
+                Arc::new(tags),
+                Arc::new(owner_ids),
+                Arc::new(acls),
+                Arc::new(encryptions),
+                Arc::new(client_checksums),
             ],
-        ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))?;
+        ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))
+    }
 
-        self.append_to_parquet("objects.parquet", batch).await?;
-        Ok(())
+    /// Writes `batch` in place of any existing row for the same version and
+    /// returns the data ID of the version replaced, if it was not a marker.
+    async fn replace_version(
+        &self,
+        table: &TableWriter<'_>,
+        bucket: &str,
+        key: &str,
+        version_id: Version,
+        batch: RecordBatch,
+    ) -> Result<Option<ObjectId>> {
+        let replaced = self.find_version(bucket, key, version_id).await?;
+        if replaced.is_none() {
+            self.append_to_parquet(table, batch).await?;
+            return Ok(None);
+        }
+
+        let predicate = version_predicate(bucket, key, version_id);
+        self.replace_rows(table, &predicate, Some(batch)).await?;
+
+        Ok(replaced.and_then(|(id, is_delete_marker)| (!is_delete_marker).then_some(id)))
+    }
+
+    /// Creation time for a new version of `key`. Versions are ordered by
+    /// creation time, so this is kept strictly after the newest existing
+    /// version even when two writes land in the same millisecond.
+    pub async fn next_version_time(&self, bucket: &str, key: &str) -> Result<DateTime<Utc>> {
+        let _guard = self.read_table(Table::Objects).await;
+        self.version_time_after_newest(bucket, key).await
+    }
+
+    /// `next_version_time` for callers already holding the objects table.
+    async fn version_time_after_newest(&self, bucket: &str, key: &str) -> Result<DateTime<Utc>> {
+        let sql = format!(
+            "SELECT MAX(created_at) FROM objects WHERE bucket = {} AND key = {}",
+            sql_string(bucket),
+            sql_string(key)
+        );
+
+        let df = self.ctx.sql(&sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let now = Utc::now();
+        let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
+            return Ok(now);
+        };
+
+        let latest_array = batch.column(0).as_any().downcast_ref::<TimestampMillisecondArray>()
+            .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+        if latest_array.is_null(0) {
+            return Ok(now);
+        }
+
+        let after_latest = DateTime::from_timestamp_millis(latest_array.value(0) + 1)
+            .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+            .with_timezone(&Utc);
+
+        Ok(now.max(after_latest))
+    }
+
+    /// The data ID of a version and whether it is a delete marker.
+    async fn find_version(&self, bucket: &str, key: &str, version_id: Version) -> Result<Option<(ObjectId, bool)>> {
+        let sql = format!(
+            "SELECT id, is_delete_marker FROM objects WHERE {}",
+            version_predicate(bucket, key, version_id)
+        );
+
+        let df = self.ctx.sql(&sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
+            return Ok(None);
+        };
+
+        let id_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+            .ok_or_else(|| StorageError::Database("Failed to cast id column".to_string()))?;
+        let is_delete_marker_array = batch.column(1).as_any().downcast_ref::<BooleanArray>()
+            .ok_or_else(|| StorageError::Database("Failed to cast is_delete_marker column".to_string()))?;
+
+        Ok(Some((id_array.value(0).to_string(), is_delete_marker_array.value(0))))
     }
 
-    async fn append_to_parquet(&self, filename: &str, batch: RecordBatch) -> Result<()> {
-        let path = self.storage_path.join(filename);
+    async fn append_to_parquet(&self, table: &TableWriter<'_>, batch: RecordBatch) -> Result<()> {
+        let path = self.table_path(table.table);
         
         // Read existing data
         let existing_df = self.ctx.read_parquet(path.to_str().unwrap(), ParquetReadOptions::default()).await
@@ -180,101 +511,265 @@ impl MetadataStore {
         let mut all_batches = existing_batches;
         all_batches.push(batch);
 
-        // Write all data back
-        let file = std::fs::File::create(&path)
+        self.write_parquet(table, all_batches).await
+    }
+
+    /// Rewrites `table` keeping only rows that do not match `predicate`,
+    /// then appends `replacement` if given.
+    async fn replace_rows(
+        &self,
+        table: &TableWriter<'_>,
+        predicate: &str,
+        replacement: Option<RecordBatch>,
+    ) -> Result<()> {
+        let sql = format!("SELECT * FROM {} WHERE NOT ({})", table.table.name(), predicate);
+
+        let df = self.ctx.sql(&sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let mut batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        batches.extend(replacement);
+
+        self.write_parquet(table, batches).await
+    }
+
+    /// Overwrites a table's parquet file with `batches` and re-registers it.
+    async fn write_parquet(&self, table: &TableWriter<'_>, batches: Vec<RecordBatch>) -> Result<()> {
+        let path = self.table_path(table.table);
+        let schema = self.schema(table.table).clone();
+
+        Self::write_parquet_file(&path, schema.clone(), batches)?;
+
+        // Re-register the updated file. The new table is built first so the
+        // swap leaves no moment where concurrent queries find it missing.
+        let table_path = ListingTableUrl::parse(path.to_str().unwrap())
+            .map_err(|e| StorageError::Database(format!("Invalid table path: {}", e)))?;
+        let options = ParquetReadOptions::default()
+            .to_listing_options(&self.ctx.copied_config(), self.ctx.copied_table_options());
+        let config = ListingTableConfig::new(table_path)
+            .with_listing_options(options)
+            .with_schema(schema);
+        let provider = ListingTable::try_new(config)
+            .map_err(|e| StorageError::Database(format!("Failed to re-register table: {}", e)))?;
+
+        let name = table.table.name();
+        self.ctx.deregister_table(name)
+            .map_err(|e| StorageError::Database(format!("Failed to deregister table: {}", e)))?;
+        self.ctx.register_table(name, Arc::new(provider))
+            .map_err(|e| StorageError::Database(format!("Failed to re-register table: {}", e)))?;
+
+        Ok(())
+    }
+
+    fn table_path(&self, table: Table) -> PathBuf {
+        self.storage_path.join(format!("{}.parquet", table.name()))
+    }
+
+    /// Writes `batches` beside `path` and renames the result into place, so
+    /// readers never see a partly written file.
+    fn write_parquet_file(path: &Path, schema: Arc<Schema>, batches: Vec<RecordBatch>) -> Result<()> {
+        let staging = path.with_extension(format!("{}.tmp", Uuid::new_v4().simple()));
+        let file = std::fs::File::create(&staging)
             .map_err(|e| StorageError::Io(e))?;
-            
+
         let props = WriterProperties::builder().build();
-        let mut writer = ArrowWriter::try_new(file, all_batches[0].schema(), Some(props))
+        let mut writer = ArrowWriter::try_new(file, schema, Some(props))
             .map_err(|e| StorageError::Database(format!("Failed to create parquet writer: {}", e)))?;
 
-        for batch in all_batches {
+        for batch in batches {
             writer.write(&batch)
                 .map_err(|e| StorageError::Database(format!("Failed to write batch: {}", e)))?;
         }
 
         writer.close()
             .map_err(|e| StorageError::Database(format!("Failed to close parquet writer: {}", e)))?;
-
-        // Re-register the updated file
-        self.ctx.deregister_table(filename.strip_suffix(".parquet").unwrap())
-            .map_err(|e| StorageError::Database(format!("Failed to deregister table: {}", e)))?;
-            
-        self.ctx.register_parquet(filename.strip_suffix(".parquet").unwrap(), path.to_str().unwrap(), ParquetReadOptions::default()).await
-            .map_err(|e| StorageError::Database(format!("Failed to re-register table: {}", e)))?;
+        std::fs::rename(&staging, path)?;
 
         Ok(())
     }
 
     pub async fn get_object_metadata(&self, bucket: &str, key: &str, version_id: Option<Version>) -> Result<Option<ObjectReference>> {
-        let sql = if let Some(vid) = version_id {
-            format!(
-                "SELECT id, bucket, key, version_id, size, etag, created_at 
-                 FROM objects 
-                 WHERE bucket = '{}' AND key = '{}' AND version_id = '{}' AND is_delete_marker = false",
-                bucket, key, vid
-            )
-        } else {
-            format!(
-                "SELECT id, bucket, key, version_id, size, etag, created_at 
-                 FROM objects 
-                 WHERE bucket = '{}' AND key = '{}' AND is_delete_marker = false
-                 ORDER BY created_at DESC 
-                 LIMIT 1",
-                bucket, key
-            )
+        Ok(self.get_object_record(bucket, key, version_id).await?
+            .map(|record| ObjectReference::from_record(&record)))
+    }
+
+    /// Full metadata and checksums for the current (or a specific) version.
+    /// `None` if the version does not exist or is a delete marker, which
+    /// includes a key whose current version is a delete marker.
+    pub async fn get_object_record(&self, bucket: &str, key: &str, version_id: Option<Version>) -> Result<Option<ObjectRecord>> {
+        let _guard = self.read_table(Table::Objects).await;
+        let sql = match version_id {
+            Some(vid) => format!(
+                "SELECT {} FROM objects WHERE {} AND is_delete_marker = false",
+                OBJECT_RECORD_COLUMNS,
+                version_predicate(bucket, key, vid)
+            ),
+            None => format!(
+                "SELECT {columns} FROM (
+                     SELECT {columns}, is_delete_marker,
+                            ROW_NUMBER() OVER (ORDER BY created_at DESC) AS version_rank
+                     FROM objects WHERE bucket = {} AND key = {}
+                 ) WHERE version_rank = 1 AND is_delete_marker = false",
+                sql_string(bucket),
+                sql_string(key),
+                columns = OBJECT_RECORD_COLUMNS
+            ),
         };
 
+        Ok(self.query_object_records(&sql).await?.into_iter().next())
+    }
+
+    /// The delete marker at `version_id`, or the current version of the key
+    /// if no version is given, provided it is a delete marker.
+    pub async fn get_delete_marker(&self, bucket: &str, key: &str, version_id: Option<Version>) -> Result<Option<DeleteMarker>> {
+        let _guard = self.read_table(Table::Objects).await;
+        let sql = format!(
+            "SELECT version_id, created_at, is_delete_marker, is_latest FROM (
+                 SELECT version_id, created_at, is_delete_marker,
+                        ROW_NUMBER() OVER (ORDER BY created_at DESC) = 1 AS is_latest
+                 FROM objects WHERE bucket = {} AND key = {}
+             ) WHERE is_delete_marker AND {}",
+            sql_string(bucket),
+            sql_string(key),
+            match version_id {
+                Some(vid) => format!("version_id = {}", sql_string(&vid.to_string())),
+                None => "is_latest".to_string(),
+            }
+        );
+
         let df = self.ctx.sql(&sql).await
             .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
-            
+
         let batches = df.collect().await
             .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
 
-        if batches.is_empty() || batches[0].num_rows() == 0 {
+        let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
             return Ok(None);
-        }
+        };
 
-        let batch = &batches[0];
-        let id_array = batch.column(0).as_any().downcast_ref::<StringArray>()
-            .ok_or_else(|| StorageError::Database("Failed to cast id column".to_string()))?;
-        let bucket_array = batch.column(1).as_any().downcast_ref::<StringArray>()
-            .ok_or_else(|| StorageError::Database("Failed to cast bucket column".to_string()))?;
-        let key_array = batch.column(2).as_any().downcast_ref::<StringArray>()
-            .ok_or_else(|| StorageError::Database("Failed to cast key column".to_string()))?;
-        let version_id_array = batch.column(3).as_any().downcast_ref::<StringArray>()
+        let version_id_array = batch.column(0).as_any().downcast_ref::<StringArray>()
             .ok_or_else(|| StorageError::Database("Failed to cast version_id column".to_string()))?;
-        let size_array = batch.column(4).as_any().downcast_ref::<UInt64Array>()
-            .ok_or_else(|| StorageError::Database("Failed to cast size column".to_string()))?;
-        let etag_array = batch.column(5).as_any().downcast_ref::<StringArray>()
-            .ok_or_else(|| StorageError::Database("Failed to cast etag column".to_string()))?;
-        let created_at_array = batch.column(6).as_any().downcast_ref::<TimestampMillisecondArray>()
+        let created_at_array = batch.column(1).as_any().downcast_ref::<TimestampMillisecondArray>()
             .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+        let is_latest_array = batch.column(3).as_any().downcast_ref::<BooleanArray>()
+            .ok_or_else(|| StorageError::Database("Failed to cast is_latest column".to_string()))?;
 
-        let obj_ref = ObjectReference {
-            id: id_array.value(0).to_string(),
-            bucket: bucket_array.value(0).to_string(),
-            key: key_array.value(0).to_string(),
+        Ok(Some(DeleteMarker {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
             version_id: Uuid::parse_str(version_id_array.value(0))
                 .map_err(|e| StorageError::Database(format!("Invalid UUID: {}", e)))?,
-            size: size_array.value(0),
-            etag: etag_array.value(0).to_string(),
-            last_modified: DateTime::from_timestamp_millis(created_at_array.value(0))
+            created_at: DateTime::from_timestamp_millis(created_at_array.value(0))
                 .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
                 .with_timezone(&Utc),
-            storage_class: crate::object::StorageClass::Standard,
-        };
+            is_latest: is_latest_array.value(0),
+        }))
+    }
+
+    /// Runs a query selecting `OBJECT_RECORD_COLUMNS` from `objects`.
+    async fn query_object_records(&self, sql: &str) -> Result<Vec<ObjectRecord>> {
+        let df = self.ctx.sql(sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let mut records = Vec::new();
+        for batch in batches {
+            let id_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast id column".to_string()))?;
+            let bucket_array = batch.column(1).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast bucket column".to_string()))?;
+            let key_array = batch.column(2).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast key column".to_string()))?;
+            let version_id_array = batch.column(3).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast version_id column".to_string()))?;
+            let size_array = batch.column(4).as_any().downcast_ref::<UInt64Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast size column".to_string()))?;
+            let etag_array = batch.column(5).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast etag column".to_string()))?;
+            let content_type_array = batch.column(6).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast content_type column".to_string()))?;
+            let created_at_array = batch.column(7).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+            let custom_metadata_array = batch.column(8).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast custom_metadata column".to_string()))?;
+            let sha256_array = batch.column(9).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast checksum_sha256 column".to_string()))?;
+            let blake3_array = batch.column(10).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast checksum_blake3 column".to_string()))?;
+            let parts_array = batch.column(11).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast parts column".to_string()))?;
+            let lock_mode_array = batch.column(12).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast lock_mode column".to_string()))?;
+            let retain_until_array = batch.column(13).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast retain_until column".to_string()))?;
+            let legal_hold_array = batch.column(14).as_any().downcast_ref::<BooleanArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast legal_hold column".to_string()))?;
+            let tags_array = batch.column(15).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast tags column".to_string()))?;
+            let owner_id_array = batch.column(16).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast owner_id column".to_string()))?;
+            let acl_array = batch.column(17).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast acl column".to_string()))?;
+            let encryption_array = batch.column(18).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast encryption column".to_string()))?;
+            let client_checksum_array = batch.column(19).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast checksum_client column".to_string()))?;
+
+            for row in 0..batch.num_rows() {
+                let custom_metadata = serde_json::from_str(custom_metadata_array.value(row))
+                    .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+                let parts = if parts_array.is_null(row) {
+                    None
+                } else {
+                    Some(serde_json::from_str(parts_array.value(row))
+                        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?)
+                };
+
+                records.push(ObjectRecord {
+                    id: id_array.value(row).to_string(),
+                    metadata: ObjectMetadata {
+                        key: key_array.value(row).to_string(),
+                        bucket: bucket_array.value(row).to_string(),
+                        size: size_array.value(row),
+                        content_type: content_type_array.value(row).to_string(),
+                        created_at: DateTime::from_timestamp_millis(created_at_array.value(row))
+                            .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+                            .with_timezone(&Utc),
+                        etag: etag_array.value(row).to_string(),
+                        custom_metadata,
+                        version_id: Uuid::parse_str(version_id_array.value(row))
+                            .map_err(|e| StorageError::Database(format!("Invalid UUID: {}", e)))?,
+                        parts,
+                        lock: read_lock(lock_mode_array, retain_until_array, legal_hold_array, row)?,
+                        tags: read_tags(tags_array, row)?,
+                        owner_id: (!owner_id_array.is_null(row)).then(|| owner_id_array.value(row).to_string()),
+                        acl: read_acl(acl_array, row)?,
+                        encryption: read_encryption(encryption_array, row)?,
+                    },
+                    checksum: Checksum {
+                        sha256: sha256_array.value(row).to_string(),
+                        blake3: blake3_array.value(row).to_string(),
+                        client: read_client_checksum(client_checksum_array, row)?,
+                    },
+                });
+            }
+        }
 
-        Ok(Some(obj_ref))
+        Ok(records)
     }
 
     pub async fn get_versioned_object(&self, bucket: &str, key: &str) -> Result<Option<VersionedObject>> {
+        let _guard = self.read_table(Table::Objects).await;
         let sql = format!(
             "SELECT version_id, id, size, etag, created_at, is_delete_marker 
              FROM objects 
-             WHERE bucket = '{}' AND key = '{}' 
+             WHERE bucket = {} AND key = {} 
              ORDER BY created_at ASC",
-            bucket, key
+            sql_string(bucket), sql_string(key)
         );
 
         let df = self.ctx.sql(&sql).await
@@ -324,50 +819,64 @@ impl MetadataStore {
                 };
 
                 versioned_obj.versions.insert(created_at, version_info);
-                
-                if !is_delete_marker {
-                    versioned_obj.latest_version = Some(version_id);
-                    versioned_obj.is_deleted = false;
-                } else {
-                    versioned_obj.latest_version = Some(version_id);
-                    versioned_obj.is_deleted = true;
-                }
+
+                // Rows come oldest first, so the last one seen is current
+                versioned_obj.latest_version = Some(version_id);
+                versioned_obj.is_deleted = is_delete_marker;
             }
         }
 
         Ok(Some(versioned_obj))
     }
 
-    pub async fn list_objects(&self, bucket: &str, prefix: Option<&str>, max_keys: usize) -> Result<Vec<ObjectReference>> {
-        let sql = if let Some(p) = prefix {
-            format!(
-                "SELECT DISTINCT bucket, key, 
-                        FIRST_VALUE(id) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as id,
-                        FIRST_VALUE(version_id) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as version_id,
-                        FIRST_VALUE(size) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as size,
-                        FIRST_VALUE(etag) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as etag,
-                        FIRST_VALUE(created_at) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as created_at
-                 FROM objects 
-                 WHERE bucket = '{}' AND key LIKE '{}%' AND is_delete_marker = false
-                 ORDER BY key 
-                 LIMIT {}",
-                bucket, p, max_keys
-            )
-        } else {
-            format!(
-                "SELECT DISTINCT bucket, key, 
-                        FIRST_VALUE(id) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as id,
-                        FIRST_VALUE(version_id) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as version_id,
-                        FIRST_VALUE(size) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as size,
-                        FIRST_VALUE(etag) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as etag,
-                        FIRST_VALUE(created_at) OVER (PARTITION BY bucket, key ORDER BY created_at DESC) as created_at
-                 FROM objects 
-                 WHERE bucket = '{}' AND is_delete_marker = false
-                 ORDER BY key 
-                 LIMIT {}",
-                bucket, max_keys
-            )
-        };
+    /// Lists the live keys of a bucket in key order, starting after `after`.
+    /// With a delimiter, keys sharing the text up to the first delimiter past
+    /// the prefix collapse into one common prefix, which counts as a single
+    /// entry towards `max_keys`. Only keys whose current version carries
+    /// every pair in `tags` are listed. Filtering, grouping and the page limit
+    /// all run in the query, so a page costs the same wherever it starts.
+    pub async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        after: Option<&str>,
+        max_keys: usize,
+        tags: &[(String, String)],
+    ) -> Result<ListObjectsPage> {
+        let _guard = self.read_table(Table::Objects).await;
+        if max_keys == 0 {
+            return Ok(ListObjectsPage::default());
+        }
+
+        let prefix = prefix.unwrap_or("");
+        let after = after.unwrap_or("");
+        let (entry, is_prefix) = listing_entry(prefix, delimiter);
+
+        let sql = format!(
+            "SELECT entry, is_prefix, key, id, version_id, size, etag, created_at, owner_id FROM (
+                 SELECT *, ROW_NUMBER() OVER (PARTITION BY entry ORDER BY key) AS entry_rank FROM (
+                     SELECT {entry} AS entry, {is_prefix} AS is_prefix, key, id, version_id, size, etag, created_at, owner_id
+                     FROM (
+                         SELECT key, id, version_id, size, etag, created_at, is_delete_marker, tags, owner_id,
+                                ROW_NUMBER() OVER (PARTITION BY key ORDER BY created_at DESC) AS version_rank
+                         FROM objects
+                         WHERE bucket = {bucket} AND starts_with(key, {prefix}) AND key > {after}
+                     )
+                     WHERE version_rank = 1 AND is_delete_marker = false AND {tagged}
+                 )
+             )
+             WHERE entry_rank = 1 AND entry > {after}
+             ORDER BY entry
+             LIMIT {limit}",
+            entry = entry,
+            is_prefix = is_prefix,
+            bucket = sql_string(bucket),
+            prefix = sql_string(prefix),
+            after = sql_string(after),
+            tagged = tags_predicate(tags)?,
+            limit = max_keys + 1
+        );
 
         let df = self.ctx.sql(&sql).await
             .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
@@ -375,27 +884,46 @@ impl MetadataStore {
         let batches = df.collect().await
             .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
 
-        let mut objects = Vec::new();
+        let mut page = ListObjectsPage::default();
+        let mut entries = 0;
         for batch in batches {
+            let entry_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast entry column".to_string()))?;
+            let is_prefix_array = batch.column(1).as_any().downcast_ref::<BooleanArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast is_prefix column".to_string()))?;
+            let key_array = batch.column(2).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast key column".to_string()))?;
+            let id_array = batch.column(3).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast id column".to_string()))?;
+            let version_id_array = batch.column(4).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast version_id column".to_string()))?;
+            let size_array = batch.column(5).as_any().downcast_ref::<UInt64Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast size column".to_string()))?;
+            let etag_array = batch.column(6).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast etag column".to_string()))?;
+            let created_at_array = batch.column(7).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+            let owner_id_array = batch.column(8).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast owner_id column".to_string()))?;
+
             for row in 0..batch.num_rows() {
-                let bucket_array = batch.column(0).as_any().downcast_ref::<StringArray>()
-                    .ok_or_else(|| StorageError::Database("Failed to cast bucket column".to_string()))?;
-                let key_array = batch.column(1).as_any().downcast_ref::<StringArray>()
-                    .ok_or_else(|| StorageError::Database("Failed to cast key column".to_string()))?;
-                let id_array = batch.column(2).as_any().downcast_ref::<StringArray>()
-                    .ok_or_else(|| StorageError::Database("Failed to cast id column".to_string()))?;
-                let version_id_array = batch.column(3).as_any().downcast_ref::<StringArray>()
-                    .ok_or_else(|| StorageError::Database("Failed to cast version_id column".to_string()))?;
-                let size_array = batch.column(4).as_any().downcast_ref::<UInt64Array>()
-                    .ok_or_else(|| StorageError::Database("Failed to cast size column".to_string()))?;
-                let etag_array = batch.column(5).as_any().downcast_ref::<StringArray>()
-                    .ok_or_else(|| StorageError::Database("Failed to cast etag column".to_string()))?;
-                let created_at_array = batch.column(6).as_any().downcast_ref::<TimestampMillisecondArray>()
-                    .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+                if entries == max_keys {
+                    page.is_truncated = true;
+                    break;
+                }
+                entries += 1;
+
+                let entry = entry_array.value(row).to_string();
+                page.next_marker = Some(entry.clone());
 
-                let obj_ref = ObjectReference {
+                if is_prefix_array.value(row) {
+                    page.common_prefixes.push(entry);
+                    continue;
+                }
+
+                page.objects.push(ObjectReference {
                     id: id_array.value(row).to_string(),
-                    bucket: bucket_array.value(row).to_string(),
+                    bucket: bucket.to_string(),
                     key: key_array.value(row).to_string(),
                     version_id: Uuid::parse_str(version_id_array.value(row))
                         .map_err(|e| StorageError::Database(format!("Invalid UUID: {}", e)))?,
@@ -405,116 +933,556 @@ impl MetadataStore {
                         .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
                         .with_timezone(&Utc),
                     storage_class: crate::object::StorageClass::Standard,
-                };
-
-                objects.push(obj_ref);
+                    owner_id: (!owner_id_array.is_null(row)).then(|| owner_id_array.value(row).to_string()),
+                });
             }
         }
 
-        Ok(objects)
+        if !page.is_truncated {
+            page.next_marker = None;
+        }
+
+        Ok(page)
     }
 
-    pub async fn delete_object(&self, bucket: &str, key: &str, version_id: Option<Version>) -> Result<bool> {
-        if let Some(vid) = version_id {
-            // Delete specific version - we need to rewrite the parquet file without this record
-            let sql = format!(
-                "SELECT * FROM objects WHERE NOT (bucket = '{}' AND key = '{}' AND version_id = '{}')",
-                bucket, key, vid
-            );
-            
-            let df = self.ctx.sql(&sql).await
-                .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
-                
-            let batches = df.collect().await
-                .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
-
-            // Rewrite the objects parquet file
-            let path = self.storage_path.join("objects.parquet");
-            let file = std::fs::File::create(&path)
-                .map_err(|e| StorageError::Io(e))?;
-                
-            let props = WriterProperties::builder().build();
-            let mut writer = ArrowWriter::try_new(file, self.objects_schema.clone(), Some(props))
-                .map_err(|e| StorageError::Database(format!("Failed to create parquet writer: {}", e)))?;
-
-            for batch in batches {
-                writer.write(&batch)
-                    .map_err(|e| StorageError::Database(format!("Failed to write batch: {}", e)))?;
+    /// Lists every version and delete marker under `prefix`, keys ascending
+    /// and newest version first. Pagination resumes after `key_marker`, or
+    /// within it after `version_id_marker`. Common prefixes collapse as in
+    /// `list_objects`.
+    pub async fn list_object_versions(
+        &self,
+        bucket: &str,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        key_marker: Option<&str>,
+        version_id_marker: Option<Version>,
+        max_keys: usize,
+    ) -> Result<ListVersionsResponse> {
+        let _guard = self.read_table(Table::Objects).await;
+        let mut response = ListVersionsResponse {
+            bucket: bucket.to_string(),
+            prefix: prefix.map(|p| p.to_string()),
+            delimiter: delimiter.map(|d| d.to_string()),
+            key_marker: key_marker.map(|k| k.to_string()),
+            version_id_marker,
+            max_keys,
+            is_truncated: false,
+            versions: Vec::new(),
+            delete_markers: Vec::new(),
+            common_prefixes: Vec::new(),
+            next_key_marker: None,
+            next_version_id_marker: None,
+        };
+
+        if max_keys == 0 {
+            return Ok(response);
+        }
+
+        let prefix = prefix.unwrap_or("");
+        let key_marker = key_marker.unwrap_or("");
+        let (entry, is_prefix) = listing_entry(prefix, delimiter);
+
+        let ranked = format!(
+            "SELECT {entry} AS entry, {is_prefix} AS is_prefix, key, version_id, size, etag, created_at, is_delete_marker,
+                    ROW_NUMBER() OVER (PARTITION BY key ORDER BY created_at DESC) AS version_rank
+             FROM objects
+             WHERE bucket = {bucket} AND starts_with(key, {prefix})",
+            entry = entry,
+            is_prefix = is_prefix,
+            bucket = sql_string(bucket),
+            prefix = sql_string(prefix)
+        );
+
+        // Resuming inside a key: skip the versions up to and including the marker
+        let resume = match version_id_marker {
+            Some(version_id) => {
+                let sql = format!(
+                    "SELECT version_rank FROM ({}) WHERE key = {} AND version_id = {}",
+                    ranked,
+                    sql_string(key_marker),
+                    sql_string(&version_id.to_string())
+                );
+                let batches = self.ctx.sql(&sql).await
+                    .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?
+                    .collect().await
+                    .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+                let rank = batches.iter()
+                    .find(|batch| batch.num_rows() > 0)
+                    .and_then(|batch| batch.column(0).as_any().downcast_ref::<UInt64Array>().map(|a| a.value(0)))
+                    .ok_or_else(|| StorageError::InvalidArgument(
+                        format!("Version {} of {} does not exist", version_id, key_marker)
+                    ))?;
+                format!(
+                    " OR (entry = {} AND NOT is_prefix AND version_rank > {})",
+                    sql_string(key_marker),
+                    rank
+                )
             }
+            None => String::new(),
+        };
 
-            writer.close()
-                .map_err(|e| StorageError::Database(format!("Failed to close parquet writer: {}", e)))?;
+        let sql = format!(
+            "SELECT entry, is_prefix, key, version_id, size, etag, created_at, is_delete_marker, version_rank FROM (
+                 SELECT *, ROW_NUMBER() OVER (PARTITION BY entry ORDER BY key, version_rank) AS entry_rank
+                 FROM ({ranked})
+             )
+             WHERE (NOT is_prefix OR entry_rank = 1) AND (entry > {marker}{resume})
+             ORDER BY entry, version_rank
+             LIMIT {limit}",
+            ranked = ranked,
+            marker = sql_string(key_marker),
+            resume = resume,
+            limit = max_keys + 1
+        );
 
-            // Re-register the updated file
-            self.ctx.deregister_table("objects")
-                .map_err(|e| StorageError::Database(format!("Failed to deregister table: {}", e)))?;
-                
-            self.ctx.register_parquet("objects", path.to_str().unwrap(), ParquetReadOptions::default()).await
-                .map_err(|e| StorageError::Database(format!("Failed to re-register table: {}", e)))?;
+        let df = self.ctx.sql(&sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
 
-            Ok(true)
-        } else {
-            // Add delete marker
-            let new_version_id = Uuid::new_v4();
-            
-            let ids = StringArray::from(vec![format!("{}:{}:delete-marker", bucket, key)]);
-            let buckets = StringArray::from(vec![bucket]);
-            let keys = StringArray::from(vec![key]);
-            let version_ids = StringArray::from(vec![new_version_id.to_string()]);
-            let sizes = UInt64Array::from(vec![0u64]);
-            let etags = StringArray::from(vec![""]);
-            let content_types = StringArray::from(vec![""]);
-            let created_ats = TimestampMillisecondArray::from(vec![Utc::now().timestamp_millis()]);
-            let custom_metadatas = StringArray::from(vec!["{}"]);
-            let checksum_sha256s = StringArray::from(vec![""]);
-            let checksum_blake3s = StringArray::from(vec![""]);
-            let is_delete_markers = BooleanArray::from(vec![true]);
-
-            let batch = RecordBatch::try_new(
-                self.objects_schema.clone(),
-                vec![
-                    Arc::new(ids),
-                    Arc::new(buckets),
-                    Arc::new(keys),
-                    Arc::new(version_ids),
-                    Arc::new(sizes),
-                    Arc::new(etags),
-                    Arc::new(content_types),
-                    Arc::new(created_ats),
-                    Arc::new(custom_metadatas),
-                    Arc::new(checksum_sha256s),
-                    Arc::new(checksum_blake3s),
-                    Arc::new(is_delete_markers),
-                ],
-            ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))?;
-
-            self.append_to_parquet("objects.parquet", batch).await?;
-            Ok(true)
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let mut entries = 0;
+        for batch in batches {
+            let entry_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast entry column".to_string()))?;
+            let is_prefix_array = batch.column(1).as_any().downcast_ref::<BooleanArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast is_prefix column".to_string()))?;
+            let key_array = batch.column(2).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast key column".to_string()))?;
+            let version_id_array = batch.column(3).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast version_id column".to_string()))?;
+            let size_array = batch.column(4).as_any().downcast_ref::<UInt64Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast size column".to_string()))?;
+            let etag_array = batch.column(5).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast etag column".to_string()))?;
+            let created_at_array = batch.column(6).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+            let is_delete_marker_array = batch.column(7).as_any().downcast_ref::<BooleanArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast is_delete_marker column".to_string()))?;
+            let version_rank_array = batch.column(8).as_any().downcast_ref::<UInt64Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast version_rank column".to_string()))?;
+
+            for row in 0..batch.num_rows() {
+                if entries == max_keys {
+                    response.is_truncated = true;
+                    break;
+                }
+                entries += 1;
+
+                let entry = entry_array.value(row).to_string();
+                if is_prefix_array.value(row) {
+                    response.next_key_marker = Some(entry.clone());
+                    response.next_version_id_marker = None;
+                    response.common_prefixes.push(entry);
+                    continue;
+                }
+
+                let version_id = Uuid::parse_str(version_id_array.value(row))
+                    .map_err(|e| StorageError::Database(format!("Invalid UUID: {}", e)))?;
+                let last_modified = DateTime::from_timestamp_millis(created_at_array.value(row))
+                    .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+                    .with_timezone(&Utc);
+                let is_latest = version_rank_array.value(row) == 1;
+                let key = key_array.value(row).to_string();
+
+                response.next_key_marker = Some(key.clone());
+                response.next_version_id_marker = Some(version_id);
+
+                if is_delete_marker_array.value(row) {
+                    response.delete_markers.push(DeleteMarker {
+                        bucket: bucket.to_string(),
+                        key,
+                        version_id,
+                        created_at: last_modified,
+                        is_latest,
+                    });
+                } else {
+                    response.versions.push(ObjectVersion {
+                        bucket: bucket.to_string(),
+                        key,
+                        version_id,
+                        is_latest,
+                        last_modified,
+                        etag: etag_array.value(row).to_string(),
+                        size: size_array.value(row),
+                        storage_class: crate::object::StorageClass::Standard,
+                        is_delete_marker: false,
+                    });
+                }
+            }
+        }
+
+        if !response.is_truncated {
+            response.next_key_marker = None;
+            response.next_version_id_marker = None;
         }
+
+        Ok(response)
+    }
+
+    /// Permanently removes one version of a key. Returns the data ID of the
+    /// removed version and whether it was a delete marker.
+    pub async fn delete_object_version(&self, bucket: &str, key: &str, version_id: Version) -> Result<Option<(ObjectId, bool)>> {
+        let table = self.write_table(Table::Objects).await;
+        let Some(removed) = self.find_version(bucket, key, version_id).await? else {
+            return Ok(None);
+        };
+
+        let predicate = version_predicate(bucket, key, version_id);
+        self.replace_rows(&table, &predicate, None).await?;
+
+        Ok(Some(removed))
     }
 
-    pub async fn create_bucket(&self, name: &str, region: Option<&str>) -> Result<()> {
-        let names = StringArray::from(vec![name]);
-        let created_ats = TimestampMillisecondArray::from(vec![Utc::now().timestamp_millis()]);
-        let regions = StringArray::from(vec![region.unwrap_or("us-east-1")]);
+    /// Makes a delete marker the current version of a key, replacing any
+    /// existing version with the same ID. Returns the marker and the data ID
+    /// of the version it replaced, if any.
+    pub async fn store_delete_marker(&self, bucket: &str, key: &str, version_id: Version) -> Result<(DeleteMarker, Option<ObjectId>)> {
+        let table = self.write_table(Table::Objects).await;
+        let created_at = self.version_time_after_newest(bucket, key).await?;
+
+        let ids = StringArray::from(vec![format!("{}:{}:delete-marker", bucket, key)]);
+        let buckets = StringArray::from(vec![bucket]);
+        let keys = StringArray::from(vec![key]);
+        let version_ids = StringArray::from(vec![version_id.to_string()]);
+        let sizes = UInt64Array::from(vec![0u64]);
+        let etags = StringArray::from(vec![""]);
+        let content_types = StringArray::from(vec![""]);
+        let created_ats = TimestampMillisecondArray::from(vec![created_at.timestamp_millis()]);
+        let custom_metadatas = StringArray::from(vec!["{}"]);
+        let checksum_sha256s = StringArray::from(vec![""]);
+        let checksum_blake3s = StringArray::from(vec![""]);
+        let is_delete_markers = BooleanArray::from(vec![true]);
+        let parts = StringArray::from(vec![None::<&str>]);
+        let (lock_modes, retain_untils, legal_holds) = lock_arrays(None);
+        let tags = StringArray::from(vec![None::<&str>]);
+        let owner_ids = StringArray::from(vec![None::<&str>]);
+        let acls = StringArray::from(vec![None::<&str>]);
+        let encryptions = StringArray::from(vec![None::<&str>]);
+        let client_checksums = StringArray::from(vec![None::<&str>]);
 
         let batch = RecordBatch::try_new(
-            self.buckets_schema.clone(),
+            self.objects_schema.clone(),
             vec![
-                Arc::new(names),
+                Arc::new(ids),
+                Arc::new(buckets),
+                Arc::new(keys),
+                Arc::new(version_ids),
+                Arc::new(sizes),
+                Arc::new(etags),
+                Arc::new(content_types),
                 Arc::new(created_ats),
-                Arc::new(regions),
+                Arc::new(custom_metadatas),
+                Arc::new(checksum_sha256s),
+                Arc::new(checksum_blake3s),
+                Arc::new(is_delete_markers),
+                Arc::new(parts),
+                Arc::new(lock_modes),
+                Arc::new(retain_untils),
+                Arc::new(legal_holds),
+                Arc::new(tags),
+                Arc::new(owner_ids),
+                Arc::new(acls),
+                Arc::new(encryptions),
+                Arc::new(client_checksums),
             ],
         ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))?;
 
-        self.append_to_parquet("buckets.parquet", batch).await?;
+        let replaced = self.replace_version(&table, bucket, key, version_id, batch).await?;
+
+        let marker = DeleteMarker {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            version_id,
+            created_at,
+            is_latest: true,
+        };
+
+        Ok((marker, replaced))
+    }
+
+    /// Every version and delete marker under `prefix`, ranked newest first
+    /// per key, for lifecycle evaluation.
+    pub(crate) async fn lifecycle_versions(&self, bucket: &str, prefix: &str) -> Result<Vec<LifecycleVersion>> {
+        let _guard = self.read_table(Table::Objects).await;
+        let sql = format!(
+            "SELECT key, version_id, created_at, is_delete_marker,
+                    ROW_NUMBER() OVER (PARTITION BY key ORDER BY created_at DESC) AS version_rank,
+                    LAG(created_at) OVER (PARTITION BY key ORDER BY created_at DESC) AS superseded_at,
+                    COUNT(*) OVER (PARTITION BY key) AS version_count,
+                    tags
+             FROM objects WHERE bucket = {} AND starts_with(key, {})",
+            sql_string(bucket),
+            sql_string(prefix)
+        );
+
+        let df = self.ctx.sql(&sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let timestamp = |millis: i64| DateTime::from_timestamp_millis(millis)
+            .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))
+            .map(|t| t.with_timezone(&Utc));
+
+        let mut versions = Vec::new();
+        for batch in batches {
+            let key_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast key column".to_string()))?;
+            let version_id_array = batch.column(1).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast version_id column".to_string()))?;
+            let created_at_array = batch.column(2).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+            let is_delete_marker_array = batch.column(3).as_any().downcast_ref::<BooleanArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast is_delete_marker column".to_string()))?;
+            let rank_array = batch.column(4).as_any().downcast_ref::<UInt64Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast version_rank column".to_string()))?;
+            let superseded_at_array = batch.column(5).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast superseded_at column".to_string()))?;
+            let count_array = batch.column(6).as_any().downcast_ref::<Int64Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast version_count column".to_string()))?;
+            let tags_array = batch.column(7).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast tags column".to_string()))?;
+
+            for row in 0..batch.num_rows() {
+                versions.push(LifecycleVersion {
+                    key: key_array.value(row).to_string(),
+                    version_id: Uuid::parse_str(version_id_array.value(row))
+                        .map_err(|e| StorageError::Database(format!("Invalid UUID: {}", e)))?,
+                    created_at: timestamp(created_at_array.value(row))?,
+                    is_delete_marker: is_delete_marker_array.value(row),
+                    rank: rank_array.value(row),
+                    superseded_at: if superseded_at_array.is_null(row) {
+                        None
+                    } else {
+                        Some(timestamp(superseded_at_array.value(row))?)
+                    },
+                    version_count: count_array.value(row) as u64,
+                    tags: read_tags(tags_array, row)?,
+                });
+            }
+        }
+
+        Ok(versions)
+    }
+
+    /// Whether `key` has any version other than `version_id`.
+    pub async fn has_other_versions(&self, bucket: &str, key: &str, version_id: Version) -> Result<bool> {
+        let _guard = self.read_table(Table::Objects).await;
+        let sql = format!(
+            "SELECT COUNT(*) as count FROM objects WHERE bucket = {} AND key = {} AND version_id <> {}",
+            sql_string(bucket),
+            sql_string(key),
+            sql_string(&version_id.to_string())
+        );
+        self.any_rows(&sql).await
+    }
+
+    /// Whether any remaining version still uses the data stored under `id`.
+    pub async fn is_data_referenced(&self, id: &str) -> Result<bool> {
+        let _guard = self.read_table(Table::Objects).await;
+        let sql = format!(
+            "SELECT COUNT(*) as count FROM objects WHERE id = {} AND is_delete_marker = false",
+            sql_string(id)
+        );
+        self.any_rows(&sql).await
+    }
+
+    /// Records a new bucket. Fails with `BucketAlreadyExists` if the name is
+    /// already taken, whoever owns it.
+    pub async fn create_bucket(&self, bucket: &Bucket) -> Result<()> {
+        let batch = self.bucket_batch(bucket)?;
+        let table = self.write_table(Table::Buckets).await;
+        if self.find_bucket(&bucket.name).await?.is_some() {
+            return Err(StorageError::BucketAlreadyExists(bucket.name.clone()));
+        }
+
+        self.append_to_parquet(&table, batch).await?;
         Ok(())
     }
 
+    /// Changes the stored settings of an existing bucket. `change` sees the
+    /// bucket as currently stored, and no other write to the buckets table
+    /// can come between reading it and storing the result. Returns the
+    /// updated bucket, or fails with `NoSuchBucket`.
+    pub async fn modify_bucket<F>(&self, name: &str, change: F) -> Result<Bucket>
+    where
+        F: FnOnce(&mut Bucket) -> Result<()>,
+    {
+        let table = self.write_table(Table::Buckets).await;
+        let mut bucket = self.find_bucket(name).await?
+            .ok_or_else(|| StorageError::NoSuchBucket(name.to_string()))?;
+        change(&mut bucket)?;
+
+        let batch = self.bucket_batch(&bucket)?;
+        let predicate = format!("name = {}", sql_string(name));
+        self.replace_rows(&table, &predicate, Some(batch)).await?;
+        Ok(bucket)
+    }
+
+    fn bucket_batch(&self, bucket: &Bucket) -> Result<RecordBatch> {
+        let object_lock_json = bucket.object_lock.as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+        let lifecycle_json = bucket.lifecycle.as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+        let cors_json = bucket.cors.as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+        let encryption_json = bucket.encryption.as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+
+        RecordBatch::try_new(
+            self.buckets_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![bucket.name.as_str()])),
+                Arc::new(TimestampMillisecondArray::from(vec![bucket.created_at.timestamp_millis()])),
+                Arc::new(StringArray::from(vec![bucket.region.as_str()])),
+                Arc::new(StringArray::from(vec![bucket.owner_id.as_deref()])),
+                Arc::new(StringArray::from(vec![bucket.versioning.as_str()])),
+                Arc::new(StringArray::from(vec![object_lock_json])),
+                Arc::new(StringArray::from(vec![lifecycle_json])),
+                Arc::new(StringArray::from(vec![bucket.policy.as_ref().map(BucketPolicy::document)])),
+                Arc::new(acl_array(bucket.acl.as_ref())?),
+                Arc::new(StringArray::from(vec![cors_json])),
+                Arc::new(StringArray::from(vec![encryption_json])),
+            ],
+        ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))
+    }
+
+    pub async fn get_bucket(&self, name: &str) -> Result<Option<Bucket>> {
+        let _guard = self.read_table(Table::Buckets).await;
+        self.find_bucket(name).await
+    }
+
+    /// `get_bucket` for callers that already hold a lock on the buckets table.
+    async fn find_bucket(&self, name: &str) -> Result<Option<Bucket>> {
+        let sql = format!(
+            "SELECT {} FROM buckets WHERE name = {}",
+            BUCKET_COLUMNS,
+            sql_string(name)
+        );
+
+        Ok(self.query_buckets(&sql).await?.into_iter().next())
+    }
+
+    pub async fn list_buckets(&self) -> Result<Vec<Bucket>> {
+        let _guard = self.read_table(Table::Buckets).await;
+        self.query_buckets(&format!("SELECT {} FROM buckets ORDER BY name", BUCKET_COLUMNS)).await
+    }
+
+    pub async fn delete_bucket(&self, name: &str) -> Result<()> {
+        let predicate = format!("name = {}", sql_string(name));
+        let table = self.write_table(Table::Buckets).await;
+        self.replace_rows(&table, &predicate, None).await
+    }
+
+    async fn query_buckets(&self, sql: &str) -> Result<Vec<Bucket>> {
+        let df = self.ctx.sql(sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let mut buckets = Vec::new();
+        for batch in batches {
+            let name_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast name column".to_string()))?;
+            let created_at_array = batch.column(1).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+            let region_array = batch.column(2).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast region column".to_string()))?;
+            let owner_id_array = batch.column(3).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast owner_id column".to_string()))?;
+            let versioning_array = batch.column(4).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast versioning column".to_string()))?;
+            let object_lock_array = batch.column(5).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast object_lock column".to_string()))?;
+            let lifecycle_array = batch.column(6).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast lifecycle column".to_string()))?;
+            let policy_array = batch.column(7).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast policy column".to_string()))?;
+            let acl_array = batch.column(8).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast acl column".to_string()))?;
+            let cors_array = batch.column(9).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast cors column".to_string()))?;
+            let encryption_array = batch.column(10).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast encryption column".to_string()))?;
+
+            for row in 0..batch.num_rows() {
+                buckets.push(Bucket {
+                    name: name_array.value(row).to_string(),
+                    created_at: DateTime::from_timestamp_millis(created_at_array.value(row))
+                        .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+                        .with_timezone(&Utc),
+                    region: region_array.value(row).to_string(),
+                    owner_id: (!owner_id_array.is_null(row)).then(|| owner_id_array.value(row).to_string()),
+                    versioning: if versioning_array.is_null(row) {
+                        VersioningStatus::Unversioned
+                    } else {
+                        VersioningStatus::parse(versioning_array.value(row))
+                            .ok_or_else(|| StorageError::Database("Invalid versioning status".to_string()))?
+                    },
+                    object_lock: if object_lock_array.is_null(row) {
+                        None
+                    } else {
+                        Some(serde_json::from_str(object_lock_array.value(row))
+                            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?)
+                    },
+                    lifecycle: if lifecycle_array.is_null(row) {
+                        None
+                    } else {
+                        Some(serde_json::from_str(lifecycle_array.value(row))
+                            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?)
+                    },
+                    policy: if policy_array.is_null(row) {
+                        None
+                    } else {
+                        Some(BucketPolicy::parse(policy_array.value(row))?)
+                    },
+                    acl: read_acl(acl_array, row)?,
+                    cors: if cors_array.is_null(row) {
+                        None
+                    } else {
+                        Some(serde_json::from_str(cors_array.value(row))
+                            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?)
+                    },
+                    encryption: if encryption_array.is_null(row) {
+                        None
+                    } else {
+                        Some(serde_json::from_str(encryption_array.value(row))
+                            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?)
+                    },
+                });
+            }
+        }
+
+        Ok(buckets)
+    }
+
     pub async fn bucket_exists(&self, name: &str) -> Result<bool> {
-        let sql = format!("SELECT COUNT(*) as count FROM buckets WHERE name = '{}'", name);
-        
-        let df = self.ctx.sql(&sql).await
+        let _guard = self.read_table(Table::Buckets).await;
+        let sql = format!("SELECT COUNT(*) as count FROM buckets WHERE name = {}", sql_string(name));
+        self.any_rows(&sql).await
+    }
+
+    /// Whether any version or delete marker is stored under `bucket`.
+    pub async fn bucket_has_objects(&self, bucket: &str) -> Result<bool> {
+        let _guard = self.read_table(Table::Objects).await;
+        let sql = format!("SELECT COUNT(*) as count FROM objects WHERE bucket = {}", sql_string(bucket));
+        self.any_rows(&sql).await
+    }
+
+    /// Runs a `COUNT(*)` query and reports whether it matched anything.
+    async fn any_rows(&self, sql: &str) -> Result<bool> {
+        let df = self.ctx.sql(sql).await
             .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
             
         let batches = df.collect().await
@@ -525,9 +1493,486 @@ impl MetadataStore {
         }
 
         let batch = &batches[0];
-        let count_array = batch.column(0).as_any().downcast_ref::<UInt64Array>()
+        let count_array = batch.column(0).as_any().downcast_ref::<Int64Array>()
             .ok_or_else(|| StorageError::Database("Failed to cast count column".to_string()))?;
 
         Ok(count_array.value(0) > 0)
     }
-}
\ No newline at end of file
+
+    pub async fn store_credential(&self, credential: &Credential) -> Result<()> {
+        let table = self.write_table(Table::Credentials).await;
+        self.replace_credential(&table, credential).await
+    }
+
+    /// Changes a stored credential under the credentials write lock, so a
+    /// concurrent rotate and revoke cannot undo each other. Returns the
+    /// updated credential, or `None` if the access key is unknown.
+    pub async fn modify_credential<F>(&self, access_key: &str, change: F) -> Result<Option<Credential>>
+    where
+        F: FnOnce(&mut Credential),
+    {
+        let table = self.write_table(Table::Credentials).await;
+        let Some(mut credential) = self.find_credential(access_key).await? else {
+            return Ok(None);
+        };
+        change(&mut credential);
+        self.replace_credential(&table, &credential).await?;
+        Ok(Some(credential))
+    }
+
+    async fn replace_credential(&self, table: &TableWriter<'_>, credential: &Credential) -> Result<()> {
+        let batch = RecordBatch::try_new(
+            self.credentials_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![credential.access_key.as_str()])),
+                Arc::new(StringArray::from(vec![credential.secret_key.as_str()])),
+                Arc::new(StringArray::from(vec![credential.owner_id.as_str()])),
+                Arc::new(BooleanArray::from(vec![credential.enabled])),
+                Arc::new(TimestampMillisecondArray::from(vec![credential.created_at.timestamp_millis()])),
+            ],
+        ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))?;
+
+        let predicate = format!("access_key = {}", sql_string(&credential.access_key));
+        self.replace_rows(table, &predicate, Some(batch)).await
+    }
+
+    /// Looks up an access key. This runs on every signed request.
+    pub async fn get_credential(&self, access_key: &str) -> Result<Option<Credential>> {
+        let _guard = self.read_table(Table::Credentials).await;
+        self.find_credential(access_key).await
+    }
+
+    /// `get_credential` for callers that already hold a lock on the
+    /// credentials table.
+    async fn find_credential(&self, access_key: &str) -> Result<Option<Credential>> {
+        let sql = format!(
+            "SELECT access_key, secret_key, owner_id, enabled, created_at FROM credentials WHERE access_key = {}",
+            sql_string(access_key)
+        );
+
+        Ok(self.query_credentials(&sql).await?.into_iter().next())
+    }
+
+    pub async fn list_credentials(&self) -> Result<Vec<Credential>> {
+        let _guard = self.read_table(Table::Credentials).await;
+        self.query_credentials(
+            "SELECT access_key, secret_key, owner_id, enabled, created_at FROM credentials ORDER BY created_at"
+        ).await
+    }
+
+    async fn query_credentials(&self, sql: &str) -> Result<Vec<Credential>> {
+        let df = self.ctx.sql(sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let mut credentials = Vec::new();
+        for batch in batches {
+            let access_key_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast access_key column".to_string()))?;
+            let secret_key_array = batch.column(1).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast secret_key column".to_string()))?;
+            let owner_id_array = batch.column(2).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast owner_id column".to_string()))?;
+            let enabled_array = batch.column(3).as_any().downcast_ref::<BooleanArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast enabled column".to_string()))?;
+            let created_at_array = batch.column(4).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast created_at column".to_string()))?;
+
+            for row in 0..batch.num_rows() {
+                credentials.push(Credential {
+                    access_key: access_key_array.value(row).to_string(),
+                    secret_key: secret_key_array.value(row).to_string(),
+                    owner_id: owner_id_array.value(row).to_string(),
+                    enabled: enabled_array.value(row),
+                    created_at: DateTime::from_timestamp_millis(created_at_array.value(row))
+                        .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+                        .with_timezone(&Utc),
+                });
+            }
+        }
+
+        Ok(credentials)
+    }
+
+    pub async fn store_multipart_upload(&self, upload: &MultipartUpload) -> Result<()> {
+        let custom_metadata_json = serde_json::to_string(&upload.custom_metadata)
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+        let (lock_modes, retain_untils, legal_holds) = lock_arrays(Some(&upload.lock));
+        let tags = tags_array(&upload.tags)?;
+        let owner_ids = StringArray::from(vec![upload.owner_id.as_deref()]);
+        let acls = acl_array(upload.acl.as_ref())?;
+        let encryptions = encryption_array(upload.encryption.as_ref())?;
+
+        let batch = RecordBatch::try_new(
+            self.uploads_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![upload.upload_id.as_str()])),
+                Arc::new(StringArray::from(vec![upload.bucket.as_str()])),
+                Arc::new(StringArray::from(vec![upload.key.as_str()])),
+                Arc::new(TimestampMillisecondArray::from(vec![upload.initiated_at.timestamp_millis()])),
+                Arc::new(StringArray::from(vec![upload.content_type.as_str()])),
+                Arc::new(StringArray::from(vec![custom_metadata_json.as_str()])),
+                Arc::new(lock_modes),
+                Arc::new(retain_untils),
+                Arc::new(legal_holds),
+                Arc::new(tags),
+                Arc::new(owner_ids),
+                Arc::new(acls),
+                Arc::new(encryptions),
+            ],
+        ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))?;
+
+        let table = self.write_table(Table::MultipartUploads).await;
+        self.append_to_parquet(&table, batch).await
+    }
+
+    pub async fn get_multipart_upload(&self, upload_id: &str) -> Result<Option<MultipartUpload>> {
+        let _guard = self.read_table(Table::MultipartUploads).await;
+        self.find_multipart_upload(upload_id).await
+    }
+
+    /// `get_multipart_upload` for callers already holding the uploads table.
+    async fn find_multipart_upload(&self, upload_id: &str) -> Result<Option<MultipartUpload>> {
+        let sql = format!(
+            "SELECT upload_id, bucket, key, initiated_at, content_type, custom_metadata,
+                    lock_mode, retain_until, legal_hold, tags, owner_id, acl, encryption
+             FROM multipart_uploads WHERE upload_id = {}",
+            sql_string(upload_id)
+        );
+
+        Ok(self.query_multipart_uploads(&sql).await?.into_iter().next())
+    }
+
+    pub async fn list_multipart_uploads(&self, bucket: &str, prefix: Option<&str>) -> Result<Vec<MultipartUpload>> {
+        let _guard = self.read_table(Table::MultipartUploads).await;
+        let sql = format!(
+            "SELECT upload_id, bucket, key, initiated_at, content_type, custom_metadata,
+                    lock_mode, retain_until, legal_hold, tags, owner_id, acl, encryption
+             FROM multipart_uploads
+             WHERE bucket = {} AND starts_with(key, {})
+             ORDER BY key, initiated_at",
+            sql_string(bucket),
+            sql_string(prefix.unwrap_or(""))
+        );
+
+        self.query_multipart_uploads(&sql).await
+    }
+
+    /// Removes an upload and all of its part records.
+    pub async fn delete_multipart_upload(&self, upload_id: &str) -> Result<()> {
+        let predicate = format!("upload_id = {}", sql_string(upload_id));
+        // Uploads before parts, wherever both are taken
+        let uploads = self.write_table(Table::MultipartUploads).await;
+        let parts = self.write_table(Table::MultipartParts).await;
+        self.replace_rows(&parts, &predicate, None).await?;
+        self.replace_rows(&uploads, &predicate, None).await
+    }
+
+    async fn query_multipart_uploads(&self, sql: &str) -> Result<Vec<MultipartUpload>> {
+        let df = self.ctx.sql(sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let mut uploads = Vec::new();
+        for batch in batches {
+            let upload_id_array = batch.column(0).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast upload_id column".to_string()))?;
+            let bucket_array = batch.column(1).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast bucket column".to_string()))?;
+            let key_array = batch.column(2).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast key column".to_string()))?;
+            let initiated_at_array = batch.column(3).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast initiated_at column".to_string()))?;
+            let content_type_array = batch.column(4).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast content_type column".to_string()))?;
+            let custom_metadata_array = batch.column(5).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast custom_metadata column".to_string()))?;
+            let lock_mode_array = batch.column(6).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast lock_mode column".to_string()))?;
+            let retain_until_array = batch.column(7).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast retain_until column".to_string()))?;
+            let legal_hold_array = batch.column(8).as_any().downcast_ref::<BooleanArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast legal_hold column".to_string()))?;
+            let tags_array = batch.column(9).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast tags column".to_string()))?;
+            let owner_id_array = batch.column(10).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast owner_id column".to_string()))?;
+            let acl_array = batch.column(11).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast acl column".to_string()))?;
+            let encryption_array = batch.column(12).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast encryption column".to_string()))?;
+
+            for row in 0..batch.num_rows() {
+                uploads.push(MultipartUpload {
+                    upload_id: upload_id_array.value(row).to_string(),
+                    bucket: bucket_array.value(row).to_string(),
+                    key: key_array.value(row).to_string(),
+                    initiated_at: DateTime::from_timestamp_millis(initiated_at_array.value(row))
+                        .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+                        .with_timezone(&Utc),
+                    content_type: content_type_array.value(row).to_string(),
+                    custom_metadata: serde_json::from_str(custom_metadata_array.value(row))
+                        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?,
+                    lock: read_lock(lock_mode_array, retain_until_array, legal_hold_array, row)?,
+                    tags: read_tags(tags_array, row)?,
+                    owner_id: (!owner_id_array.is_null(row)).then(|| owner_id_array.value(row).to_string()),
+                    acl: read_acl(acl_array, row)?,
+                    encryption: read_encryption(encryption_array, row)?,
+                });
+            }
+        }
+
+        Ok(uploads)
+    }
+
+    /// Records a part, replacing any earlier upload of the same part number.
+    /// Fails with `NoSuchUpload` if the upload has been completed or aborted
+    /// in the meantime.
+    pub async fn store_part(&self, upload_id: &str, part: &PartInfo) -> Result<()> {
+        let batch = RecordBatch::try_new(
+            self.parts_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![upload_id])),
+                Arc::new(UInt32Array::from(vec![part.part_number])),
+                Arc::new(UInt64Array::from(vec![part.size])),
+                Arc::new(StringArray::from(vec![part.etag.as_str()])),
+                Arc::new(TimestampMillisecondArray::from(vec![part.last_modified.timestamp_millis()])),
+            ],
+        ).map_err(|e| StorageError::Database(format!("Failed to create record batch: {}", e)))?;
+
+        let predicate = format!(
+            "upload_id = {} AND part_number = {}",
+            sql_string(upload_id),
+            part.part_number
+        );
+        let _uploads = self.read_table(Table::MultipartUploads).await;
+        let table = self.write_table(Table::MultipartParts).await;
+        if self.find_multipart_upload(upload_id).await?.is_none() {
+            return Err(StorageError::NoSuchUpload(upload_id.to_string()));
+        }
+        self.replace_rows(&table, &predicate, Some(batch)).await
+    }
+
+    pub async fn list_parts(&self, upload_id: &str) -> Result<Vec<PartInfo>> {
+        let sql = format!(
+            "SELECT part_number, size, etag, last_modified
+             FROM multipart_parts WHERE upload_id = {}
+             ORDER BY part_number",
+            sql_string(upload_id)
+        );
+
+        let _guard = self.read_table(Table::MultipartParts).await;
+        let df = self.ctx.sql(&sql).await
+            .map_err(|e| StorageError::Database(format!("Query failed: {}", e)))?;
+
+        let batches = df.collect().await
+            .map_err(|e| StorageError::Database(format!("Failed to collect results: {}", e)))?;
+
+        let mut parts = Vec::new();
+        for batch in batches {
+            let part_number_array = batch.column(0).as_any().downcast_ref::<UInt32Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast part_number column".to_string()))?;
+            let size_array = batch.column(1).as_any().downcast_ref::<UInt64Array>()
+                .ok_or_else(|| StorageError::Database("Failed to cast size column".to_string()))?;
+            let etag_array = batch.column(2).as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast etag column".to_string()))?;
+            let last_modified_array = batch.column(3).as_any().downcast_ref::<TimestampMillisecondArray>()
+                .ok_or_else(|| StorageError::Database("Failed to cast last_modified column".to_string()))?;
+
+            for row in 0..batch.num_rows() {
+                parts.push(PartInfo {
+                    part_number: part_number_array.value(row),
+                    size: size_array.value(row),
+                    etag: etag_array.value(row).to_string(),
+                    last_modified: DateTime::from_timestamp_millis(last_modified_array.value(row))
+                        .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+                        .with_timezone(&Utc),
+                });
+            }
+        }
+
+        Ok(parts)
+    }
+}
+
+/// SQL expressions for the entry a key lists under (the key itself, or its
+/// common prefix when a delimiter follows `prefix`) and whether that entry
+/// is a common prefix.
+fn listing_entry(prefix: &str, delimiter: Option<&str>) -> (String, String) {
+    let Some(delimiter) = delimiter.filter(|d| !d.is_empty()) else {
+        return ("key".to_string(), "false".to_string());
+    };
+
+    let position = format!(
+        "strpos(substr(key, {}), {})",
+        prefix.chars().count() + 1,
+        sql_string(delimiter)
+    );
+    (
+        format!(
+            "CASE WHEN {pos} > 0 THEN substr(key, 1, {pos} + {}) ELSE key END",
+            prefix.chars().count() + delimiter.chars().count() - 1,
+            pos = position
+        ),
+        format!("{} > 0", position),
+    )
+}
+
+/// Values for a column that a file written by an older version lacks:
+/// null, unless null would change how the existing rows behave.
+fn missing_column(table: Table, field: &Field, rows: usize) -> ArrayRef {
+    match (table, field.name().as_str()) {
+        // Buckets from before versioning kept every version written to
+        // them, which is what Enabled does; null would mean Unversioned
+        (Table::Buckets, "versioning") => Arc::new(StringArray::from(vec![VersioningStatus::Enabled.as_str(); rows])),
+        _ => new_null_array(field.data_type(), rows),
+    }
+}
+
+/// Columns read by `MetadataStore::query_buckets`, in order.
+const BUCKET_COLUMNS: &str =
+    "name, created_at, region, owner_id, versioning, object_lock, lifecycle, policy, acl, cors, encryption";
+
+/// Columns read by `MetadataStore::query_object_records`, in order.
+const OBJECT_RECORD_COLUMNS: &str = "id, bucket, key, version_id, size, etag, content_type, created_at, \
+     custom_metadata, checksum_sha256, checksum_blake3, parts, lock_mode, retain_until, legal_hold, tags, owner_id, acl, \
+     encryption, checksum_client";
+
+/// Matches the row of one version of a key.
+fn version_predicate(bucket: &str, key: &str, version_id: Version) -> String {
+    format!(
+        "bucket = {} AND key = {} AND version_id = {}",
+        sql_string(bucket),
+        sql_string(key),
+        sql_string(&version_id.to_string())
+    )
+}
+
+/// Object Lock columns for one row; all null for a delete marker.
+fn lock_arrays(lock: Option<&ObjectLock>) -> (StringArray, TimestampMillisecondArray, BooleanArray) {
+    let retention = lock.and_then(|l| l.retention);
+    (
+        StringArray::from(vec![retention.map(|r| r.mode.as_str())]),
+        TimestampMillisecondArray::from(vec![retention.map(|r| r.retain_until.timestamp_millis())]),
+        BooleanArray::from(vec![lock.map(|l| l.legal_hold)]),
+    )
+}
+
+/// Reads the Object Lock columns of one row written by `lock_arrays`.
+fn read_lock(
+    mode_array: &StringArray,
+    retain_until_array: &TimestampMillisecondArray,
+    legal_hold_array: &BooleanArray,
+    row: usize,
+) -> Result<ObjectLock> {
+    let retention = if mode_array.is_null(row) || retain_until_array.is_null(row) {
+        None
+    } else {
+        Some(Retention {
+            mode: RetentionMode::parse(mode_array.value(row))
+                .ok_or_else(|| StorageError::Database("Invalid retention mode".to_string()))?,
+            retain_until: DateTime::from_timestamp_millis(retain_until_array.value(row))
+                .ok_or_else(|| StorageError::Database("Invalid timestamp".to_string()))?
+                .with_timezone(&Utc),
+        })
+    };
+
+    Ok(ObjectLock {
+        retention,
+        legal_hold: !legal_hold_array.is_null(row) && legal_hold_array.value(row),
+    })
+}
+
+/// The tags column for one row; null when there are no tags.
+fn tags_array(tags: &TagSet) -> Result<StringArray> {
+    let json = if tags.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(tags)
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?)
+    };
+    Ok(StringArray::from(vec![json]))
+}
+
+fn read_tags(tags_array: &StringArray, row: usize) -> Result<TagSet> {
+    if tags_array.is_null(row) {
+        return Ok(TagSet::new());
+    }
+    serde_json::from_str(tags_array.value(row))
+        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))
+}
+
+/// The acl column for one row; null when no ACL is recorded.
+fn acl_array(acl: Option<&AccessControlList>) -> Result<StringArray> {
+    let json = acl.map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+    Ok(StringArray::from(vec![json]))
+}
+
+fn read_acl(acl_array: &StringArray, row: usize) -> Result<Option<AccessControlList>> {
+    if acl_array.is_null(row) {
+        return Ok(None);
+    }
+    serde_json::from_str(acl_array.value(row))
+        .map(Some)
+        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))
+}
+
+/// The encryption column for one row; null for unencrypted data.
+fn encryption_array(encryption: Option<&ObjectEncryption>) -> Result<StringArray> {
+    let json = encryption.map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+    Ok(StringArray::from(vec![json]))
+}
+
+fn read_encryption(encryption_array: &StringArray, row: usize) -> Result<Option<ObjectEncryption>> {
+    if encryption_array.is_null(row) {
+        return Ok(None);
+    }
+    serde_json::from_str(encryption_array.value(row))
+        .map(Some)
+        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))
+}
+
+/// The checksum_client column for one row; null when the client sent no
+/// checksum.
+fn client_checksum_array(checksum: Option<&ClientChecksum>) -> Result<StringArray> {
+    let json = checksum.map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+    Ok(StringArray::from(vec![json]))
+}
+
+fn read_client_checksum(checksum_array: &StringArray, row: usize) -> Result<Option<ClientChecksum>> {
+    if checksum_array.is_null(row) {
+        return Ok(None);
+    }
+    serde_json::from_str(checksum_array.value(row))
+        .map(Some)
+        .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))
+}
+
+/// Matches rows whose tags column holds every pair in `tags`. Tags are
+/// stored as a JSON array of `["key","value"]` pairs, and since quotes in
+/// keys and values are escaped, the encoded pair only occurs where that
+/// exact pair is stored.
+fn tags_predicate(tags: &[(String, String)]) -> Result<String> {
+    let mut conditions = vec!["true".to_string()];
+    for tag in tags {
+        let pair = serde_json::to_string(tag)
+            .map_err(|e| StorageError::Serialization(format!("JSON error: {:?}", e)))?;
+        conditions.push(format!("strpos(coalesce(tags, ''), {}) > 0", sql_string(&pair)));
+    }
+    Ok(conditions.join(" AND "))
+}
+
+/// Quotes a value as a SQL string literal.
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
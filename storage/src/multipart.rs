@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::acl::AccessControlList;
+use crate::encryption::ObjectEncryption;
+use crate::lock::ObjectLock;
+use crate::object::ObjectAttributes;
+use crate::tagging::TagSet;
+
+/// Every part except the last must be at least this large.
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+pub const MAX_PART_NUMBER: u32 = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipartUpload {
+    pub upload_id: String,
+    pub bucket: String,
+    pub key: String,
+    pub initiated_at: DateTime<Utc>,
+    pub content_type: String,
+    pub custom_metadata: HashMap<String, String>,
+    /// Object Lock settings requested for the completed object.
+    #[serde(default)]
+    pub lock: ObjectLock,
+    /// Tags for the completed object.
+    #[serde(default)]
+    pub tags: TagSet,
+    /// Owner and ACL of the completed object.
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    #[serde(default)]
+    pub acl: Option<AccessControlList>,
+    /// Encryption of the parts, and of the completed object.
+    #[serde(default)]
+    pub encryption: Option<ObjectEncryption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartInfo {
+    pub part_number: u32,
+    pub size: u64,
+    pub etag: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+/// One page of the parts of an upload, in part number order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListPartsPage {
+    pub parts: Vec<PartInfo>,
+    pub is_truncated: bool,
+    /// The last part number returned, to continue from on the next page.
+    pub next_part_number_marker: Option<u32>,
+}
+
+/// One page of a bucket's in-progress uploads, by key and then oldest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListUploadsPage {
+    pub uploads: Vec<MultipartUpload>,
+    pub is_truncated: bool,
+    /// Key and upload ID of the last upload returned, to continue from on
+    /// the next page.
+    pub next_key_marker: Option<String>,
+    pub next_upload_id_marker: Option<String>,
+}
+
+impl MultipartUpload {
+    pub fn new(bucket: String, key: String, attributes: ObjectAttributes) -> Self {
+        Self {
+            upload_id: Uuid::new_v4().simple().to_string(),
+            bucket,
+            key,
+            initiated_at: Utc::now(),
+            content_type: attributes.content_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+            custom_metadata: attributes.custom_metadata,
+            lock: attributes.lock,
+            tags: attributes.tags,
+            owner_id: attributes.owner_id,
+            acl: attributes.acl,
+            encryption: None,
+        }
+    }
+}
+
+/// ETag of a completed upload: a hash over the part ETags suffixed with the
+/// part count, mirroring S3's `md5-of-md5s-N` shape.
+pub fn multipart_etag(parts: &[PartInfo]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for part in parts {
+        hasher.update(part.etag.trim_matches('"').as_bytes());
+    }
+    format!("\"{}-{}\"", &hasher.finalize().to_hex()[..32], parts.len())
+}
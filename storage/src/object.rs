@@ -6,8 +6,32 @@ use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use std::collections::HashMap;
 
+use crate::acl::AccessControlList;
+use crate::checksum::ClientChecksum;
+use crate::encryption::ObjectEncryption;
+use crate::lock::ObjectLock;
+use crate::tagging::TagSet;
+use crate::{Result, StorageError};
+
 pub type ObjectId = String;
 
+/// Longest object key accepted, in bytes of UTF-8.
+pub const MAX_KEY_LENGTH: usize = 1024;
+
+/// Checks an object key: any non-empty UTF-8 string of up to
+/// `MAX_KEY_LENGTH` bytes, slashes and all.
+pub fn validate_object_key(key: &str) -> Result<()> {
+    if key.is_empty() {
+        return Err(StorageError::InvalidArgument("Object key must not be empty".to_string()));
+    }
+    if key.len() > MAX_KEY_LENGTH {
+        return Err(StorageError::KeyTooLong(format!(
+            "Object key is {} bytes long; the limit is {}", key.len(), MAX_KEY_LENGTH
+        )));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Object {
     pub id: ObjectId,
@@ -26,12 +50,46 @@ pub struct ObjectMetadata {
     pub etag: String,
     pub custom_metadata: HashMap<String, String>,
     pub version_id: Uuid,
+    /// Part sizes, for objects assembled by a multipart upload.
+    #[serde(default)]
+    pub parts: Option<Vec<u64>>,
+    /// Object Lock retention and legal hold of this version.
+    #[serde(default)]
+    pub lock: ObjectLock,
+    #[serde(default)]
+    pub tags: TagSet,
+    /// Canonical ID of the account that wrote this version; `None` for
+    /// versions written before owners were recorded.
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    #[serde(default)]
+    pub acl: Option<AccessControlList>,
+    /// How the version's data is encrypted at rest; `None` for plaintext.
+    #[serde(default)]
+    pub encryption: Option<ObjectEncryption>,
+}
+
+/// What a client supplies alongside the data of a new object version.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectAttributes {
+    /// Defaults to `application/octet-stream`.
+    pub content_type: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+    /// Requested Object Lock settings; without explicit retention the
+    /// bucket's default retention applies.
+    pub lock: ObjectLock,
+    pub tags: TagSet,
+    pub owner_id: Option<String>,
+    pub acl: Option<AccessControlList>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Checksum {
     pub sha256: String,
     pub blake3: String,
+    /// The checksum a client uploaded the data with, if it sent one.
+    #[serde(default)]
+    pub client: Option<ClientChecksum>,
 }
 
 impl Object {
@@ -58,6 +116,12 @@ impl Object {
             etag,
             custom_metadata,
             version_id,
+            parts: None,
+            lock: ObjectLock::default(),
+            tags: TagSet::new(),
+            owner_id: None,
+            acl: None,
+            encryption: None,
         };
 
         Self {
@@ -73,7 +137,7 @@ impl Object {
         calculated.sha256 == self.checksum.sha256 && calculated.blake3 == self.checksum.blake3
     }
 
-    fn calculate_checksum(data: &[u8]) -> Checksum {
+    pub(crate) fn calculate_checksum(data: &[u8]) -> Checksum {
         let mut sha256_hasher = Sha256::new();
         sha256_hasher.update(data);
         let sha256 = format!("{:x}", sha256_hasher.finalize());
@@ -81,11 +145,16 @@ impl Object {
         let blake3_hash = blake3::hash(data);
         let blake3 = blake3_hash.to_hex().to_string();
 
-        Checksum { sha256, blake3 }
+        Checksum { sha256, blake3, client: None }
     }
 
-    fn generate_id(bucket: &str, key: &str, blake3_hash: &str) -> ObjectId {
-        format!("{}:{}:{}", bucket, key, &blake3_hash[..16])
+    /// The ID data is stored under. `data_id` is the content hash for
+    /// plaintext, so identical writes share a file, and random otherwise.
+    /// The ID doubles as a file name, so it carries a hash of the key
+    /// rather than the key itself, which may contain `/` or `..`.
+    pub(crate) fn generate_id(bucket: &str, key: &str, data_id: &str) -> ObjectId {
+        let key_hash = blake3::hash(key.as_bytes()).to_hex();
+        format!("{}:{}:{}", bucket, &key_hash[..32], &data_id[..16])
     }
 
     pub fn content_length(&self) -> u64 {
@@ -107,6 +176,27 @@ pub struct ObjectReference {
     pub etag: String,
     pub last_modified: DateTime<Utc>,
     pub storage_class: StorageClass,
+    pub owner_id: Option<String>,
+}
+
+/// Everything recorded about a stored object version except its data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectRecord {
+    pub id: ObjectId,
+    pub metadata: ObjectMetadata,
+    pub checksum: Checksum,
+}
+
+/// One page of a bucket listing, in key order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListObjectsPage {
+    pub objects: Vec<ObjectReference>,
+    /// Keys rolled up at the first delimiter after the prefix.
+    pub common_prefixes: Vec<String>,
+    pub is_truncated: bool,
+    /// The last key or common prefix returned. Pass it back as `after` to
+    /// fetch the next page.
+    pub next_marker: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -132,6 +222,21 @@ impl ObjectReference {
             etag: object.metadata.etag.clone(),
             last_modified: object.metadata.created_at,
             storage_class: StorageClass::default(),
+            owner_id: object.metadata.owner_id.clone(),
+        }
+    }
+
+    pub fn from_record(record: &ObjectRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            bucket: record.metadata.bucket.clone(),
+            key: record.metadata.key.clone(),
+            version_id: record.metadata.version_id,
+            size: record.metadata.size,
+            etag: record.metadata.etag.clone(),
+            last_modified: record.metadata.created_at,
+            storage_class: StorageClass::default(),
+            owner_id: record.metadata.owner_id.clone(),
         }
     }
 }
\ No newline at end of file
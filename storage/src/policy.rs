@@ -0,0 +1,456 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::{Result, StorageError};
+
+/// Largest bucket policy document accepted, in bytes.
+pub const MAX_POLICY_SIZE: usize = 20 * 1024;
+
+const POLICY_VERSIONS: &[&str] = &["2012-10-17", "2008-10-17"];
+const RESOURCE_PREFIX: &str = "arn:aws:s3:::";
+
+/// A bucket policy: the JSON document as given, and its parsed statements.
+/// Stored and serialized as the document text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BucketPolicy {
+    document: String,
+    statements: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyEffect {
+    Allow,
+    Deny,
+}
+
+/// One request as seen by policy evaluation.
+#[derive(Debug, Clone)]
+pub struct PolicyRequest<'a> {
+    /// Identifiers the caller is known by; empty for anonymous requests,
+    /// which only match a `*` principal.
+    pub principals: Vec<String>,
+    /// S3 action name, e.g. `s3:GetObject`.
+    pub action: &'a str,
+    /// Resource ARN: `arn:aws:s3:::bucket` or `arn:aws:s3:::bucket/key`.
+    pub resource: String,
+    /// Condition keys present on the request, lower-cased, with their values.
+    pub conditions: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Statement {
+    effect: PolicyEffect,
+    /// `Principal`, or `NotPrincipal` when `not_principal` is set.
+    principals: Vec<String>,
+    not_principal: bool,
+    actions: Vec<String>,
+    not_action: bool,
+    resources: Vec<String>,
+    not_resource: bool,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    operator: Operator,
+    /// The `...IfExists` form, which holds when the key is absent.
+    if_exists: bool,
+    /// Lower-cased condition key, e.g. `aws:sourceip`.
+    key: String,
+    values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    StringEquals,
+    StringNotEquals,
+    StringEqualsIgnoreCase,
+    StringNotEqualsIgnoreCase,
+    StringLike,
+    StringNotLike,
+    NumericEquals,
+    NumericNotEquals,
+    NumericLessThan,
+    NumericLessThanEquals,
+    NumericGreaterThan,
+    NumericGreaterThanEquals,
+    IpAddress,
+    NotIpAddress,
+    Bool,
+    Null,
+}
+
+impl Operator {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "StringEquals" => Operator::StringEquals,
+            "StringNotEquals" => Operator::StringNotEquals,
+            "StringEqualsIgnoreCase" => Operator::StringEqualsIgnoreCase,
+            "StringNotEqualsIgnoreCase" => Operator::StringNotEqualsIgnoreCase,
+            "StringLike" => Operator::StringLike,
+            "StringNotLike" => Operator::StringNotLike,
+            "NumericEquals" => Operator::NumericEquals,
+            "NumericNotEquals" => Operator::NumericNotEquals,
+            "NumericLessThan" => Operator::NumericLessThan,
+            "NumericLessThanEquals" => Operator::NumericLessThanEquals,
+            "NumericGreaterThan" => Operator::NumericGreaterThan,
+            "NumericGreaterThanEquals" => Operator::NumericGreaterThanEquals,
+            "IpAddress" => Operator::IpAddress,
+            "NotIpAddress" => Operator::NotIpAddress,
+            "Bool" => Operator::Bool,
+            "Null" => Operator::Null,
+            _ => return None,
+        })
+    }
+
+    /// Negated operators hold when no value matches, including when the key
+    /// is absent.
+    fn is_negated(&self) -> bool {
+        matches!(
+            self,
+            Operator::StringNotEquals
+                | Operator::StringNotEqualsIgnoreCase
+                | Operator::StringNotLike
+                | Operator::NumericNotEquals
+                | Operator::NotIpAddress
+        )
+    }
+
+    /// Whether `value` from the request matches the policy value `expected`,
+    /// before negation.
+    fn matches(&self, expected: &str, value: &str) -> bool {
+        let numeric = |compare: fn(f64, f64) -> bool| match (value.parse::<f64>(), expected.parse::<f64>()) {
+            (Ok(value), Ok(expected)) => compare(value, expected),
+            _ => false,
+        };
+
+        match self {
+            Operator::StringEquals | Operator::StringNotEquals => value == expected,
+            Operator::StringEqualsIgnoreCase | Operator::StringNotEqualsIgnoreCase => value.eq_ignore_ascii_case(expected),
+            Operator::StringLike | Operator::StringNotLike => wildcard_match(expected, value),
+            Operator::NumericEquals | Operator::NumericNotEquals => numeric(|a, b| a == b),
+            Operator::NumericLessThan => numeric(|a, b| a < b),
+            Operator::NumericLessThanEquals => numeric(|a, b| a <= b),
+            Operator::NumericGreaterThan => numeric(|a, b| a > b),
+            Operator::NumericGreaterThanEquals => numeric(|a, b| a >= b),
+            Operator::IpAddress | Operator::NotIpAddress => ip_in_range(expected, value),
+            Operator::Bool => value.eq_ignore_ascii_case(expected),
+            Operator::Null => unreachable!("Null conditions test presence, not values"),
+        }
+    }
+}
+
+impl BucketPolicy {
+    /// Parses a policy document, checking its structure and that every
+    /// element is one we can evaluate.
+    pub fn parse(document: &str) -> Result<Self> {
+        if document.len() > MAX_POLICY_SIZE {
+            return malformed(format!("Policies must be no larger than {} bytes", MAX_POLICY_SIZE));
+        }
+
+        let root: Value = serde_json::from_str(document)
+            .map_err(|e| StorageError::MalformedPolicy(format!("Policies must be valid JSON: {}", e)))?;
+        let Value::Object(root) = root else {
+            return malformed("Policies must be a JSON object".to_string());
+        };
+
+        if let Some(version) = root.get("Version") {
+            if !version.as_str().is_some_and(|v| POLICY_VERSIONS.contains(&v)) {
+                return malformed(format!("Policy version must be one of {:?}", POLICY_VERSIONS));
+            }
+        }
+
+        let statements = match root.get("Statement") {
+            Some(Value::Array(statements)) => statements.iter().map(Statement::parse).collect::<Result<Vec<_>>>()?,
+            Some(statement @ Value::Object(_)) => vec![Statement::parse(statement)?],
+            _ => return malformed("Missing required field Statement".to_string()),
+        };
+        if statements.is_empty() {
+            return malformed("Statement must not be empty".to_string());
+        }
+
+        Ok(Self { document: document.to_string(), statements })
+    }
+
+    /// The policy document as it was given.
+    pub fn document(&self) -> &str {
+        &self.document
+    }
+
+    /// Checks that every resource the policy names lies in `bucket`.
+    pub fn check_resources(&self, bucket: &str) -> Result<()> {
+        let bucket_arn = format!("{}{}", RESOURCE_PREFIX, bucket);
+        for resource in self.statements.iter().flat_map(|s| &s.resources) {
+            let in_bucket = resource == &bucket_arn
+                || resource.strip_prefix(&bucket_arn).is_some_and(|rest| rest.starts_with('/'));
+            if !in_bucket {
+                return malformed(format!("Policy has invalid resource: {}", resource));
+            }
+        }
+        Ok(())
+    }
+
+    /// The effect the policy has on `request`: `Deny` if any matching
+    /// statement denies it, otherwise `Allow` if one allows it, and `None`
+    /// when no statement applies.
+    pub fn evaluate(&self, request: &PolicyRequest) -> Option<PolicyEffect> {
+        let mut effect = None;
+        for statement in self.statements.iter().filter(|s| s.applies_to(request)) {
+            match statement.effect {
+                PolicyEffect::Deny => return Some(PolicyEffect::Deny),
+                PolicyEffect::Allow => effect = Some(PolicyEffect::Allow),
+            }
+        }
+        effect
+    }
+}
+
+impl TryFrom<String> for BucketPolicy {
+    type Error = StorageError;
+
+    fn try_from(document: String) -> Result<Self> {
+        Self::parse(&document)
+    }
+}
+
+impl From<BucketPolicy> for String {
+    fn from(policy: BucketPolicy) -> Self {
+        policy.document
+    }
+}
+
+impl Statement {
+    fn parse(value: &Value) -> Result<Self> {
+        let Value::Object(statement) = value else {
+            return malformed("Each Statement must be a JSON object".to_string());
+        };
+
+        let effect = match statement.get("Effect").and_then(Value::as_str) {
+            Some("Allow") => PolicyEffect::Allow,
+            Some("Deny") => PolicyEffect::Deny,
+            _ => return malformed("Effect must be Allow or Deny".to_string()),
+        };
+
+        let (principals, not_principal) = match (statement.get("Principal"), statement.get("NotPrincipal")) {
+            (Some(principal), None) => (parse_principal(principal)?, false),
+            (None, Some(principal)) => (parse_principal(principal)?, true),
+            _ => return malformed("Each Statement must have exactly one of Principal and NotPrincipal".to_string()),
+        };
+
+        let (actions, not_action) = match (statement.get("Action"), statement.get("NotAction")) {
+            (Some(actions), None) => (string_list(actions, "Action")?, false),
+            (None, Some(actions)) => (string_list(actions, "NotAction")?, true),
+            _ => return malformed("Each Statement must have exactly one of Action and NotAction".to_string()),
+        };
+        for action in &actions {
+            if action != "*" && !action.to_ascii_lowercase().starts_with("s3:") {
+                return malformed(format!("Policy has invalid action: {}", action));
+            }
+        }
+
+        let (resources, not_resource) = match (statement.get("Resource"), statement.get("NotResource")) {
+            (Some(resources), None) => (string_list(resources, "Resource")?, false),
+            (None, Some(resources)) => (string_list(resources, "NotResource")?, true),
+            _ => return malformed("Each Statement must have exactly one of Resource and NotResource".to_string()),
+        };
+        for resource in &resources {
+            if !resource.starts_with(RESOURCE_PREFIX) {
+                return malformed(format!("Policy has invalid resource: {}", resource));
+            }
+        }
+
+        let conditions = match statement.get("Condition") {
+            None => Vec::new(),
+            Some(Value::Object(operators)) => {
+                let mut conditions = Vec::new();
+                for (name, keys) in operators {
+                    let (operator_name, if_exists) = match name.strip_suffix("IfExists") {
+                        Some(base) => (base, true),
+                        None => (name.as_str(), false),
+                    };
+                    let operator = Operator::parse(operator_name)
+                        .ok_or_else(|| StorageError::MalformedPolicy(format!("Unsupported condition operator: {}", name)))?;
+
+                    let Value::Object(keys) = keys else {
+                        return malformed(format!("Condition {} must map keys to values", name));
+                    };
+                    for (key, values) in keys {
+                        let values = condition_values(values, key)?;
+                        if matches!(operator, Operator::IpAddress | Operator::NotIpAddress) {
+                            if let Some(bad) = values.iter().find(|v| parse_cidr(v).is_none()) {
+                                return malformed(format!("Invalid IP address range: {}", bad));
+                            }
+                        }
+                        conditions.push(Condition {
+                            operator,
+                            if_exists,
+                            key: key.to_ascii_lowercase(),
+                            values,
+                        });
+                    }
+                }
+                conditions
+            }
+            Some(_) => return malformed("Condition must be a JSON object".to_string()),
+        };
+
+        Ok(Self {
+            effect,
+            principals,
+            not_principal,
+            actions,
+            not_action,
+            resources,
+            not_resource,
+            conditions,
+        })
+    }
+
+    fn applies_to(&self, request: &PolicyRequest) -> bool {
+        let principal = self.principals.iter()
+            .any(|p| p == "*" || request.principals.iter().any(|caller| caller == p));
+        let action = self.actions.iter()
+            .any(|a| wildcard_match(&a.to_ascii_lowercase(), &request.action.to_ascii_lowercase()));
+        let resource = self.resources.iter()
+            .any(|r| wildcard_match(r, &request.resource));
+
+        principal != self.not_principal
+            && action != self.not_action
+            && resource != self.not_resource
+            && self.conditions.iter().all(|c| c.holds(&request.conditions))
+    }
+}
+
+impl Condition {
+    fn holds(&self, context: &HashMap<String, String>) -> bool {
+        let value = context.get(&self.key);
+
+        if self.operator == Operator::Null {
+            let want_absent = self.values.iter().any(|v| v.eq_ignore_ascii_case("true"));
+            return value.is_none() == want_absent;
+        }
+
+        let Some(value) = value else {
+            return self.if_exists || self.operator.is_negated();
+        };
+
+        let matched = self.values.iter().any(|expected| self.operator.matches(expected, value));
+        matched != self.operator.is_negated()
+    }
+}
+
+fn malformed<T>(message: String) -> Result<T> {
+    Err(StorageError::MalformedPolicy(message))
+}
+
+/// Reads `"*"`, `{"AWS": ...}` or `{"CanonicalUser": ...}` into a flat list
+/// of principal identifiers.
+fn parse_principal(value: &Value) -> Result<Vec<String>> {
+    match value {
+        Value::String(s) if s == "*" => Ok(vec!["*".to_string()]),
+        Value::Object(kinds) if !kinds.is_empty() => {
+            let mut principals = Vec::new();
+            for (kind, ids) in kinds {
+                if kind != "AWS" && kind != "CanonicalUser" {
+                    return malformed(format!("Unsupported principal type: {}", kind));
+                }
+                principals.extend(string_list(ids, "Principal")?);
+            }
+            Ok(principals)
+        }
+        _ => malformed("Principal must be \"*\" or an object of principal lists".to_string()),
+    }
+}
+
+/// A string or a non-empty array of strings.
+fn string_list(value: &Value, field: &str) -> Result<Vec<String>> {
+    match value {
+        Value::String(s) => Ok(vec![s.clone()]),
+        Value::Array(items) if !items.is_empty() => items.iter()
+            .map(|item| item.as_str().map(str::to_string)
+                .ok_or_else(|| StorageError::MalformedPolicy(format!("{} must contain only strings", field))))
+            .collect(),
+        _ => malformed(format!("{} must be a string or a non-empty list of strings", field)),
+    }
+}
+
+/// Condition values may be strings, booleans or numbers, alone or in a list.
+fn condition_values(value: &Value, key: &str) -> Result<Vec<String>> {
+    let scalar = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+
+    let values = match value {
+        Value::Array(items) => items.iter().map(scalar).collect::<Option<Vec<_>>>(),
+        value => scalar(value).map(|v| vec![v]),
+    };
+    values.filter(|v| !v.is_empty())
+        .ok_or_else(|| StorageError::MalformedPolicy(format!("Invalid value for condition key {}", key)))
+}
+
+/// Glob match where `*` matches any run of characters and `?` exactly one.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    // Where the last `*` was, and how much of the value it has swallowed
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star, swallowed)) = backtrack {
+            p = star + 1;
+            v = swallowed + 1;
+            backtrack = Some((star, swallowed + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parses `address` or `address/prefix-length`.
+fn parse_cidr(range: &str) -> Option<(IpAddr, u32)> {
+    let (address, prefix) = match range.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.parse::<u32>().ok()?)),
+        None => (range, None),
+    };
+    let address: IpAddr = address.trim().parse().ok()?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    (prefix <= max).then_some((address, prefix))
+}
+
+fn ip_in_range(range: &str, address: &str) -> bool {
+    let (Some((network, prefix)), Ok(address)) = (parse_cidr(range), address.parse::<IpAddr>()) else {
+        return false;
+    };
+
+    let (network, address, bits) = match (network, address) {
+        (IpAddr::V4(n), IpAddr::V4(a)) => (u32::from(n) as u128, u32::from(a) as u128, 32),
+        (IpAddr::V6(n), IpAddr::V6(a)) => (u128::from(n), u128::from(a), 128),
+        (IpAddr::V4(n), IpAddr::V6(a)) => match a.to_ipv4_mapped() {
+            Some(a) => (u32::from(n) as u128, u32::from(a) as u128, 32),
+            None => return false,
+        },
+        (IpAddr::V6(_), IpAddr::V4(_)) => return false,
+    };
+
+    if prefix == 0 {
+        return true;
+    }
+    let shift = bits - prefix;
+    network >> shift == address >> shift
+}
@@ -0,0 +1,238 @@
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::{Result, StorageError};
+use crate::checksum::{ChecksumHasher, UploadDigests};
+use crate::encryption::{DataKey, ObjectEncryption, Sealer};
+use crate::object::Checksum;
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Object data that has been streamed to a temporary file and hashed but not
+/// yet committed. Dropping it without committing removes the file.
+#[derive(Debug)]
+pub struct StagedData {
+    path: PathBuf,
+    size: u64,
+    checksum: Checksum,
+    /// Identifies the stored bytes: the content hash for plaintext, random
+    /// for encrypted data, whose file differs on every write.
+    data_id: String,
+    encryption: Option<ObjectEncryption>,
+    /// Base64 MD5 of the data, when it was asked for.
+    content_md5: Option<String>,
+    /// Whether the file is a hard link to data already stored, and so takes
+    /// no new space.
+    linked: bool,
+    committed: bool,
+}
+
+impl StagedData {
+    /// Copies `reader` into a new file at `path`, hashing as it goes and
+    /// encrypting with `encryption`'s data key if one is given. `digests`
+    /// asks for the client digests to compute as well. Fails with
+    /// `InsufficientSpace` once more than `max_size` bytes arrive.
+    pub(crate) async fn write<R>(
+        path: PathBuf,
+        reader: R,
+        max_size: u64,
+        encryption: Option<(DataKey, ObjectEncryption)>,
+        digests: UploadDigests,
+    ) -> Result<Self>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut writer = StagingWriter::create(path, encryption, digests).await?;
+        writer.append(reader, max_size).await?;
+        writer.finish().await
+    }
+
+    /// Stages an existing data file by hard-linking it to `path`, so no bytes
+    /// are copied. Falls back to a copy where hard links are unsupported.
+    /// Encrypted data keeps its data key, rewrapped as `encryption`.
+    pub(crate) async fn link(
+        source: &Path,
+        path: PathBuf,
+        size: u64,
+        checksum: Checksum,
+        encryption: Option<ObjectEncryption>,
+    ) -> Result<Self> {
+        let linked = fs::hard_link(source, &path).await.is_ok();
+        if !linked {
+            fs::copy(source, &path).await?;
+        }
+
+        Ok(Self {
+            path,
+            size,
+            data_id: data_id(&checksum, encryption.as_ref()),
+            checksum,
+            encryption,
+            content_md5: None,
+            linked,
+            committed: false,
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn checksum(&self) -> &Checksum {
+        &self.checksum
+    }
+
+    pub fn encryption(&self) -> Option<&ObjectEncryption> {
+        self.encryption.as_ref()
+    }
+
+    /// The base64 MD5 of the data, if `UploadDigests::content_md5` asked
+    /// for it.
+    pub fn content_md5(&self) -> Option<&str> {
+        self.content_md5.as_deref()
+    }
+
+    pub(crate) fn data_id(&self) -> &str {
+        &self.data_id
+    }
+
+    pub(crate) fn is_linked(&self) -> bool {
+        self.linked
+    }
+
+    /// Moves the staged file to `destination`, consuming the staging handle.
+    pub(crate) async fn commit_to(mut self, destination: &Path) -> Result<()> {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::rename(&self.path, destination).await?;
+        self.committed = true;
+
+        // Renaming a hard link onto another link to the same file is a no-op
+        // that leaves the source name behind
+        if fs::try_exists(&self.path).await? {
+            fs::remove_file(&self.path).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StagedData {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Builds staged data from any number of readers in turn, as when the parts
+/// of a multipart upload are assembled.
+pub(crate) struct StagingWriter {
+    file: fs::File,
+    staged: StagedData,
+    sealer: Option<Sealer>,
+    sha256: Sha256,
+    blake3: blake3::Hasher,
+    md5: Option<Md5>,
+    client_checksum: Option<ChecksumHasher>,
+    buffer: Vec<u8>,
+}
+
+impl StagingWriter {
+    pub(crate) async fn create(
+        path: PathBuf,
+        encryption: Option<(DataKey, ObjectEncryption)>,
+        digests: UploadDigests,
+    ) -> Result<Self> {
+        let file = fs::File::create(&path).await?;
+        let (sealer, encryption) = match encryption {
+            Some((key, encryption)) => (Some(Sealer::new(key)?), Some(encryption)),
+            None => (None, None),
+        };
+
+        Ok(Self {
+            file,
+            staged: StagedData {
+                path,
+                size: 0,
+                checksum: Checksum { sha256: String::new(), blake3: String::new(), client: None },
+                data_id: String::new(),
+                encryption,
+                content_md5: None,
+                linked: false,
+                committed: false,
+            },
+            sealer,
+            sha256: Sha256::new(),
+            blake3: blake3::Hasher::new(),
+            md5: digests.content_md5.then(Md5::new),
+            client_checksum: digests.checksum.map(ChecksumHasher::new),
+            buffer: vec![0u8; BUFFER_SIZE],
+        })
+    }
+
+    /// Copies all of `reader`. Fails with `InsufficientSpace` once the data
+    /// staged so far exceeds `max_size` bytes.
+    pub(crate) async fn append<R>(&mut self, mut reader: R, max_size: u64) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        loop {
+            let n = reader.read(&mut self.buffer).await?;
+            if n == 0 {
+                return Ok(());
+            }
+
+            self.staged.size += n as u64;
+            if self.staged.size > max_size {
+                return Err(StorageError::InsufficientSpace(
+                    format!("Object exceeds the {} bytes of space available", max_size)
+                ));
+            }
+
+            let data = &self.buffer[..n];
+            self.sha256.update(data);
+            self.blake3.update(data);
+            if let Some(md5) = &mut self.md5 {
+                md5.update(data);
+            }
+            if let Some(hasher) = &mut self.client_checksum {
+                hasher.update(data);
+            }
+            match &mut self.sealer {
+                Some(sealer) => self.file.write_all(&sealer.update(data)?).await?,
+                None => self.file.write_all(data).await?,
+            }
+        }
+    }
+
+    pub(crate) async fn finish(mut self) -> Result<StagedData> {
+        if let Some(sealer) = self.sealer.take() {
+            self.file.write_all(&sealer.finish()?).await?;
+        }
+        self.file.flush().await?;
+        self.file.sync_all().await?;
+
+        let mut staged = self.staged;
+        staged.checksum = Checksum {
+            sha256: format!("{:x}", self.sha256.finalize()),
+            blake3: self.blake3.finalize().to_hex().to_string(),
+            client: self.client_checksum.map(ChecksumHasher::finish),
+        };
+        staged.content_md5 = self.md5.map(|md5| openssl::base64::encode_block(&md5.finalize()));
+        staged.data_id = data_id(&staged.checksum, staged.encryption.as_ref());
+
+        Ok(staged)
+    }
+}
+
+fn data_id(checksum: &Checksum, encryption: Option<&ObjectEncryption>) -> String {
+    match encryption {
+        Some(_) => Uuid::new_v4().simple().to_string(),
+        None => checksum.blake3.clone(),
+    }
+}
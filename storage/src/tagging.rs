@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use crate::{Result, StorageError};
+
+/// Most tags one object version may carry.
+pub const MAX_OBJECT_TAGS: usize = 10;
+/// Longest tag key, in characters.
+pub const MAX_TAG_KEY_LENGTH: usize = 128;
+/// Longest tag value, in characters.
+pub const MAX_TAG_VALUE_LENGTH: usize = 256;
+
+/// Key/value pairs attached to an object version, in the order given.
+pub type TagSet = Vec<(String, String)>;
+
+/// Checks a tag set against the limits S3 places on object tags.
+pub fn validate_tags(tags: &[(String, String)]) -> Result<()> {
+    let invalid = |message: String| Err(StorageError::InvalidTag(message));
+
+    if tags.len() > MAX_OBJECT_TAGS {
+        return invalid(format!("Object tags cannot be greater than {}", MAX_OBJECT_TAGS));
+    }
+
+    let mut keys = HashSet::new();
+    for (key, value) in tags {
+        if key.is_empty() || key.chars().count() > MAX_TAG_KEY_LENGTH {
+            return invalid(format!("The TagKey you have provided is invalid: {:?}", key));
+        }
+        if value.chars().count() > MAX_TAG_VALUE_LENGTH {
+            return invalid(format!("The TagValue you have provided is invalid: {:?}", value));
+        }
+        if !key.chars().chain(value.chars()).all(is_tag_char) {
+            return invalid(format!("The tag {:?}={:?} contains characters that are not allowed", key, value));
+        }
+        if key.starts_with("aws:") {
+            return invalid("Your TagKey cannot be prefixed with aws:".to_string());
+        }
+        if !keys.insert(key) {
+            return invalid("Cannot provide multiple Tags with the same key".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Letters, digits, whitespace and `+ - = . _ : / @`.
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c.is_whitespace() || "+-=._:/@".contains(c)
+}
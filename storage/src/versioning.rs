@@ -6,6 +6,28 @@ use crate::object::{Object, ObjectId, ObjectReference};
 
 pub type Version = Uuid;
 
+/// Version ID given to writes while versioning is not enabled; S3 spells it
+/// `null`. There is at most one null version per key.
+pub const NULL_VERSION: Version = Uuid::nil();
+
+/// Formats a version ID the way S3 clients expect it.
+pub fn format_version_id(version_id: &Version) -> String {
+    if version_id.is_nil() {
+        "null".to_string()
+    } else {
+        version_id.to_string()
+    }
+}
+
+/// Parses a client-supplied version ID, accepting `null`.
+pub fn parse_version_id(version_id: &str) -> Option<Version> {
+    if version_id == "null" {
+        Some(NULL_VERSION)
+    } else {
+        Uuid::parse_str(version_id).ok().filter(|v| !v.is_nil())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VersionedObject {
     pub bucket: String,
@@ -71,6 +93,7 @@ impl VersionedObject {
             key: self.key.clone(),
             version_id,
             created_at,
+            is_latest: true,
         }
     }
 
@@ -148,18 +171,31 @@ pub struct DeleteMarker {
     pub key: String,
     pub version_id: Version,
     pub created_at: DateTime<Utc>,
+    pub is_latest: bool,
+}
+
+/// Outcome of deleting a key or one of its versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteResult {
+    /// The version removed or the delete marker created; `None` when an
+    /// unversioned object was removed outright.
+    pub version_id: Option<Version>,
+    /// Whether a delete marker was created or removed.
+    pub delete_marker: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListVersionsResponse {
     pub bucket: String,
     pub prefix: Option<String>,
+    pub delimiter: Option<String>,
     pub key_marker: Option<String>,
     pub version_id_marker: Option<Version>,
     pub max_keys: usize,
     pub is_truncated: bool,
     pub versions: Vec<ObjectVersion>,
     pub delete_markers: Vec<DeleteMarker>,
+    pub common_prefixes: Vec<String>,
     pub next_key_marker: Option<String>,
     pub next_version_id_marker: Option<Version>,
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+mod common;
+
+use common::empty_engine;
+use storage::{
+    AccessControlList, CannedAcl, Grant, Grantee, ObjectAttributes, Permission, StorageError,
+};
+
+#[test]
+fn canned_acls_grant_the_owner_and_groups() {
+    let private = AccessControlList::canned(CannedAcl::Private, "alice");
+    assert!(private.allows(Some("alice"), Permission::Read));
+    assert!(private.allows(Some("alice"), Permission::WriteAcp));
+    assert!(!private.allows(Some("bob"), Permission::Read));
+    assert!(!private.allows(None, Permission::Read));
+
+    let public_read = AccessControlList::canned(CannedAcl::PublicRead, "alice");
+    assert!(public_read.allows(None, Permission::Read));
+    assert!(!public_read.allows(Some("bob"), Permission::Write));
+
+    let public_read_write = AccessControlList::canned(CannedAcl::PublicReadWrite, "alice");
+    assert!(public_read_write.allows(None, Permission::Write));
+    assert!(!public_read_write.allows(None, Permission::ReadAcp));
+
+    let authenticated_read = AccessControlList::canned(CannedAcl::AuthenticatedRead, "alice");
+    assert!(authenticated_read.allows(Some("bob"), Permission::Read));
+    assert!(!authenticated_read.allows(None, Permission::Read));
+}
+
+#[tokio::test]
+async fn buckets_and_objects_record_owner_and_acl() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket("photos", None, Some("alice")).await.unwrap();
+
+    let bucket = engine.get_bucket("photos").await.unwrap().unwrap();
+    assert_eq!(bucket.acl, Some(AccessControlList::private("alice")));
+
+    engine.set_bucket_acl("photos", AccessControlList::canned(CannedAcl::PublicRead, "alice")).await.unwrap();
+    let bucket = engine.get_bucket("photos").await.unwrap().unwrap();
+    assert!(bucket.acl.unwrap().allows(None, Permission::Read));
+
+    let staged = engine.stage_data(&b"data"[..]).await.unwrap();
+    let attributes = ObjectAttributes {
+        owner_id: Some("bob".to_string()),
+        acl: Some(AccessControlList::private("bob")),
+        ..Default::default()
+    };
+    engine.put_staged_object("photos", "cat.jpg", staged, attributes).await.unwrap();
+
+    let record = engine.get_object_record("photos", "cat.jpg", None).await.unwrap().unwrap();
+    assert_eq!(record.metadata.owner_id.as_deref(), Some("bob"));
+    assert_eq!(record.metadata.acl, Some(AccessControlList::private("bob")));
+
+    let page = engine.list_objects("photos", None, None, None, 10, &[]).await.unwrap();
+    assert_eq!(page.objects[0].owner_id.as_deref(), Some("bob"));
+
+    let mut shared = AccessControlList::private("bob");
+    shared.grants.push(Grant {
+        grantee: Grantee::CanonicalUser("carol".to_string()),
+        permission: Permission::Read,
+    });
+    engine.set_object_acl("photos", "cat.jpg", None, shared.clone()).await.unwrap().unwrap();
+    let record = engine.get_object_record("photos", "cat.jpg", None).await.unwrap().unwrap();
+    assert_eq!(record.metadata.acl, Some(shared));
+    assert_eq!(record.metadata.owner_id.as_deref(), Some("bob"));
+
+    assert!(engine.set_object_acl("photos", "missing", None, AccessControlList::default()).await.unwrap().is_none());
+    let result = engine.set_bucket_acl("missing", AccessControlList::default()).await;
+    assert!(matches!(result, Err(StorageError::NoSuchBucket(_))));
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use bytes::Bytes;
+use common::empty_engine;
+use std::collections::HashMap;
+use storage::{AccessControlList, CannedAcl, ObjectAttributes, StorageEngine, StorageError};
+
+async fn engine(dir: &tempfile::TempDir, auto_create_buckets: bool) -> StorageEngine {
+    empty_engine(dir).await.with_auto_create_buckets(auto_create_buckets)
+}
+
+#[tokio::test]
+async fn put_to_missing_bucket_fails_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+
+    let result = engine.put_object("missing", "key", Bytes::from_static(b"data"), None, HashMap::new()).await;
+    assert!(matches!(result, Err(StorageError::NoSuchBucket(name)) if name == "missing"));
+    assert!(!engine.bucket_exists("missing").await.unwrap());
+
+    let result = engine.create_multipart_upload("missing", "key", Default::default(), None).await;
+    assert!(matches!(result, Err(StorageError::NoSuchBucket(_))));
+}
+
+#[tokio::test]
+async fn put_to_existing_bucket_succeeds_without_auto_create() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, false).await;
+
+    engine.create_bucket("photos", None, Some("owner")).await.unwrap();
+    let object = engine.put_object("photos", "cat.jpg", Bytes::from_static(b"meow"), None, HashMap::new()).await.unwrap();
+    assert_eq!(object.size, 4);
+}
+
+#[tokio::test]
+async fn put_to_missing_bucket_creates_it_when_enabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, true).await;
+
+    let attributes = ObjectAttributes {
+        owner_id: Some("alice".to_string()),
+        acl: Some(AccessControlList::canned(CannedAcl::Private, "alice")),
+        ..Default::default()
+    };
+    let staged = engine.stage_data(&b"meow"[..]).await.unwrap();
+    engine.put_staged_object("photos", "cat.jpg", staged, attributes.clone()).await.unwrap();
+    let bucket = engine.get_bucket("photos").await.unwrap().expect("bucket was not created");
+    assert_eq!(bucket.region, storage::DEFAULT_REGION);
+    assert_eq!(bucket.owner_id.as_deref(), Some("alice"));
+    assert_eq!(bucket.acl, attributes.acl);
+
+    // A second write reuses the bucket rather than recreating it
+    engine.put_object("photos", "dog.jpg", Bytes::from_static(b"woof"), None, HashMap::new()).await.unwrap();
+    assert_eq!(engine.list_buckets().await.unwrap().len(), 1);
+
+    engine.create_multipart_upload("uploads", "big", attributes, None).await.unwrap();
+    let bucket = engine.get_bucket("uploads").await.unwrap().expect("bucket was not created");
+    assert_eq!(bucket.owner_id.as_deref(), Some("alice"));
+}
+
+#[tokio::test]
+async fn auto_create_still_validates_bucket_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, true).await;
+
+    let result = engine.put_object("Not_Valid", "key", Bytes::from_static(b"data"), None, HashMap::new()).await;
+    assert!(matches!(result, Err(StorageError::InvalidBucketName(_))));
+}
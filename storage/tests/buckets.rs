@@ -0,0 +1,160 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{empty_engine, engine, put};
+use storage::{
+    validate_bucket_name, AccessControlList, CannedAcl, ObjectAttributes, StorageError, VersioningStatus,
+    DEFAULT_REGION,
+};
+
+#[tokio::test]
+async fn buckets_are_created_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+
+    let created = engine.create_bucket("photos", Some("eu-west-1"), Some("alice")).await.unwrap();
+    assert_eq!((created.region.as_str(), created.owner_id.as_deref()), ("eu-west-1", Some("alice")));
+    let docs = engine.create_bucket("docs", None, Some("bob")).await.unwrap();
+    assert_eq!(docs.region, DEFAULT_REGION);
+
+    let result = engine.create_bucket("photos", None, Some("bob")).await;
+    assert!(matches!(result, Err(StorageError::BucketAlreadyExists(_))));
+    let stored = engine.get_bucket("photos").await.unwrap().unwrap();
+    assert_eq!(stored.owner_id.as_deref(), Some("alice"));
+
+    let mut names: Vec<_> = engine.list_buckets().await.unwrap().into_iter().map(|bucket| bucket.name).collect();
+    names.sort();
+    assert_eq!(names, ["docs", "photos"]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_creates_of_one_name_succeed_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = Arc::new(empty_engine(&dir).await);
+
+    let tasks: Vec<_> = (0..5)
+        .map(|i| {
+            let engine = engine.clone();
+            tokio::spawn(async move { engine.create_bucket("photos", None, Some(&format!("owner{}", i))).await })
+        })
+        .collect();
+    let mut created = Vec::new();
+    for task in tasks {
+        match task.await.unwrap() {
+            Ok(bucket) => created.push(bucket),
+            Err(e) => assert!(matches!(e, StorageError::BucketAlreadyExists(_)), "{}", e),
+        }
+    }
+
+    assert_eq!(created.len(), 1);
+    let stored = engine.get_bucket("photos").await.unwrap().unwrap();
+    assert_eq!(stored.owner_id, created[0].owner_id);
+    assert_eq!(engine.list_buckets().await.unwrap().len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_creates_of_different_names_all_survive() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = Arc::new(empty_engine(&dir).await);
+
+    let tasks: Vec<_> = (0..10)
+        .map(|i| {
+            let engine = engine.clone();
+            tokio::spawn(async move {
+                engine.create_bucket(&format!("bucket-{}", i), None, None).await.unwrap();
+                // Reads run alongside the other creates
+                engine.list_buckets().await.unwrap();
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let names: Vec<_> = engine.list_buckets().await.unwrap().into_iter().map(|bucket| bucket.name).collect();
+    let mut expected: Vec<_> = (0..10).map(|i| format!("bucket-{}", i)).collect();
+    expected.sort();
+    assert_eq!(names, expected);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_setting_changes_are_all_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = Arc::new(engine(&dir, "photos").await);
+    let acl = AccessControlList::canned(CannedAcl::PublicRead, "alice");
+
+    let versioning = {
+        let engine = engine.clone();
+        tokio::spawn(async move { engine.set_bucket_versioning("photos", VersioningStatus::Enabled).await })
+    };
+    let access = {
+        let (engine, acl) = (engine.clone(), acl.clone());
+        tokio::spawn(async move { engine.set_bucket_acl("photos", acl).await })
+    };
+    let reader = {
+        let engine = engine.clone();
+        tokio::spawn(async move {
+            for _ in 0..10 {
+                assert!(engine.get_bucket("photos").await.unwrap().is_some());
+            }
+        })
+    };
+    versioning.await.unwrap().unwrap();
+    access.await.unwrap().unwrap();
+    reader.await.unwrap();
+
+    let stored = engine.get_bucket("photos").await.unwrap().unwrap();
+    assert_eq!(stored.versioning, VersioningStatus::Enabled);
+    assert_eq!(stored.acl, Some(acl));
+}
+
+#[test]
+fn bucket_names_follow_the_s3_rules() {
+    for name in ["abc", "my.bucket-1", "0numbers9", &"a".repeat(63)] {
+        validate_bucket_name(name).unwrap();
+    }
+    for name in ["ab", &"a".repeat(64), "Photos", "under_score", "-dash", "dot.", "a..b", "192.168.1.1", "xn--abc", "abc-s3alias"] {
+        assert!(matches!(validate_bucket_name(name), Err(StorageError::InvalidBucketName(_))), "{}", name);
+    }
+}
+
+#[tokio::test]
+async fn only_empty_buckets_are_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "photos").await;
+    engine.set_bucket_versioning("photos", VersioningStatus::Enabled).await.unwrap();
+
+    let result = engine.delete_bucket("missing").await;
+    assert!(matches!(result, Err(StorageError::NoSuchBucket(_))));
+
+    put(&engine, "photos", "cat.jpg", b"meow").await;
+    let result = engine.delete_bucket("photos").await;
+    assert!(matches!(result, Err(StorageError::BucketNotEmpty(_))));
+
+    // The old version and the delete marker are still content
+    engine.delete_object("photos", "cat.jpg", None, false).await.unwrap();
+    let result = engine.delete_bucket("photos").await;
+    assert!(matches!(result, Err(StorageError::BucketNotEmpty(_))));
+
+    let versions = engine.list_object_versions("photos", None, None, None, None, 1000).await.unwrap();
+    for version_id in versions.versions.iter().map(|v| v.version_id).chain(versions.delete_markers.iter().map(|m| m.version_id)) {
+        engine.delete_object("photos", "cat.jpg", Some(version_id), false).await.unwrap();
+    }
+    engine.delete_bucket("photos").await.unwrap();
+    assert!(engine.get_bucket("photos").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn deleting_a_bucket_aborts_its_uploads() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload = engine.create_multipart_upload("uploads", "big", ObjectAttributes::default(), None).await.unwrap();
+
+    engine.delete_bucket("uploads").await.unwrap();
+    assert!(engine.get_multipart_upload(&upload.upload_id).await.unwrap().is_none());
+
+    // The name can be reused
+    engine.create_bucket("uploads", None, None).await.unwrap();
+    assert!(engine.list_multipart_uploads("uploads", None, None, None, 1000).await.unwrap().uploads.is_empty());
+}
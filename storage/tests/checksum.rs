@@ -0,0 +1,58 @@
+mod common;
+
+use common::{empty_engine, keyed_engine};
+use storage::{
+    ChecksumAlgorithm, ClientChecksum, ObjectAttributes, ServerSideEncryption, UploadDigests,
+};
+
+#[tokio::test]
+async fn client_digests_are_computed_on_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+
+    let staged = engine.stage_data(&b"123456789"[..]).await.unwrap();
+    assert!(staged.content_md5().is_none());
+    assert!(staged.checksum().client.is_none());
+
+    let expected = [
+        (ChecksumAlgorithm::Crc32, "y/Q5Jg=="),
+        (ChecksumAlgorithm::Crc32c, "4waSgw=="),
+        (ChecksumAlgorithm::Sha1, "98O8HYCOBHMq32eZZczDTKeuNEE="),
+        (ChecksumAlgorithm::Sha256, "FeKw08M4keuw8e9gnsQZQgwg4yDOlMZfvIwzEkSOsiU="),
+    ];
+    for (algorithm, value) in expected {
+        let digests = UploadDigests { content_md5: true, checksum: Some(algorithm) };
+        let staged = engine.stage_data_with(&b"123456789"[..], None, digests).await.unwrap();
+        assert_eq!(staged.content_md5(), Some("JfnnlDI7RTiF9RgfG2JNCw=="));
+        assert_eq!(staged.checksum().client, Some(ClientChecksum { algorithm, value: value.to_string() }));
+    }
+
+    assert_eq!(ChecksumAlgorithm::from_name("crc32c"), Some(ChecksumAlgorithm::Crc32c));
+    assert_eq!(ChecksumAlgorithm::from_name("MD5"), None);
+}
+
+#[tokio::test]
+async fn client_checksums_are_stored_and_kept_by_copies() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = keyed_engine(&dir).await;
+    engine.create_bucket("sums", None, None).await.unwrap();
+
+    let digests = UploadDigests { content_md5: false, checksum: Some(ChecksumAlgorithm::Crc32c) };
+    let staged = engine.stage_data_with(&b"123456789"[..], None, digests).await.unwrap();
+    engine.put_staged_object("sums", "a", staged, ObjectAttributes::default()).await.unwrap();
+    let source = engine.get_object_record("sums", "a", None).await.unwrap().unwrap();
+    let checksum = source.checksum.client.clone().unwrap();
+    assert_eq!(checksum.value, "4waSgw==");
+
+    // Linked and re-encrypted copies both carry the checksum over
+    for (key, encryption) in [("linked", None), ("encrypted", Some(ServerSideEncryption::S3))] {
+        let staged = engine.stage_existing(&source, None, encryption.as_ref()).await.unwrap();
+        engine.put_staged_object("sums", key, staged, ObjectAttributes::default()).await.unwrap();
+        let copy = engine.get_object_record("sums", key, None).await.unwrap().unwrap();
+        assert_eq!(copy.checksum.client.as_ref(), Some(&checksum));
+    }
+
+    engine.put_object("sums", "a", bytes::Bytes::from_static(b"plain"), None, Default::default()).await.unwrap();
+    let record = engine.get_object_record("sums", "a", None).await.unwrap().unwrap();
+    assert!(record.checksum.client.is_none());
+}
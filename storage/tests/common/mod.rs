@@ -0,0 +1,44 @@
+//! Fixtures shared by the storage integration tests.
+#![allow(dead_code)]
+
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use storage::{ObjectReference, StorageEngine};
+
+/// An engine over an empty storage directory, with room for 1 GiB.
+pub async fn empty_engine(dir: &tempfile::TempDir) -> StorageEngine {
+    StorageEngine::new(dir.path().to_str().unwrap(), 1 << 30).await.unwrap()
+}
+
+/// An engine over an empty storage directory that can encrypt with SSE-S3.
+pub async fn keyed_engine(dir: &tempfile::TempDir) -> StorageEngine {
+    empty_engine(dir).await.with_master_key([3u8; storage::KEY_LENGTH])
+}
+
+/// An engine with `bucket` already created.
+pub async fn engine(dir: &tempfile::TempDir, bucket: &str) -> StorageEngine {
+    let engine = empty_engine(dir).await;
+    engine.create_bucket(bucket, None, None).await.unwrap();
+    engine
+}
+
+/// Stores `data` at `bucket/key` without any metadata.
+pub async fn put(engine: &StorageEngine, bucket: &str, key: &str, data: &[u8]) -> ObjectReference {
+    engine.put_object(bucket, key, Bytes::copy_from_slice(data), None, HashMap::new()).await.unwrap()
+}
+
+/// Every data file under `objects/`, which must all sit one directory down.
+pub fn data_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in std::fs::read_dir(root.join("objects")).unwrap() {
+        let dir = dir.unwrap().path();
+        assert!(dir.is_dir(), "unexpected file {:?}", dir);
+        for file in std::fs::read_dir(&dir).unwrap() {
+            let file = file.unwrap().path();
+            assert!(file.is_file(), "unexpected directory {:?}", file);
+            files.push(file);
+        }
+    }
+    files
+}
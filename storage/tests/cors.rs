@@ -0,0 +1,69 @@
+mod common;
+
+use common::empty_engine;
+use storage::{CorsConfiguration, CorsRule, StorageError};
+
+fn rule(origins: &[&str], methods: &[&str], headers: &[&str]) -> CorsRule {
+    CorsRule {
+        allowed_origins: origins.iter().map(|s| s.to_string()).collect(),
+        allowed_methods: methods.iter().map(|s| s.to_string()).collect(),
+        allowed_headers: headers.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn first_matching_rule_applies() {
+    let config = CorsConfiguration {
+        rules: vec![
+            rule(&["https://*.example.com"], &["PUT", "DELETE"], &["Content-*"]),
+            rule(&["*"], &["GET"], &[]),
+        ],
+    };
+    assert!(config.validate().is_ok());
+
+    let upload = config.find_rule("https://app.example.com", "PUT", &["content-type"]).unwrap();
+    assert_eq!(upload.allowed_methods, vec!["PUT", "DELETE"]);
+    assert!(config.find_rule("https://app.example.com", "PUT", &["x-amz-date"]).is_none());
+    assert!(config.find_rule("https://example.org", "PUT", &[]).is_none());
+
+    let read = config.find_rule("https://example.org", "GET", &[]).unwrap();
+    assert!(read.allows_any_origin());
+    assert!(config.find_rule("https://example.org", "GET", &["authorization"]).is_none());
+}
+
+#[test]
+fn invalid_configurations_are_rejected() {
+    let empty = CorsConfiguration::default();
+    assert!(matches!(empty.validate(), Err(StorageError::InvalidArgument(_))));
+
+    let patch = CorsConfiguration { rules: vec![rule(&["*"], &["PATCH"], &[])] };
+    assert!(matches!(patch.validate(), Err(StorageError::InvalidArgument(_))));
+
+    let wildcards = CorsConfiguration { rules: vec![rule(&["https://*.*.com"], &["GET"], &[])] };
+    assert!(matches!(wildcards.validate(), Err(StorageError::InvalidArgument(_))));
+}
+
+#[tokio::test]
+async fn cors_configuration_is_stored_with_the_bucket() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket("site", None, None).await.unwrap();
+
+    let config = CorsConfiguration {
+        rules: vec![CorsRule {
+            id: Some("browser".to_string()),
+            expose_headers: vec!["ETag".to_string()],
+            max_age_seconds: Some(3000),
+            ..rule(&["https://example.com"], &["GET", "PUT"], &["*"])
+        }],
+    };
+    engine.set_bucket_cors("site", Some(config.clone())).await.unwrap();
+    assert_eq!(engine.get_bucket("site").await.unwrap().unwrap().cors, Some(config));
+
+    engine.set_bucket_cors("site", None).await.unwrap();
+    assert_eq!(engine.get_bucket("site").await.unwrap().unwrap().cors, None);
+
+    let result = engine.set_bucket_cors("missing", None).await;
+    assert!(matches!(result, Err(StorageError::NoSuchBucket(_))));
+}
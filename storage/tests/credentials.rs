@@ -0,0 +1,109 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::empty_engine;
+use storage::StorageError;
+
+#[tokio::test]
+async fn credentials_are_created_rotated_and_revoked() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+
+    let alice = engine.create_credential("alice").await.unwrap();
+    let bob = engine.create_credential("bob").await.unwrap();
+    assert!(alice.enabled && alice.access_key.starts_with("O3"));
+    assert_ne!(alice.access_key, bob.access_key);
+
+    let stored = engine.get_credential(&alice.access_key).await.unwrap().unwrap();
+    assert_eq!((stored.secret_key, stored.owner_id), (alice.secret_key.clone(), "alice".to_string()));
+
+    let rotated = engine.rotate_credential(&alice.access_key).await.unwrap();
+    assert_eq!((&rotated.access_key, &rotated.owner_id), (&alice.access_key, &alice.owner_id));
+    assert_ne!(rotated.secret_key, alice.secret_key);
+    let stored = engine.get_credential(&alice.access_key).await.unwrap().unwrap();
+    assert_eq!(stored.secret_key, rotated.secret_key);
+
+    engine.revoke_credential(&bob.access_key).await.unwrap();
+    let stored = engine.get_credential(&bob.access_key).await.unwrap().unwrap();
+    assert!(!stored.enabled);
+
+    let mut listed: Vec<_> = engine.list_credentials().await.unwrap().into_iter()
+        .map(|credential| (credential.owner_id, credential.enabled))
+        .collect();
+    listed.sort();
+    assert_eq!(listed, [("alice".to_string(), true), ("bob".to_string(), false)]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrently_created_credentials_are_all_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = Arc::new(empty_engine(&dir).await);
+
+    let tasks: Vec<_> = (0..10)
+        .map(|i| {
+            let engine = engine.clone();
+            tokio::spawn(async move {
+                let credential = engine.create_credential(&format!("owner{}", i)).await.unwrap();
+                // Lookups run alongside the other writes, as signed requests would
+                engine.get_credential(&credential.access_key).await.unwrap().unwrap();
+                credential
+            })
+        })
+        .collect();
+    let mut created = Vec::new();
+    for task in tasks {
+        created.push(task.await.unwrap().access_key);
+    }
+
+    let mut listed: Vec<_> = engine.list_credentials().await.unwrap().into_iter()
+        .map(|credential| credential.access_key)
+        .collect();
+    created.sort();
+    listed.sort();
+    assert_eq!(listed, created);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn a_concurrent_rotation_does_not_undo_a_revocation() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = Arc::new(empty_engine(&dir).await);
+    let alice = engine.create_credential("alice").await.unwrap();
+
+    let rotate = {
+        let (engine, access_key) = (engine.clone(), alice.access_key.clone());
+        tokio::spawn(async move { engine.rotate_credential(&access_key).await })
+    };
+    let revoke = {
+        let (engine, access_key) = (engine.clone(), alice.access_key.clone());
+        tokio::spawn(async move { engine.revoke_credential(&access_key).await })
+    };
+    let rotated = rotate.await.unwrap().unwrap();
+    revoke.await.unwrap().unwrap();
+
+    let stored = engine.get_credential(&alice.access_key).await.unwrap().unwrap();
+    assert!(!stored.enabled);
+    assert_eq!(stored.secret_key, rotated.secret_key);
+}
+
+#[tokio::test]
+async fn credentials_survive_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let credential = empty_engine(&dir).await.create_credential("alice").await.unwrap();
+
+    let engine = empty_engine(&dir).await;
+    let stored = engine.get_credential(&credential.access_key).await.unwrap().unwrap();
+    assert_eq!(stored.secret_key, credential.secret_key);
+}
+
+#[tokio::test]
+async fn unknown_access_keys_are_not_found() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+
+    assert!(engine.get_credential("O3UNKNOWN").await.unwrap().is_none());
+    let result = engine.rotate_credential("O3UNKNOWN").await;
+    assert!(matches!(result, Err(StorageError::ObjectNotFound(_))));
+    let result = engine.revoke_credential("O3UNKNOWN").await;
+    assert!(matches!(result, Err(StorageError::ObjectNotFound(_))));
+}
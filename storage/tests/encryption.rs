@@ -0,0 +1,173 @@
+mod common;
+
+use common::{empty_engine, keyed_engine};
+use std::path::{Path, PathBuf};
+use storage::{
+    BucketEncryption, CustomerKey, EncryptionMode, ObjectAttributes, ServerSideEncryption, StorageEngine, StorageError, UploadDigests,
+    MIN_PART_SIZE,
+};
+use tokio::io::AsyncReadExt;
+
+fn data(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+fn data_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join("objects").join(&id[..2]).join(id)
+}
+
+async fn read(engine: &StorageEngine, key: &str, offset: u64, length: u64, customer_key: Option<&CustomerKey>)
+    -> storage::Result<Vec<u8>>
+{
+    let record = engine.get_object_record("vault", key, None).await?.unwrap();
+    let mut output = Vec::new();
+    engine.read_range(&record, offset, length, customer_key).await?.read_to_end(&mut output).await?;
+    Ok(output)
+}
+
+#[tokio::test]
+async fn sse_s3_data_is_encrypted_at_rest_and_readable_in_ranges() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = keyed_engine(&dir).await;
+    engine.create_bucket("vault", None, None).await.unwrap();
+
+    let plaintext = data(200 * 1024);
+    let staged = engine.stage_data_with(&plaintext[..], Some(&ServerSideEncryption::S3), UploadDigests::default()).await.unwrap();
+    let object = engine.put_staged_object("vault", "secret", staged, ObjectAttributes::default()).await.unwrap();
+
+    let record = engine.get_object_record("vault", "secret", None).await.unwrap().unwrap();
+    assert_eq!(record.metadata.encryption.unwrap().mode, EncryptionMode::S3);
+    let stored = std::fs::read(data_path(dir.path(), &object.id)).unwrap();
+    assert!(!stored.windows(64).any(|window| window == &plaintext[1000..1064]));
+
+    assert_eq!(read(&engine, "secret", 0, plaintext.len() as u64, None).await.unwrap(), plaintext);
+    assert_eq!(read(&engine, "secret", 65_530, 20, None).await.unwrap(), &plaintext[65_530..65_550]);
+    assert_eq!(read(&engine, "secret", 150_000, 100_000, None).await.unwrap(), &plaintext[150_000..]);
+    assert_eq!(engine.get_object("vault", "secret", None).await.unwrap().unwrap().data, plaintext);
+
+    // Tampering with the stored bytes is detected
+    let mut tampered = stored;
+    tampered[70_000] ^= 1;
+    std::fs::write(data_path(dir.path(), &object.id), tampered).unwrap();
+    assert!(read(&engine, "secret", 0, plaintext.len() as u64, None).await.is_err());
+    assert_eq!(read(&engine, "secret", 0, 100, None).await.unwrap(), &plaintext[..100]);
+}
+
+#[tokio::test]
+async fn sse_c_objects_need_the_customer_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = keyed_engine(&dir).await;
+    engine.create_bucket("vault", None, None).await.unwrap();
+
+    let key = CustomerKey::new(&[7u8; 32]).unwrap();
+    let other_key = CustomerKey::new(&[8u8; 32]).unwrap();
+    let sse = ServerSideEncryption::Customer(key.clone());
+    let staged = engine.stage_data_with(&b"customer data"[..], Some(&sse), UploadDigests::default()).await.unwrap();
+    engine.put_staged_object("vault", "secret", staged, ObjectAttributes::default()).await.unwrap();
+
+    assert!(matches!(read(&engine, "secret", 0, 13, None).await, Err(StorageError::InvalidRequest(_))));
+    assert!(matches!(
+        read(&engine, "secret", 0, 13, Some(&other_key)).await,
+        Err(StorageError::InvalidEncryptionKey(_))
+    ));
+    assert_eq!(read(&engine, "secret", 0, 13, Some(&key)).await.unwrap(), b"customer data");
+    assert!(CustomerKey::new(&[7u8; 16]).is_err());
+
+    // A copy can move the data under SSE-S3 without the bytes being rewritten
+    let source = engine.get_object_record("vault", "secret", None).await.unwrap().unwrap();
+    assert!(engine.stage_existing(&source, None, Some(&ServerSideEncryption::S3)).await.is_err());
+    let staged = engine.stage_existing(&source, Some(&key), Some(&ServerSideEncryption::S3)).await.unwrap();
+    engine.put_staged_object("vault", "copy", staged, ObjectAttributes::default()).await.unwrap();
+    assert_eq!(read(&engine, "copy", 0, 13, None).await.unwrap(), b"customer data");
+
+    // Or decrypt it into a plaintext copy
+    let staged = engine.stage_existing(&source, Some(&key), None).await.unwrap();
+    engine.put_staged_object("vault", "plain", staged, ObjectAttributes::default()).await.unwrap();
+    let plain = engine.get_object_record("vault", "plain", None).await.unwrap().unwrap();
+    assert!(plain.metadata.encryption.is_none());
+    assert_eq!(std::fs::read(data_path(dir.path(), &plain.id)).unwrap(), b"customer data");
+}
+
+#[tokio::test]
+async fn encrypted_multipart_uploads_are_assembled() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket("vault", None, None).await.unwrap();
+
+    let key = CustomerKey::new(&[9u8; 32]).unwrap();
+    let sse = ServerSideEncryption::Customer(key.clone());
+    let upload = engine.create_multipart_upload("vault", "big", ObjectAttributes::default(), Some(&sse)).await.unwrap();
+
+    let plaintext = data(MIN_PART_SIZE as usize + 1000);
+    let (first, second) = plaintext.split_at(MIN_PART_SIZE as usize);
+
+    let unencrypted = engine.stage_data(first).await.unwrap();
+    let result = engine.upload_part(&upload.upload_id, 1, unencrypted).await;
+    assert!(matches!(result, Err(StorageError::InvalidRequest(_))));
+    let result = engine.stage_part_data(&upload.upload_id, first, None, UploadDigests::default()).await;
+    assert!(matches!(result, Err(StorageError::InvalidRequest(_))));
+
+    let mut parts = Vec::new();
+    for (number, part) in [(1, first), (2, second)] {
+        let staged = engine.stage_part_data(&upload.upload_id, part, Some(&key), UploadDigests::default()).await.unwrap();
+        let part = engine.upload_part(&upload.upload_id, number, staged).await.unwrap();
+        parts.push((number, part.etag));
+    }
+
+    let result = engine.complete_multipart_upload(&upload.upload_id, &parts, None).await;
+    assert!(matches!(result, Err(StorageError::InvalidRequest(_))));
+    engine.complete_multipart_upload(&upload.upload_id, &parts, Some(&key)).await.unwrap();
+
+    let length = plaintext.len() as u64;
+    assert_eq!(read(&engine, "big", 0, length, Some(&key)).await.unwrap(), plaintext);
+    let tail = read(&engine, "big", length - 1500, 1500, Some(&key)).await.unwrap();
+    assert_eq!(tail, &plaintext[plaintext.len() - 1500..]);
+}
+
+#[tokio::test]
+async fn a_supplied_master_key_is_kept_off_the_data_disk() {
+    let (dir, keys) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let key_file = keys.path().join("master.key");
+    std::fs::write(&key_file, [7u8; storage::KEY_LENGTH]).unwrap();
+    let master_key = storage::read_master_key(&key_file).await.unwrap();
+
+    let engine = empty_engine(&dir).await.with_master_key(master_key);
+    engine.create_bucket("vault", None, None).await.unwrap();
+    let plaintext = data(1024);
+    let staged = engine.stage_data_with(&plaintext[..], Some(&ServerSideEncryption::S3), UploadDigests::default()).await.unwrap();
+    engine.put_staged_object("vault", "secret", staged, ObjectAttributes::default()).await.unwrap();
+    assert!(!dir.path().join("master.key").exists());
+    drop(engine);
+
+    // Only an engine holding the same key can read the data back
+    let engine = empty_engine(&dir).await;
+    assert!(read(&engine, "secret", 0, 1024, None).await.is_err());
+    let engine = empty_engine(&dir).await.with_master_key(master_key);
+    assert_eq!(read(&engine, "secret", 0, 1024, None).await.unwrap(), plaintext);
+
+    std::fs::write(&key_file, b"short").unwrap();
+    assert!(storage::read_master_key(&key_file).await.is_err());
+}
+
+#[tokio::test]
+async fn sse_s3_is_refused_without_a_master_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket("vault", None, None).await.unwrap();
+
+    let result = engine.stage_data_with(&b"secret"[..], Some(&ServerSideEncryption::S3), UploadDigests::default()).await;
+    assert!(matches!(result, Err(StorageError::InvalidRequest(_))));
+    let result = engine.create_multipart_upload("vault", "big", ObjectAttributes::default(), Some(&ServerSideEncryption::S3)).await;
+    assert!(matches!(result, Err(StorageError::InvalidRequest(_))));
+    let result = engine.set_bucket_encryption("vault", Some(BucketEncryption::default())).await;
+    assert!(matches!(result, Err(StorageError::InvalidRequest(_))));
+    assert!(engine.get_bucket("vault").await.unwrap().unwrap().encryption.is_none());
+    assert!(!dir.path().join("master.key").exists());
+
+    // SSE-C needs no master key
+    let key = CustomerKey::new(&[7u8; 32]).unwrap();
+    let sse = ServerSideEncryption::Customer(key.clone());
+    let staged = engine.stage_data_with(&b"customer data"[..], Some(&sse), UploadDigests::default()).await.unwrap();
+    engine.put_staged_object("vault", "secret", staged, ObjectAttributes::default()).await.unwrap();
+    assert_eq!(read(&engine, "secret", 0, 13, Some(&key)).await.unwrap(), b"customer data");
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{data_files, engine};
+use storage::{StorageError, MAX_KEY_LENGTH};
+
+#[tokio::test]
+async fn path_like_keys_are_stored_under_flat_object_ids() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "paths").await;
+
+    let long_key = "k".repeat(MAX_KEY_LENGTH);
+    let keys = ["a/b/c.txt", "dir/", "../../escape", "./x/../y", "sp ace/ü%2F?#", long_key.as_str()];
+    for key in keys {
+        let data = bytes::Bytes::from(format!("data for {}", key));
+        let reference = engine.put_object("paths", key, data, None, Default::default()).await.unwrap();
+        assert!(!reference.id.contains('/') && !reference.id.contains(".."), "{}", reference.id);
+    }
+
+    assert_eq!(data_files(dir.path()).len(), keys.len());
+    assert!(!dir.path().parent().unwrap().join("escape").exists());
+    for key in keys {
+        let object = engine.get_object("paths", key, None).await.unwrap().unwrap();
+        assert_eq!(object.data, format!("data for {}", key).as_bytes());
+    }
+}
+
+#[tokio::test]
+async fn keys_are_limited_to_1024_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "paths").await;
+
+    // 513 two-byte characters
+    let long_key = "é".repeat(MAX_KEY_LENGTH / 2 + 1);
+    let result = engine.put_object("paths", &long_key, bytes::Bytes::from_static(b"x"), None, Default::default()).await;
+    assert!(matches!(result, Err(StorageError::KeyTooLong(_))));
+
+    let result = engine.create_multipart_upload("paths", &long_key, Default::default(), None).await;
+    assert!(matches!(result, Err(StorageError::KeyTooLong(_))));
+
+    let result = engine.put_object("paths", "", bytes::Bytes::from_static(b"x"), None, Default::default()).await;
+    assert!(matches!(result, Err(StorageError::InvalidArgument(_))));
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{empty_engine, put};
+use storage::{
+    Bucket, Expiration, LifecycleConfiguration, LifecycleFilter, LifecycleRule, NoncurrentVersionExpiration,
+    ObjectAttributes, ObjectLock, Retention, RetentionMode, VersioningStatus,
+};
+
+fn rule(prefix: &str) -> LifecycleRule {
+    LifecycleRule {
+        id: None,
+        enabled: true,
+        filter: LifecycleFilter { prefix: prefix.to_string(), tags: Vec::new() },
+        expiration: None,
+        noncurrent_version_expiration: None,
+        abort_incomplete_multipart_upload_days: None,
+    }
+}
+
+#[tokio::test]
+async fn expiration_removes_current_versions_under_the_prefix() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket("logs", None, None).await.unwrap();
+    put(&engine, "logs", "tmp/a", b"data").await;
+    put(&engine, "logs", "keep/b", b"data").await;
+    engine.create_multipart_upload("logs", "tmp/big", ObjectAttributes::default(), None).await.unwrap();
+
+    let mut expire = rule("tmp/");
+    expire.expiration = Some(Expiration { days: Some(1), ..Default::default() });
+    expire.abort_incomplete_multipart_upload_days = Some(1);
+    engine.set_bucket_lifecycle("logs", Some(LifecycleConfiguration { rules: vec![expire] })).await.unwrap();
+
+    // Nothing is due yet
+    let report = engine.apply_lifecycle(Utc::now()).await.unwrap();
+    assert_eq!(report.expired_objects, 0);
+
+    let report = engine.apply_lifecycle(Utc::now() + Duration::days(2)).await.unwrap();
+    assert_eq!(report.expired_objects, 1);
+    assert_eq!(report.aborted_uploads, 1);
+    assert!(engine.get_object_record("logs", "tmp/a", None).await.unwrap().is_none());
+    assert!(engine.get_object_record("logs", "keep/b", None).await.unwrap().is_some());
+    assert!(engine.list_multipart_uploads("logs", None, None, None, 1000).await.unwrap().uploads.is_empty());
+}
+
+#[tokio::test]
+async fn noncurrent_versions_and_orphaned_markers_are_cleaned_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket("docs", None, None).await.unwrap();
+    engine.set_bucket_versioning("docs", VersioningStatus::Enabled).await.unwrap();
+
+    let oldest = put(&engine, "docs", "a", b"data").await;
+    let older = put(&engine, "docs", "a", b"data").await;
+    let current = put(&engine, "docs", "a", b"data").await;
+    let gone = put(&engine, "docs", "gone", b"data").await;
+    engine.delete_object("docs", "gone", None, false).await.unwrap();
+    engine.delete_object("docs", "gone", Some(gone.version_id), false).await.unwrap();
+
+    let mut cleanup = rule("");
+    cleanup.noncurrent_version_expiration = Some(NoncurrentVersionExpiration {
+        noncurrent_days: 1,
+        newer_noncurrent_versions: Some(1),
+    });
+    let mut markers = rule("");
+    markers.expiration = Some(Expiration { expired_object_delete_marker: true, ..Default::default() });
+    engine.set_bucket_lifecycle("docs", Some(LifecycleConfiguration { rules: vec![cleanup, markers] })).await.unwrap();
+
+    let report = engine.apply_lifecycle(Utc::now() + Duration::days(2)).await.unwrap();
+    // The oldest version of "a" and the marker hiding nothing go
+    assert_eq!(report.removed_versions, 2);
+    assert!(engine.get_object_record("docs", "a", Some(oldest.version_id)).await.unwrap().is_none());
+    assert!(engine.get_object_record("docs", "a", Some(older.version_id)).await.unwrap().is_some());
+    assert!(engine.get_object_record("docs", "a", Some(current.version_id)).await.unwrap().is_some());
+    let versions = engine.list_object_versions("docs", Some("gone"), None, None, None, 1000).await.unwrap();
+    assert!(versions.versions.is_empty() && versions.delete_markers.is_empty());
+}
+
+#[tokio::test]
+async fn lifecycle_respects_object_lock() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket_with(Bucket::new("vault".to_string(), None, None).with_object_lock()).await.unwrap();
+
+    let retention = Retention { mode: RetentionMode::Compliance, retain_until: Utc::now() + Duration::days(30) };
+    let staged = engine.stage_data(&b"data"[..]).await.unwrap();
+    let locked = engine.put_staged_object(
+        "vault", "a", staged, ObjectAttributes {
+            lock: ObjectLock { retention: Some(retention), legal_hold: false },
+            ..Default::default()
+        },
+    ).await.unwrap();
+    put(&engine, "vault", "a", b"data").await;
+
+    let mut cleanup = rule("");
+    cleanup.noncurrent_version_expiration = Some(NoncurrentVersionExpiration {
+        noncurrent_days: 1,
+        newer_noncurrent_versions: None,
+    });
+    engine.set_bucket_lifecycle("vault", Some(LifecycleConfiguration { rules: vec![cleanup] })).await.unwrap();
+
+    let report = engine.apply_lifecycle(Utc::now() + Duration::days(2)).await.unwrap();
+    assert_eq!(report.skipped_locked, 1);
+    assert!(engine.get_object_record("vault", "a", Some(locked.version_id)).await.unwrap().is_some());
+
+    assert!(engine.set_bucket_lifecycle("vault", Some(LifecycleConfiguration { rules: vec![rule("")] })).await.is_err());
+}
+
+#[tokio::test]
+async fn periods_beyond_the_limit_never_come_due() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+
+    let mut forever = rule("");
+    forever.expiration = Some(Expiration { days: Some(u32::MAX), ..Default::default() });
+    forever.abort_incomplete_multipart_upload_days = Some(u32::MAX);
+    let lifecycle = LifecycleConfiguration { rules: vec![forever] };
+    assert!(lifecycle.validate().is_err());
+
+    // A configuration stored before the limit existed is skipped, not fatal
+    let mut bucket = Bucket::new("old".to_string(), None, None);
+    bucket.lifecycle = Some(lifecycle);
+    engine.create_bucket_with(bucket).await.unwrap();
+    put(&engine, "old", "a", b"data").await;
+    engine.create_multipart_upload("old", "big", ObjectAttributes::default(), None).await.unwrap();
+
+    let report = engine.apply_lifecycle(Utc::now() + Duration::days(365)).await.unwrap();
+    assert_eq!(report, storage::LifecycleReport::default());
+    assert!(engine.get_object_record("old", "a", None).await.unwrap().is_some());
+
+    let mut limit = rule("");
+    limit.expiration = Some(Expiration { days: Some(storage::MAX_LIFECYCLE_DAYS), ..Default::default() });
+    assert!(LifecycleConfiguration { rules: vec![limit] }.validate().is_ok());
+}
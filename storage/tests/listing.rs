@@ -0,0 +1,65 @@
+mod common;
+
+use common::{engine, put};
+use storage::StorageEngine;
+
+/// Walks every page of a listing, `max_keys` entries at a time, returning
+/// the keys, the common prefixes and the number of pages.
+async fn list_all(engine: &StorageEngine, prefix: Option<&str>, delimiter: Option<&str>, max_keys: usize)
+    -> (Vec<String>, Vec<String>, usize)
+{
+    let (mut keys, mut prefixes, mut pages) = (Vec::new(), Vec::new(), 0);
+    let mut after = None;
+    loop {
+        let page = engine.list_objects("logs", prefix, delimiter, after.as_deref(), max_keys, &[]).await.unwrap();
+        assert!(page.objects.len() + page.common_prefixes.len() <= max_keys);
+        pages += 1;
+        keys.extend(page.objects.into_iter().map(|object| object.key));
+        prefixes.extend(page.common_prefixes);
+        if !page.is_truncated {
+            assert!(page.next_marker.is_none());
+            return (keys, prefixes, pages);
+        }
+        after = Some(page.next_marker.expect("truncated pages have a marker"));
+    }
+}
+
+#[tokio::test]
+async fn pages_continue_after_the_marker() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "logs").await;
+    let mut expected = Vec::new();
+    for i in 0..7 {
+        let key = format!("day-{}", i);
+        put(&engine, "logs", &key, b"entry").await;
+        expected.push(key);
+    }
+
+    let (keys, prefixes, pages) = list_all(&engine, None, None, 3).await;
+    assert_eq!((keys, prefixes, pages), (expected.clone(), vec![], 3));
+
+    let (keys, _, pages) = list_all(&engine, None, None, 7).await;
+    assert_eq!((keys, pages), (expected, 1));
+
+    let page = engine.list_objects("logs", None, None, Some("day-4"), 10, &[]).await.unwrap();
+    let keys: Vec<_> = page.objects.into_iter().map(|object| object.key).collect();
+    assert_eq!(keys, ["day-5", "day-6"]);
+}
+
+#[tokio::test]
+async fn common_prefixes_are_paged_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "logs").await;
+    for key in ["a/1", "a/2", "a/3", "b/1", "b/2", "c", "d/1", "e"] {
+        put(&engine, "logs", key, b"entry").await;
+    }
+
+    for max_keys in 1..=6 {
+        let (keys, prefixes, _) = list_all(&engine, None, Some("/"), max_keys).await;
+        assert_eq!(keys, ["c", "e"], "max_keys {}", max_keys);
+        assert_eq!(prefixes, ["a/", "b/", "d/"], "max_keys {}", max_keys);
+    }
+
+    let (keys, prefixes, pages) = list_all(&engine, Some("a/"), Some("/"), 2).await;
+    assert_eq!((keys, prefixes, pages), (vec!["a/1".to_string(), "a/2".to_string(), "a/3".to_string()], vec![], 2));
+}
@@ -0,0 +1,265 @@
+mod common;
+
+use common::engine;
+use storage::{
+    ObjectAttributes, StorageEngine, StorageError, VersioningStatus, MIN_PART_SIZE,
+};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+fn data(size: usize, seed: u8) -> Vec<u8> {
+    (0..size).map(|i| (i % 251) as u8 ^ seed).collect()
+}
+
+async fn create(engine: &StorageEngine, key: &str) -> String {
+    engine.create_multipart_upload("uploads", key, ObjectAttributes::default(), None).await.unwrap().upload_id
+}
+
+/// Uploads `data` as `part_number` and returns the `(part number, etag)`
+/// pair to complete with.
+async fn upload(engine: &StorageEngine, upload_id: &str, part_number: u32, data: &[u8]) -> (u32, String) {
+    let staged = engine.stage_data(data).await.unwrap();
+    (part_number, engine.upload_part(upload_id, part_number, staged).await.unwrap().etag)
+}
+
+async fn read(engine: &StorageEngine, key: &str) -> Vec<u8> {
+    let record = engine.get_object_record("uploads", key, None).await.unwrap().unwrap();
+    let mut output = Vec::new();
+    engine.read_range(&record, 0, record.metadata.size, None).await.unwrap().read_to_end(&mut output).await.unwrap();
+    output
+}
+
+#[tokio::test]
+async fn parts_uploaded_out_of_order_are_assembled_by_number() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload_id = create(&engine, "big").await;
+
+    let (first, second, third) = (data(MIN_PART_SIZE as usize, 1), data(MIN_PART_SIZE as usize, 2), data(10, 3));
+    let part3 = upload(&engine, &upload_id, 3, &third).await;
+    let part1 = upload(&engine, &upload_id, 1, &first).await;
+    let part2 = upload(&engine, &upload_id, 2, &second).await;
+
+    // Parts must be listed in ascending order, and every one must exist
+    let result = engine.complete_multipart_upload(&upload_id, &[part2.clone(), part1.clone(), part3.clone()], None).await;
+    assert!(matches!(result, Err(StorageError::InvalidPartOrder(_))));
+    let result = engine.complete_multipart_upload(&upload_id, &[part1.clone(), part1.clone()], None).await;
+    assert!(matches!(result, Err(StorageError::InvalidPartOrder(_))));
+    let result = engine.complete_multipart_upload(&upload_id, &[part1.clone(), (4, part3.1.clone())], None).await;
+    assert!(matches!(result, Err(StorageError::InvalidPart(_))));
+    assert!(matches!(engine.complete_multipart_upload(&upload_id, &[], None).await, Err(StorageError::InvalidPart(_))));
+
+    // Parts may be skipped
+    engine.complete_multipart_upload(&upload_id, &[part1, part3], None).await.unwrap();
+    assert_eq!(read(&engine, "big").await, [first, third].concat());
+}
+
+#[tokio::test]
+async fn every_part_but_the_last_must_be_large_enough() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload_id = create(&engine, "big").await;
+
+    let small = upload(&engine, &upload_id, 1, &data(MIN_PART_SIZE as usize - 1, 1)).await;
+    let last = upload(&engine, &upload_id, 2, &data(10, 2)).await;
+    let result = engine.complete_multipart_upload(&upload_id, &[small.clone(), last], None).await;
+    assert!(matches!(result, Err(StorageError::EntityTooSmall(_))));
+
+    // A single small part is a valid upload
+    engine.complete_multipart_upload(&upload_id, &[small], None).await.unwrap();
+    assert_eq!(read(&engine, "big").await.len(), MIN_PART_SIZE as usize - 1);
+}
+
+#[tokio::test]
+async fn completing_checks_the_etag_of_each_part() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload_id = create(&engine, "big").await;
+
+    let (number, etag) = upload(&engine, &upload_id, 1, b"first attempt").await;
+    let result = engine.complete_multipart_upload(&upload_id, &[(number, "\"0123456789abcdef\"".to_string())], None).await;
+    assert!(matches!(result, Err(StorageError::InvalidPart(_))));
+
+    // Uploading a part again replaces it, and its old ETag no longer matches
+    let replaced = upload(&engine, &upload_id, 1, b"second attempt").await;
+    let result = engine.complete_multipart_upload(&upload_id, &[(number, etag.clone())], None).await;
+    assert!(matches!(result, Err(StorageError::InvalidPart(_))));
+
+    // ETags match with or without their quotes
+    let unquoted = (replaced.0, replaced.1.trim_matches('"').to_string());
+    engine.complete_multipart_upload(&upload_id, &[unquoted], None).await.unwrap();
+    assert_eq!(read(&engine, "big").await, b"second attempt");
+}
+
+#[tokio::test]
+async fn aborting_removes_the_upload_and_its_parts() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload_id = create(&engine, "big").await;
+    let part = upload(&engine, &upload_id, 1, b"part").await;
+
+    let upload_dir = dir.path().join("multipart").join(&upload_id);
+    assert!(upload_dir.join("1").is_file());
+    let used = engine.get_stats().await.used_space_bytes;
+
+    engine.abort_multipart_upload(&upload_id).await.unwrap();
+    assert!(!upload_dir.exists());
+    assert_eq!(engine.get_stats().await.used_space_bytes, used - 4);
+    assert!(engine.get_multipart_upload(&upload_id).await.unwrap().is_none());
+    assert!(engine.list_parts(&upload_id, 0, 1000).await.unwrap().parts.is_empty());
+
+    assert!(matches!(engine.abort_multipart_upload(&upload_id).await, Err(StorageError::NoSuchUpload(_))));
+    let result = engine.complete_multipart_upload(&upload_id, &[part], None).await;
+    assert!(matches!(result, Err(StorageError::NoSuchUpload(_))));
+    assert!(engine.get_object_record("uploads", "big", None).await.unwrap().is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn parts_uploaded_concurrently_are_all_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = Arc::new(engine(&dir, "uploads").await);
+    let upload_id = create(&engine, "big").await;
+
+    // Readers run alongside the writers and must never see a broken table
+    let mut tasks = Vec::new();
+    for part_number in 1..=10u32 {
+        let (engine, upload_id) = (engine.clone(), upload_id.clone());
+        tasks.push(tokio::spawn(async move {
+            let part = upload(&engine, &upload_id, part_number, &data(100, part_number as u8)).await;
+            engine.get_multipart_upload(&upload_id).await.unwrap().unwrap();
+            engine.list_parts(&upload_id, 0, 1000).await.unwrap();
+            engine.list_multipart_uploads("uploads", None, None, None, 1000).await.unwrap();
+            part
+        }));
+    }
+    let mut uploaded = Vec::new();
+    for task in tasks {
+        uploaded.push(task.await.unwrap());
+    }
+
+    let page = engine.list_parts(&upload_id, 0, 1000).await.unwrap();
+    let listed: Vec<_> = page.parts.iter().map(|p| (p.part_number, p.etag.clone())).collect();
+    assert_eq!(listed, uploaded);
+}
+
+#[tokio::test]
+async fn a_part_is_not_recorded_for_an_aborted_upload() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload_id = create(&engine, "big").await;
+    engine.abort_multipart_upload(&upload_id).await.unwrap();
+
+    let staged = engine.stage_data(&b"late part"[..]).await.unwrap();
+    let result = engine.upload_part(&upload_id, 1, staged).await;
+    assert!(matches!(result, Err(StorageError::NoSuchUpload(_))));
+    assert!(engine.list_parts(&upload_id, 0, 1000).await.unwrap().parts.is_empty());
+}
+
+#[tokio::test]
+async fn parts_are_listed_in_pages() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload_id = create(&engine, "big").await;
+    for part_number in [5, 1, 3, 2, 4] {
+        upload(&engine, &upload_id, part_number, b"part").await;
+    }
+
+    let page = engine.list_parts(&upload_id, 0, 2).await.unwrap();
+    assert_eq!(page.parts.iter().map(|p| p.part_number).collect::<Vec<_>>(), [1, 2]);
+    assert!(page.is_truncated);
+    assert_eq!(page.next_part_number_marker, Some(2));
+
+    let page = engine.list_parts(&upload_id, 2, 2).await.unwrap();
+    assert_eq!(page.parts.iter().map(|p| p.part_number).collect::<Vec<_>>(), [3, 4]);
+    assert_eq!(page.next_part_number_marker, Some(4));
+
+    let page = engine.list_parts(&upload_id, 4, 2).await.unwrap();
+    assert_eq!(page.parts.iter().map(|p| p.part_number).collect::<Vec<_>>(), [5]);
+    assert!(!page.is_truncated);
+    assert_eq!(page.next_part_number_marker, None);
+
+    let page = engine.list_parts(&upload_id, 0, 1000).await.unwrap();
+    assert_eq!(page.parts.len(), 5);
+    assert!(!page.is_truncated);
+}
+
+#[tokio::test]
+async fn uploads_are_listed_in_pages_by_key_then_age() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let b1 = create(&engine, "b").await;
+    let a1 = create(&engine, "a").await;
+    let b2 = create(&engine, "b").await;
+    let c1 = create(&engine, "c").await;
+    create(&engine, "other/d").await;
+
+    let ids = |page: &storage::ListUploadsPage| page.uploads.iter().map(|u| u.upload_id.clone()).collect::<Vec<_>>();
+
+    let page = engine.list_multipart_uploads("uploads", None, None, None, 2).await.unwrap();
+    assert_eq!(ids(&page), [a1.clone(), b1.clone()]);
+    assert!(page.is_truncated);
+    assert_eq!(page.next_key_marker.as_deref(), Some("b"));
+    assert_eq!(page.next_upload_id_marker.as_deref(), Some(b1.as_str()));
+
+    // Continuing within a key picks up after the marked upload
+    let page = engine.list_multipart_uploads("uploads", None, Some("b"), Some(&b1), 2).await.unwrap();
+    assert_eq!(ids(&page), [b2.clone(), c1.clone()]);
+    assert!(page.is_truncated);
+
+    // A key marker alone skips every upload of that key
+    let page = engine.list_multipart_uploads("uploads", None, Some("b"), None, 1000).await.unwrap();
+    assert_eq!(ids(&page).len(), 2);
+    assert_eq!(ids(&page)[0], c1);
+    assert!(!page.is_truncated);
+    assert_eq!(page.next_key_marker, None);
+
+    let page = engine.list_multipart_uploads("uploads", Some("other/"), None, None, 1000).await.unwrap();
+    assert_eq!(page.uploads.len(), 1);
+    assert_eq!(page.uploads[0].key, "other/d");
+}
+
+#[tokio::test]
+async fn an_upload_completes_into_exactly_one_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    engine.set_bucket_versioning("uploads", VersioningStatus::Enabled).await.unwrap();
+    let upload_id = create(&engine, "big").await;
+    let part = upload(&engine, &upload_id, 1, b"only part").await;
+
+    // Of two concurrent completes, one wins and the other finds the upload
+    // claimed or already gone
+    let parts = [part];
+    let (first, second) = tokio::join!(
+        engine.complete_multipart_upload(&upload_id, &parts, None),
+        engine.complete_multipart_upload(&upload_id, &parts, None),
+    );
+    assert_eq!(first.is_ok() as u32 + second.is_ok() as u32, 1);
+    for result in [first, second] {
+        assert!(matches!(result, Ok(_) | Err(StorageError::NoSuchUpload(_) | StorageError::InvalidRequest(_))));
+    }
+
+    let result = engine.complete_multipart_upload(&upload_id, &parts, None).await;
+    assert!(matches!(result, Err(StorageError::NoSuchUpload(_))));
+    let versions = engine.list_object_versions("uploads", None, None, None, None, 1000).await.unwrap();
+    assert_eq!(versions.versions.len(), 1);
+    assert!(!dir.path().join("multipart").join(&upload_id).exists());
+}
+
+#[tokio::test]
+async fn a_complete_and_an_abort_do_not_both_succeed() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "uploads").await;
+    let upload_id = create(&engine, "big").await;
+    let parts = [upload(&engine, &upload_id, 1, &data(MIN_PART_SIZE as usize, 1)).await];
+
+    let (completed, aborted) = tokio::join!(
+        engine.complete_multipart_upload(&upload_id, &parts, None),
+        engine.abort_multipart_upload(&upload_id),
+    );
+    assert_eq!(completed.is_ok() as u32 + aborted.is_ok() as u32, 1);
+
+    let stored = engine.get_object_record("uploads", "big", None).await.unwrap();
+    assert_eq!(stored.is_some(), completed.is_ok());
+    assert!(engine.get_multipart_upload(&upload_id).await.unwrap().is_none());
+    assert!(!dir.path().join("multipart").join(&upload_id).exists());
+}
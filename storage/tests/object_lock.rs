@@ -0,0 +1,115 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::empty_engine;
+use storage::{
+    Bucket, DefaultRetention, ObjectAttributes, ObjectLock, ObjectLockConfiguration, Retention, RetentionMode, StorageEngine,
+    StorageError, VersioningStatus,
+};
+
+async fn locked_engine(dir: &tempfile::TempDir) -> StorageEngine {
+    let engine = empty_engine(dir).await;
+    engine.create_bucket_with(Bucket::new("vault".to_string(), None, None).with_object_lock()).await.unwrap();
+    engine
+}
+
+async fn put(engine: &StorageEngine, lock: ObjectLock) -> storage::ObjectReference {
+    let staged = engine.stage_data(&b"record"[..]).await.unwrap();
+    engine.put_staged_object("vault", "ledger", staged, ObjectAttributes { lock, ..Default::default() }).await.unwrap()
+}
+
+fn retention(mode: RetentionMode, days: i64) -> Option<Retention> {
+    Some(Retention { mode, retain_until: Utc::now() + Duration::days(days) })
+}
+
+#[tokio::test]
+async fn retained_versions_cannot_be_deleted() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = locked_engine(&dir).await;
+    assert!(engine.set_bucket_versioning("vault", VersioningStatus::Suspended).await.is_err());
+
+    let governed = put(&engine, ObjectLock { retention: retention(RetentionMode::Governance, 1), legal_hold: false }).await;
+    let compliant = put(&engine, ObjectLock { retention: retention(RetentionMode::Compliance, 1), legal_hold: false }).await;
+
+    // A plain delete only adds a marker, which is always allowed
+    assert!(engine.delete_object("vault", "ledger", None, false).await.unwrap().delete_marker);
+
+    let result = engine.delete_object("vault", "ledger", Some(governed.version_id), false).await;
+    assert!(matches!(result, Err(StorageError::ObjectLocked(_))));
+    engine.delete_object("vault", "ledger", Some(governed.version_id), true).await.unwrap();
+
+    let result = engine.delete_object("vault", "ledger", Some(compliant.version_id), true).await;
+    assert!(matches!(result, Err(StorageError::ObjectLocked(_))));
+
+    // Compliance retention can be extended but never shortened
+    let shorter = retention(RetentionMode::Compliance, 0);
+    let result = engine.set_object_retention("vault", "ledger", Some(compliant.version_id), shorter, true).await;
+    assert!(result.is_err());
+    let longer = retention(RetentionMode::Compliance, 30);
+    engine.set_object_retention("vault", "ledger", Some(compliant.version_id), longer, false).await.unwrap();
+}
+
+#[tokio::test]
+async fn legal_hold_blocks_deletes_until_lifted() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = locked_engine(&dir).await;
+
+    let held = put(&engine, ObjectLock { retention: None, legal_hold: true }).await;
+    let result = engine.delete_object("vault", "ledger", Some(held.version_id), true).await;
+    assert!(matches!(result, Err(StorageError::ObjectLocked(_))));
+
+    engine.set_object_legal_hold("vault", "ledger", Some(held.version_id), false).await.unwrap();
+    engine.delete_object("vault", "ledger", Some(held.version_id), false).await.unwrap();
+    assert!(engine.get_object_record("vault", "ledger", None).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn default_retention_applies_to_new_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = locked_engine(&dir).await;
+    engine.set_object_lock_configuration("vault", ObjectLockConfiguration {
+        default_retention: Some(DefaultRetention { mode: RetentionMode::Governance, days: Some(2), years: None }),
+    }).await.unwrap();
+
+    let version = put(&engine, ObjectLock::default()).await;
+    let record = engine.get_object_record("vault", "ledger", Some(version.version_id)).await.unwrap().unwrap();
+    let retention = record.metadata.lock.retention.unwrap();
+    assert_eq!(retention.mode, RetentionMode::Governance);
+    assert_eq!(retention.retain_until, record.metadata.created_at + Duration::days(2));
+
+    // Object Lock settings are refused on buckets without Object Lock
+    engine.create_bucket("plain", None, None).await.unwrap();
+    let staged = engine.stage_data(&b"record"[..]).await.unwrap();
+    let lock = ObjectLock { retention: None, legal_hold: true };
+    let result = engine.put_staged_object("plain", "ledger", staged, ObjectAttributes { lock, ..Default::default() }).await;
+    assert!(matches!(result, Err(StorageError::InvalidRequest(_))));
+    let result = engine.put_object("plain", "ledger", bytes::Bytes::from_static(b"record"), None, Default::default()).await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn retention_periods_are_bounded() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = locked_engine(&dir).await;
+
+    for (days, years) in [(Some(36501), None), (None, Some(101)), (None, Some(u32::MAX)), (Some(1), Some(1))] {
+        let config = ObjectLockConfiguration {
+            default_retention: Some(DefaultRetention { mode: RetentionMode::Compliance, days, years }),
+        };
+        let result = engine.set_object_lock_configuration("vault", config).await;
+        assert!(matches!(result, Err(StorageError::InvalidArgument(_))), "{:?} {:?}", days, years);
+    }
+    engine.set_object_lock_configuration("vault", ObjectLockConfiguration {
+        default_retention: Some(DefaultRetention { mode: RetentionMode::Governance, days: None, years: Some(100) }),
+    }).await.unwrap();
+    put(&engine, ObjectLock::default()).await;
+
+    let staged = engine.stage_data(&b"record"[..]).await.unwrap();
+    let lock = ObjectLock { retention: retention(RetentionMode::Compliance, 36600), legal_hold: false };
+    let result = engine.put_staged_object("vault", "ledger", staged, ObjectAttributes { lock, ..Default::default() }).await;
+    assert!(matches!(result, Err(StorageError::InvalidArgument(_))));
+
+    // A default stored before the bounds existed saturates instead of panicking
+    let forever = DefaultRetention { mode: RetentionMode::Compliance, days: None, years: Some(u32::MAX) };
+    assert_eq!(forever.retention_from(Utc::now()).retain_until, chrono::DateTime::<Utc>::MAX_UTC);
+}
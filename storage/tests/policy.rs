@@ -0,0 +1,123 @@
+mod common;
+
+use common::empty_engine;
+use std::collections::HashMap;
+use storage::{BucketPolicy, PolicyEffect, PolicyRequest, StorageError};
+
+fn request<'a>(principals: &[&str], action: &'a str, resource: &str, conditions: &[(&str, &str)]) -> PolicyRequest<'a> {
+    PolicyRequest {
+        principals: principals.iter().map(|p| p.to_string()).collect(),
+        action,
+        resource: resource.to_string(),
+        conditions: conditions.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>(),
+    }
+}
+
+#[test]
+fn explicit_deny_overrides_allow() {
+    let policy = BucketPolicy::parse(r#"{
+        "Version": "2012-10-17",
+        "Statement": [
+            {"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "arn:aws:s3:::photos/*"},
+            {"Effect": "Deny", "Principal": "*", "Action": "s3:DeleteObject", "Resource": "arn:aws:s3:::photos/keep/*"}
+        ]
+    }"#).unwrap();
+
+    let get = request(&[], "s3:GetObject", "arn:aws:s3:::photos/keep/a.jpg", &[]);
+    assert_eq!(policy.evaluate(&get), Some(PolicyEffect::Allow));
+
+    let delete = request(&["alice"], "s3:DeleteObject", "arn:aws:s3:::photos/keep/a.jpg", &[]);
+    assert_eq!(policy.evaluate(&delete), Some(PolicyEffect::Deny));
+
+    let other = request(&["alice"], "s3:DeleteObject", "arn:aws:s3:::photos/tmp/a.jpg", &[]);
+    assert_eq!(policy.evaluate(&other), Some(PolicyEffect::Allow));
+
+    let bucket = request(&["alice"], "s3:ListBucket", "arn:aws:s3:::photos", &[]);
+    assert_eq!(policy.evaluate(&bucket), None);
+}
+
+#[test]
+fn principals_and_conditions_narrow_statements() {
+    let policy = BucketPolicy::parse(r#"{
+        "Statement": {
+            "Effect": "Allow",
+            "Principal": {"AWS": ["alice", "arn:aws:iam::bob:root"]},
+            "Action": ["s3:ListBucket"],
+            "Resource": "arn:aws:s3:::photos",
+            "Condition": {
+                "IpAddress": {"aws:SourceIp": "10.0.0.0/8"},
+                "StringLike": {"s3:prefix": ["public/*", ""]},
+                "BoolIfExists": {"aws:SecureTransport": false}
+            }
+        }
+    }"#).unwrap();
+
+    let list = |principal: &str, ip: &str, prefix: &str| request(
+        &[principal],
+        "s3:listbucket",
+        "arn:aws:s3:::photos",
+        &[("aws:sourceip", ip), ("s3:prefix", prefix)],
+    );
+
+    assert_eq!(policy.evaluate(&list("alice", "10.1.2.3", "public/cats")), Some(PolicyEffect::Allow));
+    assert_eq!(policy.evaluate(&list("arn:aws:iam::bob:root", "10.1.2.3", "")), Some(PolicyEffect::Allow));
+    assert_eq!(policy.evaluate(&list("carol", "10.1.2.3", "public/cats")), None);
+    assert_eq!(policy.evaluate(&list("alice", "192.168.0.1", "public/cats")), None);
+    assert_eq!(policy.evaluate(&list("alice", "10.1.2.3", "private/")), None);
+
+    let insecure = request(
+        &["alice"],
+        "s3:ListBucket",
+        "arn:aws:s3:::photos",
+        &[("aws:sourceip", "10.0.0.1"), ("s3:prefix", ""), ("aws:securetransport", "true")],
+    );
+    assert_eq!(policy.evaluate(&insecure), None);
+}
+
+#[test]
+fn malformed_policies_are_rejected() {
+    let malformed = [
+        "not json",
+        r#"{"Statement": []}"#,
+        r#"{"Version": "2020-01-01", "Statement": {"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "arn:aws:s3:::b"}}"#,
+        r#"{"Statement": {"Effect": "Maybe", "Principal": "*", "Action": "s3:*", "Resource": "arn:aws:s3:::b"}}"#,
+        r#"{"Statement": {"Effect": "Allow", "Action": "s3:*", "Resource": "arn:aws:s3:::b"}}"#,
+        r#"{"Statement": {"Effect": "Allow", "Principal": "*", "Action": "ec2:*", "Resource": "arn:aws:s3:::b"}}"#,
+        r#"{"Statement": {"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "b"}}"#,
+        r#"{"Statement": {"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "arn:aws:s3:::b",
+            "Condition": {"StringSorta": {"s3:prefix": "x"}}}}"#,
+        r#"{"Statement": {"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "arn:aws:s3:::b",
+            "Condition": {"IpAddress": {"aws:SourceIp": "10.0.0.0/40"}}}}"#,
+    ];
+
+    for document in malformed {
+        assert!(
+            matches!(BucketPolicy::parse(document), Err(StorageError::MalformedPolicy(_))),
+            "accepted {}",
+            document
+        );
+    }
+}
+
+#[tokio::test]
+async fn policies_are_stored_with_the_bucket() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = empty_engine(&dir).await;
+    engine.create_bucket("photos", None, None).await.unwrap();
+
+    let document = r#"{"Statement": {"Effect": "Deny", "Principal": "*", "Action": "s3:GetObject", "Resource": "arn:aws:s3:::photos/*"}}"#;
+    engine.set_bucket_policy("photos", Some(BucketPolicy::parse(document).unwrap())).await.unwrap();
+
+    let bucket = engine.get_bucket("photos").await.unwrap().unwrap();
+    assert_eq!(bucket.policy.unwrap().document(), document);
+
+    let elsewhere = r#"{"Statement": {"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "arn:aws:s3:::other/*"}}"#;
+    let result = engine.set_bucket_policy("photos", Some(BucketPolicy::parse(elsewhere).unwrap())).await;
+    assert!(matches!(result, Err(StorageError::MalformedPolicy(_))));
+
+    engine.set_bucket_policy("photos", None).await.unwrap();
+    assert!(engine.get_bucket("photos").await.unwrap().unwrap().policy.is_none());
+
+    let result = engine.set_bucket_policy("missing", None).await;
+    assert!(matches!(result, Err(StorageError::NoSuchBucket(_))));
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use common::{data_files, engine};
+use std::path::Path;
+use storage::{ObjectAttributes, StorageEngine, StorageError};
+use tokio::io::AsyncReadExt;
+
+fn body(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn staging_files(root: &Path) -> usize {
+    std::fs::read_dir(root.join("tmp")).map_or(0, |entries| entries.count())
+}
+
+#[tokio::test]
+async fn bodies_are_streamed_to_disk_and_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "media").await;
+    let data = body(3 * 1024 * 1024 + 17);
+
+    // Arrives in two pieces, as a request body would
+    let (head, tail) = data.split_at(1000);
+    let staged = engine.stage_data(head.chain(tail)).await.unwrap();
+    assert_eq!(staged.size(), data.len() as u64);
+    let sha256: String = Sha256::digest(&data).iter().map(|b| format!("{:02x}", b)).collect();
+    assert_eq!(staged.checksum().sha256, sha256);
+    engine.put_staged_object("media", "video.mp4", staged, ObjectAttributes::default()).await.unwrap();
+    assert_eq!(staging_files(dir.path()), 0);
+    assert_eq!(data_files(dir.path()).len(), 1);
+
+    let (record, mut reader) = engine.open_object("media", "video.mp4", None, None).await.unwrap().unwrap();
+    assert_eq!(record.metadata.size, data.len() as u64);
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).await.unwrap();
+    assert!(read == data);
+
+    let mut reader = engine.read_range(&record, 2 * 1024 * 1024, 100, None).await.unwrap();
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).await.unwrap();
+    assert_eq!(read, &data[2 * 1024 * 1024..2 * 1024 * 1024 + 100]);
+}
+
+#[tokio::test]
+async fn uncommitted_bodies_are_discarded() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "media").await;
+
+    let staged = engine.stage_data(&body(1024)[..]).await.unwrap();
+    assert_eq!(staging_files(dir.path()), 1);
+    drop(staged);
+    assert_eq!(staging_files(dir.path()), 0);
+    assert!(data_files(dir.path()).is_empty());
+}
+
+#[tokio::test]
+async fn bodies_beyond_the_free_space_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = StorageEngine::new(dir.path().to_str().unwrap(), 4096).await.unwrap();
+    engine.create_bucket("media", None, None).await.unwrap();
+
+    let result = engine.stage_data(&body(4097)[..]).await;
+    assert!(matches!(result, Err(StorageError::InsufficientSpace(_))));
+    assert_eq!(staging_files(dir.path()), 0);
+
+    let staged = engine.stage_data(&body(4096)[..]).await.unwrap();
+    engine.put_staged_object("media", "full", staged, ObjectAttributes::default()).await.unwrap();
+    let result = engine.stage_data(&body(1)[..]).await;
+    assert!(matches!(result, Err(StorageError::InsufficientSpace(_))));
+}
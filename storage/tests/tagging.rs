@@ -0,0 +1,96 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::engine;
+use storage::{
+    Expiration, LifecycleConfiguration, LifecycleFilter, LifecycleRule, ObjectAttributes, StorageEngine,
+    StorageError, TagSet,
+};
+
+fn tags(pairs: &[(&str, &str)]) -> TagSet {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+async fn put(engine: &StorageEngine, key: &str, tags: TagSet) -> storage::ObjectReference {
+    let staged = engine.stage_data(&b"data"[..]).await.unwrap();
+    engine.put_staged_object("photos", key, staged, ObjectAttributes { tags, ..Default::default() }).await.unwrap()
+}
+
+#[tokio::test]
+async fn tags_are_stored_with_the_version_and_can_be_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "photos").await;
+    put(&engine, "cat.jpg", tags(&[("team", "ops"), ("env", "prod")])).await;
+
+    let record = engine.get_object_record("photos", "cat.jpg", None).await.unwrap().unwrap();
+    assert_eq!(record.metadata.tags, tags(&[("team", "ops"), ("env", "prod")]));
+
+    engine.set_object_tagging("photos", "cat.jpg", None, tags(&[("env", "dev")])).await.unwrap().unwrap();
+    let record = engine.get_object_record("photos", "cat.jpg", None).await.unwrap().unwrap();
+    assert_eq!(record.metadata.tags, tags(&[("env", "dev")]));
+
+    engine.set_object_tagging("photos", "cat.jpg", None, TagSet::new()).await.unwrap().unwrap();
+    let record = engine.get_object_record("photos", "cat.jpg", None).await.unwrap().unwrap();
+    assert!(record.metadata.tags.is_empty());
+
+    assert!(engine.set_object_tagging("photos", "missing", None, TagSet::new()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn tag_limits_are_enforced() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "photos").await;
+    put(&engine, "cat.jpg", TagSet::new()).await;
+
+    let too_many: TagSet = (0..11).map(|i| (format!("k{}", i), "v".to_string())).collect();
+    let long_key = "k".repeat(129);
+    let long_value = "v".repeat(257);
+    let invalid = [
+        too_many,
+        tags(&[("a", "1"), ("a", "2")]),
+        tags(&[(long_key.as_str(), "v")]),
+        tags(&[("k", long_value.as_str())]),
+        tags(&[("", "v")]),
+        tags(&[("aws:reserved", "v")]),
+        tags(&[("k", "semi;colon")]),
+    ];
+    for tags in invalid {
+        let result = engine.set_object_tagging("photos", "cat.jpg", None, tags).await;
+        assert!(matches!(result, Err(StorageError::InvalidTag(_))), "{:?}", result);
+    }
+
+    let limit: TagSet = (0..10).map(|i| (format!("k{}", i), "v".repeat(256))).collect();
+    engine.set_object_tagging("photos", "cat.jpg", None, limit).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn listing_and_lifecycle_filter_by_tag() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "photos").await;
+    put(&engine, "a", tags(&[("env", "prod"), ("team", "ops")])).await;
+    put(&engine, "b", tags(&[("env", "dev")])).await;
+    put(&engine, "c", TagSet::new()).await;
+
+    let keys = |page: storage::ListObjectsPage| page.objects.into_iter().map(|o| o.key).collect::<Vec<_>>();
+    let page = engine.list_objects("photos", None, None, None, 1000, &tags(&[("env", "prod")])).await.unwrap();
+    assert_eq!(keys(page), vec!["a"]);
+    let page = engine.list_objects("photos", None, None, None, 1000, &tags(&[("env", "prod"), ("team", "dev")])).await.unwrap();
+    assert!(keys(page).is_empty());
+    let page = engine.list_objects("photos", None, None, None, 1000, &[]).await.unwrap();
+    assert_eq!(keys(page), vec!["a", "b", "c"]);
+
+    let rule = LifecycleRule {
+        id: None,
+        enabled: true,
+        filter: LifecycleFilter { prefix: String::new(), tags: tags(&[("env", "dev")]) },
+        expiration: Some(Expiration { days: Some(1), ..Default::default() }),
+        noncurrent_version_expiration: None,
+        abort_incomplete_multipart_upload_days: None,
+    };
+    engine.set_bucket_lifecycle("photos", Some(LifecycleConfiguration { rules: vec![rule] })).await.unwrap();
+
+    let report = engine.apply_lifecycle(Utc::now() + Duration::days(2)).await.unwrap();
+    assert_eq!(report.expired_objects, 1);
+    let page = engine.list_objects("photos", None, None, None, 1000, &[]).await.unwrap();
+    assert_eq!(keys(page), vec!["a", "c"]);
+}
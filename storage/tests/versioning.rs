@@ -0,0 +1,215 @@
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{StringArray, TimestampMillisecondArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+
+use common::{data_files, empty_engine, engine, put};
+use storage::{ObjectAttributes, ObjectReference, StorageEngine, StorageError, VersioningStatus, NULL_VERSION};
+
+#[tokio::test]
+async fn unversioned_writes_replace_the_null_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "docs").await;
+
+    assert_eq!(put(&engine, "docs", "a", b"one").await.version_id, NULL_VERSION);
+    assert_eq!(put(&engine, "docs", "a", b"two").await.version_id, NULL_VERSION);
+    assert_eq!(data_files(dir.path()).len(), 1);
+
+    let deleted = engine.delete_object("docs", "a", None, false).await.unwrap();
+    assert!(!deleted.delete_marker);
+    assert_eq!(deleted.version_id, None);
+    assert!(engine.get_object_record("docs", "a", None).await.unwrap().is_none());
+    assert_eq!(data_files(dir.path()).len(), 0);
+}
+
+#[tokio::test]
+async fn enabled_versioning_keeps_every_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "docs").await;
+    engine.set_bucket_versioning("docs", VersioningStatus::Enabled).await.unwrap();
+
+    let first = put(&engine, "docs", "a", b"one").await;
+    let second = put(&engine, "docs", "a", b"two").await;
+    assert_ne!(first.version_id, second.version_id);
+
+    let deleted = engine.delete_object("docs", "a", None, false).await.unwrap();
+    assert!(deleted.delete_marker);
+    assert!(engine.get_object_record("docs", "a", None).await.unwrap().is_none());
+    let marker = engine.get_delete_marker("docs", "a", None).await.unwrap().unwrap();
+    assert_eq!(Some(marker.version_id), deleted.version_id);
+
+    let old = engine.get_object_record("docs", "a", Some(first.version_id)).await.unwrap().unwrap();
+    assert_eq!(old.metadata.size, 3);
+
+    // Removing the marker brings the previous version back
+    engine.delete_object("docs", "a", deleted.version_id, false).await.unwrap();
+    let current = engine.get_object_record("docs", "a", None).await.unwrap().unwrap();
+    assert_eq!(current.metadata.version_id, second.version_id);
+}
+
+#[tokio::test]
+async fn suspended_versioning_overwrites_only_the_null_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "docs").await;
+    engine.set_bucket_versioning("docs", VersioningStatus::Enabled).await.unwrap();
+    let kept = put(&engine, "docs", "a", b"kept").await;
+
+    engine.set_bucket_versioning("docs", VersioningStatus::Suspended).await.unwrap();
+    put(&engine, "docs", "a", b"one").await;
+    let files = data_files(dir.path()).len();
+    assert_eq!(put(&engine, "docs", "a", b"two").await.version_id, NULL_VERSION);
+    assert_eq!(data_files(dir.path()).len(), files);
+
+    let deleted = engine.delete_object("docs", "a", None, false).await.unwrap();
+    assert!(deleted.delete_marker);
+    assert_eq!(deleted.version_id, Some(NULL_VERSION));
+    assert_eq!(data_files(dir.path()).len(), files - 1);
+
+    let versions = engine.list_object_versions("docs", None, None, None, None, 1000).await.unwrap();
+    assert_eq!(versions.versions.len(), 1);
+    assert_eq!(versions.versions[0].version_id, kept.version_id);
+    assert_eq!(versions.delete_markers.len(), 1);
+
+    assert!(engine.set_bucket_versioning("docs", VersioningStatus::Unversioned).await.is_err());
+}
+
+#[tokio::test]
+async fn versions_are_listed_by_key_then_newest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "docs").await;
+    engine.set_bucket_versioning("docs", VersioningStatus::Enabled).await.unwrap();
+
+    let b1 = put(&engine, "docs", "b", b"one").await.version_id;
+    let a1 = put(&engine, "docs", "a", b"one").await.version_id;
+    let b2 = put(&engine, "docs", "b", b"two").await.version_id;
+    let a2 = put(&engine, "docs", "a", b"two").await.version_id;
+    let b3 = engine.delete_object("docs", "b", None, false).await.unwrap().version_id.unwrap();
+    let c1 = put(&engine, "docs", "c", b"one").await.version_id;
+
+    let listing = engine.list_object_versions("docs", None, None, None, None, 1000).await.unwrap();
+    let versions: Vec<_> = listing.versions.iter().map(|v| (v.key.as_str(), v.version_id, v.is_latest)).collect();
+    assert_eq!(versions, [("a", a2, true), ("a", a1, false), ("b", b2, false), ("b", b1, false), ("c", c1, true)]);
+    let markers: Vec<_> = listing.delete_markers.iter().map(|m| (m.key.as_str(), m.version_id, m.is_latest)).collect();
+    assert_eq!(markers, [("b", b3, true)]);
+
+    // Paging resumes inside a key from the version marker
+    let expected = [(a2, "a"), (a1, "a"), (b3, "b"), (b2, "b"), (b1, "b"), (c1, "c")];
+    let mut listed = Vec::new();
+    let (mut key_marker, mut version_id_marker) = (None, None);
+    loop {
+        let page = engine.list_object_versions("docs", None, None, key_marker.as_deref(), version_id_marker, 2).await
+            .unwrap();
+        let mut entries: Vec<_> = page.versions.iter().map(|v| (v.version_id, v.key.clone()))
+            .chain(page.delete_markers.iter().map(|m| (m.version_id, m.key.clone())))
+            .collect();
+        assert!(entries.len() <= 2);
+        entries.sort_by_key(|(id, _)| expected.iter().position(|(expected, _)| expected == id));
+        listed.extend(entries);
+        if !page.is_truncated {
+            break;
+        }
+        key_marker = page.next_key_marker;
+        version_id_marker = page.next_version_id_marker;
+    }
+    let expected: Vec<_> = expected.iter().map(|(id, key)| (*id, key.to_string())).collect();
+    assert_eq!(listed, expected);
+}
+
+#[tokio::test]
+async fn concurrent_writes_are_all_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "docs").await;
+    engine.set_bucket_versioning("docs", VersioningStatus::Enabled).await.unwrap();
+
+    tokio::join!(
+        put(&engine, "docs", "a", b"one"),
+        put(&engine, "docs", "a", b"two"),
+        put(&engine, "docs", "b", b"one"),
+        put(&engine, "docs", "c", b"one"),
+    );
+
+    let versions = engine.list_object_versions("docs", None, None, None, None, 1000).await.unwrap();
+    assert_eq!(versions.versions.len(), 4);
+    assert_eq!(data_files(dir.path()).len(), 4);
+}
+
+async fn create(engine: &StorageEngine, key: &str, data: &[u8]) -> storage::Result<ObjectReference> {
+    let staged = engine.stage_data(data).await.unwrap();
+    engine.put_staged_object_if_absent("docs", key, staged, ObjectAttributes::default()).await
+}
+
+#[tokio::test]
+async fn create_only_writes_never_replace_a_current_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = engine(&dir, "docs").await;
+
+    create(&engine, "a", b"one").await.unwrap();
+    // Identical data shares the stored file, which must survive the failure
+    assert!(matches!(create(&engine, "a", b"one").await, Err(StorageError::PreconditionFailed(_))));
+    assert!(matches!(create(&engine, "a", b"two").await, Err(StorageError::PreconditionFailed(_))));
+    assert_eq!(data_files(dir.path()).len(), 1);
+    assert_eq!(engine.get_object("docs", "a", None).await.unwrap().unwrap().data, &b"one"[..]);
+
+    // A delete marker leaves the key free to create again
+    engine.set_bucket_versioning("docs", VersioningStatus::Enabled).await.unwrap();
+    engine.delete_object("docs", "a", None, false).await.unwrap();
+    create(&engine, "a", b"two").await.unwrap();
+
+    // Of two concurrent creates of a new key, exactly one succeeds
+    let (first, second) = tokio::join!(create(&engine, "b", b"three"), create(&engine, "b", b"four"));
+    assert_eq!(first.is_ok() as u32 + second.is_ok() as u32, 1);
+    let versions = engine.list_object_versions("docs", Some("b"), None, None, None, 1000).await.unwrap();
+    assert_eq!(versions.versions.len(), 1);
+    assert_eq!(data_files(dir.path()).len(), 3);
+}
+
+/// Rewrites the buckets table with only the columns it had before
+/// versioning was added, as a node upgraded from then would have it.
+fn write_pre_versioning_buckets(root: &Path, name: &str) {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("created_at", DataType::Timestamp(TimeUnit::Millisecond, None), false),
+        Field::new("region", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(schema.clone(), vec![
+        Arc::new(StringArray::from(vec![name])),
+        Arc::new(TimestampMillisecondArray::from(vec![0])),
+        Arc::new(StringArray::from(vec![storage::DEFAULT_REGION])),
+    ]).unwrap();
+
+    let file = std::fs::File::create(root.join("metadata.db").join("buckets.parquet")).unwrap();
+    let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+#[tokio::test]
+async fn buckets_from_before_versioning_keep_their_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        // Every write used to add a version, as Enabled does now
+        let engine = engine(&dir, "docs").await;
+        engine.set_bucket_versioning("docs", VersioningStatus::Enabled).await.unwrap();
+        put(&engine, "docs", "a", b"one").await;
+        put(&engine, "docs", "a", b"two").await;
+    }
+    write_pre_versioning_buckets(dir.path(), "docs");
+
+    let engine = empty_engine(&dir).await;
+    assert_eq!(engine.get_bucket("docs").await.unwrap().unwrap().versioning, VersioningStatus::Enabled);
+    let third = put(&engine, "docs", "a", b"three").await;
+    assert_ne!(third.version_id, NULL_VERSION);
+
+    let versions = engine.list_object_versions("docs", None, None, None, None, 1000).await.unwrap();
+    assert_eq!(versions.versions.len(), 3);
+    assert_eq!(data_files(dir.path()).len(), 3);
+
+    // Buckets created after the upgrade still start out unversioned
+    engine.create_bucket("fresh", None, None).await.unwrap();
+    assert_eq!(engine.get_bucket("fresh").await.unwrap().unwrap().versioning, VersioningStatus::Unversioned);
+}